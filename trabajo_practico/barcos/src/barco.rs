@@ -1,11 +1,13 @@
-use crate::estado_barco::EstadoBarco;
+use crate::{estado_barco::EstadoBarco, orientacion::Orientacion, tipo_barco::TipoBarco};
 
 #[derive(Debug, Clone)]
 /// Estructura que representa un barco
 pub struct Barco {
     pub id: usize,
-    pub tamaño: usize,
+    pub tipo: TipoBarco,
     pub posiciones: Vec<(i32, i32)>,
+    pub orientacion: Orientacion,
+    pub dañadas: Vec<(i32, i32)>,
     pub estado: EstadoBarco,
 }
 
@@ -16,21 +18,38 @@ impl Barco {
     ///
     /// `id` - Identificador del barco
     ///
-    /// `tamaño` - Tamaño del barco
+    /// `tipo` - Clase del barco
     ///
     /// `posiciones` - Posiciones del barco
     ///
+    /// `orientacion` - Hacia dónde se extiende el barco desde su primera posición
+    ///
     /// # Returns
     ///
     /// `Barco` - Barco creado
-    pub fn new(id: usize, tamaño: usize, posiciones: Vec<(i32, i32)>) -> Barco {
+    pub fn new(
+        id: usize,
+        tipo: TipoBarco,
+        posiciones: Vec<(i32, i32)>,
+        orientacion: Orientacion,
+    ) -> Barco {
         Barco {
             id,
-            tamaño,
+            tipo,
             posiciones,
+            orientacion,
+            dañadas: Vec::new(),
             estado: EstadoBarco::Sano,
         }
     }
+    /// Función que obtiene la cantidad de casilleros que ocupa el barco
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Tamaño del barco
+    pub fn tamaño(&self) -> usize {
+        self.tipo.tamaño()
+    }
     /// Función que obtiene los datos del barco
     ///
     /// # Returns
@@ -39,17 +58,73 @@ impl Barco {
     pub fn obtener_datos(&self) -> (usize, Vec<(i32, i32)>) {
         (self.id, self.posiciones.clone())
     }
+    /// Función que obtiene la primera posición del barco, desde la que se extiende
+    ///
+    /// # Returns
+    ///
+    /// `Option<(i32, i32)>` - Primera posición, si el barco ocupa alguna
+    pub fn origen(&self) -> Option<(i32, i32)> {
+        self.posiciones.first().copied()
+    }
+    /// Función que registra un impacto en uno de los casilleros del barco
+    ///
+    /// El casillero sigue siendo parte del barco; el barco se hunde cuando
+    /// todos sus casilleros están dañados.
+    ///
+    /// # Args
+    ///
+    /// `coordenada` - Casillero atacado
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si el casillero era del barco y no estaba dañado
+    pub fn recibir_impacto(&mut self, coordenada: (i32, i32)) -> bool {
+        if !self.posiciones.contains(&coordenada) || self.dañadas.contains(&coordenada) {
+            return false;
+        }
+        self.dañadas.push(coordenada);
+        self.estado = if self.dañadas.len() >= self.posiciones.len() {
+            EstadoBarco::Hundido
+        } else {
+            EstadoBarco::Golpeado
+        };
+        true
+    }
+    /// Función que repara el casillero dañado más antiguo del barco
+    ///
+    /// Cuando no le quedan casilleros dañados, el barco vuelve a estar sano.
+    ///
+    /// # Returns
+    ///
+    /// `Option<(i32, i32)>` - Casillero reparado, `None` si el barco no tenía daños o ya se hundió
+    pub fn reparar(&mut self) -> Option<(i32, i32)> {
+        if self.dañadas.is_empty() || self.estado == EstadoBarco::Hundido {
+            return None;
+        }
+        let reparada = self.dañadas.remove(0);
+        if self.dañadas.is_empty() {
+            self.estado = EstadoBarco::Sano;
+        }
+        Some(reparada)
+    }
     /// Función que actualiza la posición del barco
     ///
     /// # Args
     ///
     /// `nueva_posicion` - Nueva posición del barco
     ///
+    /// `orientacion` - Orientación del barco en su nueva posición
+    ///
     /// # Returns
     ///
     /// `()` - No retorna nada
-    pub fn actualizar_posicion(&mut self, nueva_posicion: Vec<(i32, i32)>) {
+    pub fn actualizar_posicion(
+        &mut self,
+        nueva_posicion: Vec<(i32, i32)>,
+        orientacion: Orientacion,
+    ) {
         self.posiciones = nueva_posicion;
+        self.orientacion = orientacion;
     }
 }
 
@@ -59,23 +134,66 @@ mod tests {
 
     #[test]
     fn test_new() {
-        let barco = Barco::new(1, 2, vec![(1, 1), (1, 2)]);
+        let barco = Barco::new(
+            1,
+            TipoBarco::Buque,
+            vec![(1, 1), (1, 2)],
+            Orientacion::Vertical,
+        );
         assert_eq!(barco.id, 1);
-        assert_eq!(barco.tamaño, 2);
+        assert_eq!(barco.tipo, TipoBarco::Buque);
+        assert_eq!(barco.tamaño(), 2);
         assert_eq!(barco.posiciones, vec![(1, 1), (1, 2)]);
+        assert_eq!(barco.orientacion, Orientacion::Vertical);
+        assert_eq!(barco.origen(), Some((1, 1)));
         assert_eq!(barco.estado, EstadoBarco::Sano);
     }
 
     #[test]
     fn test_obtener_datos() {
-        let barco = Barco::new(1, 2, vec![(1, 1), (1, 2)]);
+        let barco = Barco::new(
+            1,
+            TipoBarco::Buque,
+            vec![(1, 1), (1, 2)],
+            Orientacion::Vertical,
+        );
         assert_eq!(barco.obtener_datos(), (1, vec![(1, 1), (1, 2)]));
     }
 
+    #[test]
+    fn test_impactos_y_reparacion() {
+        let mut barco = Barco::new(
+            1,
+            TipoBarco::Buque,
+            vec![(1, 1), (2, 1)],
+            Orientacion::Horizontal,
+        );
+        assert!(barco.recibir_impacto((2, 1)));
+        assert!(!barco.recibir_impacto((2, 1)));
+        assert!(!barco.recibir_impacto((3, 1)));
+        assert_eq!(barco.estado, EstadoBarco::Golpeado);
+        assert_eq!(barco.posiciones, vec![(1, 1), (2, 1)]);
+
+        assert_eq!(barco.reparar(), Some((2, 1)));
+        assert_eq!(barco.estado, EstadoBarco::Sano);
+        assert_eq!(barco.reparar(), None);
+
+        assert!(barco.recibir_impacto((1, 1)));
+        assert!(barco.recibir_impacto((2, 1)));
+        assert_eq!(barco.estado, EstadoBarco::Hundido);
+        assert_eq!(barco.reparar(), None);
+    }
+
     #[test]
     fn test_actualizar_posicion() {
-        let mut barco = Barco::new(1, 2, vec![(1, 1), (1, 2)]);
-        barco.actualizar_posicion(vec![(2, 2), (2, 3)]);
-        assert_eq!(barco.posiciones, vec![(2, 2), (2, 3)]);
+        let mut barco = Barco::new(
+            1,
+            TipoBarco::Buque,
+            vec![(1, 1), (1, 2)],
+            Orientacion::Vertical,
+        );
+        barco.actualizar_posicion(vec![(2, 2), (3, 2)], Orientacion::Horizontal);
+        assert_eq!(barco.posiciones, vec![(2, 2), (3, 2)]);
+        assert_eq!(barco.orientacion, Orientacion::Horizontal);
     }
 }
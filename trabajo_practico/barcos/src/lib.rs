@@ -1,2 +1,4 @@
 pub mod barco;
 pub mod estado_barco;
+pub mod orientacion;
+pub mod tipo_barco;
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumeración que representa hacia dónde se extiende un barco desde su origen
+pub enum Orientacion {
+    /// Hacia la derecha, a lo largo de la fila
+    Horizontal,
+    /// Hacia abajo, a lo largo de la columna
+    Vertical,
+}
+
+impl Orientacion {
+    /// Función que obtiene la orientación que resulta de girar el barco
+    ///
+    /// # Returns
+    ///
+    /// `Orientacion` - La orientación contraria
+    pub fn rotada(&self) -> Orientacion {
+        match self {
+            Orientacion::Horizontal => Orientacion::Vertical,
+            Orientacion::Vertical => Orientacion::Horizontal,
+        }
+    }
+
+    /// Función que obtiene los casilleros que ocupa un barco
+    ///
+    /// # Args
+    ///
+    /// `origen` - Coordenadas `(x, y)` del primer casillero
+    ///
+    /// `tamaño` - Cantidad de casilleros del barco
+    ///
+    /// # Returns
+    ///
+    /// `Vec<(i32, i32)>` - Casilleros desde el origen en esta orientación
+    pub fn posiciones(&self, origen: (i32, i32), tamaño: usize) -> Vec<(i32, i32)> {
+        let (x, y) = origen;
+        (0..tamaño as i32)
+            .map(|i| match self {
+                Orientacion::Horizontal => (x + i, y),
+                Orientacion::Vertical => (x, y + i),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posiciones() {
+        assert_eq!(
+            Orientacion::Horizontal.posiciones((1, 2), 3),
+            vec![(1, 2), (2, 2), (3, 2)]
+        );
+        assert_eq!(
+            Orientacion::Vertical.posiciones((1, 2), 2),
+            vec![(1, 2), (1, 3)]
+        );
+    }
+
+    #[test]
+    fn test_rotada() {
+        assert_eq!(Orientacion::Horizontal.rotada(), Orientacion::Vertical);
+        assert_eq!(Orientacion::Vertical.rotada(), Orientacion::Horizontal);
+    }
+}
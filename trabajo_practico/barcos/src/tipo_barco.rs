@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumeración que representa las clases de barco del juego
+pub enum TipoBarco {
+    Fragata,
+    Buque,
+    Acorazado,
+}
+
+impl TipoBarco {
+    /// Todas las clases de barco, de menor a mayor
+    pub const TODOS: [TipoBarco; 3] = [TipoBarco::Fragata, TipoBarco::Buque, TipoBarco::Acorazado];
+
+    /// Función que obtiene la cantidad de casilleros que ocupa el barco
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Tamaño del barco
+    pub fn tamaño(&self) -> usize {
+        match self {
+            TipoBarco::Fragata => 1,
+            TipoBarco::Buque => 2,
+            TipoBarco::Acorazado => 3,
+        }
+    }
+
+    /// Función que obtiene el precio del barco en la tienda
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Precio en monedas
+    pub fn precio(&self) -> usize {
+        match self {
+            TipoBarco::Fragata => 100,
+            TipoBarco::Buque => 200,
+            TipoBarco::Acorazado => 300,
+        }
+    }
+
+    /// Función que obtiene los impactos que resiste el barco antes de hundirse
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Puntos de vida del barco
+    pub fn puntos_vida(&self) -> usize {
+        self.tamaño()
+    }
+
+    /// Función que obtiene el nombre del barco para mostrar al jugador
+    ///
+    /// # Returns
+    ///
+    /// `&'static str` - Nombre del barco
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            TipoBarco::Fragata => "fragata",
+            TipoBarco::Buque => "buque",
+            TipoBarco::Acorazado => "acorazado",
+        }
+    }
+
+    /// Función que obtiene la clase de barco que ocupa un tamaño dado
+    ///
+    /// # Args
+    ///
+    /// `tamaño` - Cantidad de casilleros
+    ///
+    /// # Returns
+    ///
+    /// `Option<TipoBarco>` - Clase de barco, si existe alguna de ese tamaño
+    pub fn con_tamaño(tamaño: usize) -> Option<TipoBarco> {
+        TipoBarco::TODOS
+            .into_iter()
+            .find(|tipo| tipo.tamaño() == tamaño)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atributos() {
+        assert_eq!(TipoBarco::Acorazado.tamaño(), 3);
+        assert_eq!(TipoBarco::Acorazado.precio(), 300);
+        assert_eq!(TipoBarco::Acorazado.puntos_vida(), 3);
+        assert_eq!(TipoBarco::Acorazado.nombre(), "acorazado");
+    }
+
+    #[test]
+    fn test_con_tamaño() {
+        assert_eq!(TipoBarco::con_tamaño(1), Some(TipoBarco::Fragata));
+        assert_eq!(TipoBarco::con_tamaño(2), Some(TipoBarco::Buque));
+        assert_eq!(TipoBarco::con_tamaño(4), None);
+    }
+}
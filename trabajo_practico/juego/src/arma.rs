@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumeración que representa las armas especiales, de un solo uso, que se compran en la tienda
+pub enum TipoArma {
+    Bomba,
+    Torpedo,
+    Sonar,
+}
+
+impl TipoArma {
+    /// Todas las armas a la venta
+    pub const TODAS: [TipoArma; 3] = [TipoArma::Bomba, TipoArma::Torpedo, TipoArma::Sonar];
+
+    /// Función que obtiene el nombre del arma para mostrar al jugador
+    ///
+    /// # Returns
+    ///
+    /// `&'static str` - Nombre del arma
+    pub fn nombre(&self) -> &'static str {
+        match self {
+            TipoArma::Bomba => "bomba",
+            TipoArma::Torpedo => "torpedo",
+            TipoArma::Sonar => "sonar",
+        }
+    }
+
+    /// Función que obtiene la descripción del efecto del arma
+    ///
+    /// # Returns
+    ///
+    /// `&'static str` - Descripción del arma
+    pub fn descripcion(&self) -> &'static str {
+        match self {
+            TipoArma::Bomba => "ataca los 3x3 casilleros alrededor de una coordenada",
+            TipoArma::Torpedo => "avanza por una fila o columna hasta golpear un barco",
+            TipoArma::Sonar => "indica si hay barcos enemigos cerca de una coordenada",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Enumeración que representa hacia dónde avanza un torpedo
+pub enum Direccion {
+    Arriba,
+    Abajo,
+    Izquierda,
+    Derecha,
+}
+
+impl Direccion {
+    /// Función que obtiene cuánto cambian las coordenadas en cada paso
+    ///
+    /// # Returns
+    ///
+    /// `(i32, i32)` - Desplazamiento en x y en y
+    pub fn desplazamiento(&self) -> (i32, i32) {
+        match self {
+            Direccion::Arriba => (0, -1),
+            Direccion::Abajo => (0, 1),
+            Direccion::Izquierda => (-1, 0),
+            Direccion::Derecha => (1, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_armas() {
+        assert_eq!(TipoArma::TODAS.len(), 3);
+        assert_eq!(TipoArma::Torpedo.nombre(), "torpedo");
+        assert_eq!(Direccion::Izquierda.desplazamiento(), (-1, 0));
+    }
+}
@@ -0,0 +1,118 @@
+use libreria::{constantes::LONGITUD_MAXIMA_CHAT, custom_error::CustomError};
+
+use crate::mensaje::Mensaje;
+
+/// Comandos de chat que se pueden escribir en cualquier momento de la partida
+pub const AYUDA_CHAT: &str =
+    "Chat: /todos <texto>, /privado <nombre> <texto>, /silenciar <nombre>, /escuchar <nombre>";
+
+/// Comandos para prepararse mientras la partida no comenzó
+pub const AYUDA_PREPARACION: &str =
+    "Sala: /listo para marcarte listo, /esperar para dejar de estarlo, /comenzar si sos el anfitrión";
+
+/// Función que valida el texto de un mensaje de chat
+///
+/// # Args
+///
+/// `texto` - Texto escrito por el jugador
+///
+/// # Returns
+///
+/// `Result<String, CustomError>` - Texto sin espacios al principio ni al final
+///
+/// # Errors
+///
+/// `CustomError::ErrorChatInvalido` - Si el texto está vacío o supera el largo permitido
+pub fn validar_texto(texto: &str) -> Result<String, CustomError> {
+    let texto = texto.trim();
+    if texto.is_empty() || texto.chars().count() > LONGITUD_MAXIMA_CHAT {
+        return Err(CustomError::ErrorChatInvalido);
+    }
+    Ok(texto.to_string())
+}
+
+/// Función que interpreta una línea escrita por el jugador como comando de chat o de preparación
+///
+/// # Args
+///
+/// `linea` - Línea leída de la entrada estándar
+///
+/// # Returns
+///
+/// `Option<Result<Mensaje, CustomError>>` - Mensaje para el servidor, o `None` si la línea no es un comando
+///
+/// # Errors
+///
+/// `CustomError::AccionInvalida` - Si el comando no existe o le faltan argumentos
+///
+/// `CustomError::ErrorChatInvalido` - Si el texto está vacío o supera el largo permitido
+pub fn interpretar_comando(linea: &str) -> Option<Result<Mensaje, CustomError>> {
+    let comando = linea.trim().strip_prefix('/')?;
+    let (nombre, resto) = comando.split_once(' ').unwrap_or((comando, ""));
+    let resto = resto.trim();
+    let mensaje = match nombre {
+        "todos" => validar_texto(resto).map(|texto| Mensaje::Chat { para: None, texto }),
+        "privado" => match resto.split_once(' ') {
+            Some((destinatario, texto)) => validar_texto(texto).map(|texto| Mensaje::Chat {
+                para: Some(destinatario.to_string()),
+                texto,
+            }),
+            None => Err(CustomError::AccionInvalida),
+        },
+        "silenciar" if !resto.is_empty() => Ok(Mensaje::Silenciar(resto.to_string(), true)),
+        "escuchar" if !resto.is_empty() => Ok(Mensaje::Silenciar(resto.to_string(), false)),
+        "listo" => Ok(Mensaje::RespuestaComienzo(true)),
+        "esperar" => Ok(Mensaje::RespuestaComienzo(false)),
+        "comenzar" => Ok(Mensaje::ForzarComienzo),
+        _ => Err(CustomError::AccionInvalida),
+    };
+    Some(mensaje)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpretar_comandos_de_chat() {
+        assert!(interpretar_comando("a").is_none());
+        assert!(matches!(
+            interpretar_comando(" /todos  hola a todos "),
+            Some(Ok(Mensaje::Chat { para: None, texto })) if texto == "hola a todos"
+        ));
+        assert!(matches!(
+            interpretar_comando("/privado beto nos vemos"),
+            Some(Ok(Mensaje::Chat { para: Some(para), texto })) if para == "beto" && texto == "nos vemos"
+        ));
+        assert!(matches!(
+            interpretar_comando("/silenciar beto"),
+            Some(Ok(Mensaje::Silenciar(nombre, true))) if nombre == "beto"
+        ));
+        assert!(matches!(
+            interpretar_comando("/privado beto"),
+            Some(Err(CustomError::AccionInvalida))
+        ));
+        assert!(matches!(
+            interpretar_comando("/listo"),
+            Some(Ok(Mensaje::RespuestaComienzo(true)))
+        ));
+        assert!(matches!(
+            interpretar_comando("/comenzar"),
+            Some(Ok(Mensaje::ForzarComienzo))
+        ));
+        assert!(matches!(
+            interpretar_comando("/gritar"),
+            Some(Err(CustomError::AccionInvalida))
+        ));
+    }
+
+    #[test]
+    fn test_validar_largo_del_texto() {
+        assert!(validar_texto("   ").is_err());
+        assert!(validar_texto(&"ñ".repeat(LONGITUD_MAXIMA_CHAT)).is_ok());
+        assert!(matches!(
+            validar_texto(&"a".repeat(LONGITUD_MAXIMA_CHAT + 1)),
+            Err(CustomError::ErrorChatInvalido)
+        ));
+    }
+}
@@ -1,22 +1,56 @@
 use libreria::{
-    constantes::{ATAQ, MOV},
+    constantes::{ATAQ, GIRO, MOV, REP, TORP},
     custom_error::CustomError,
 };
-use serde_json;
 use std::{
-    io::{self, Read, Write},
+    io::{self, BufReader},
     net::TcpStream,
-    sync::{Arc, Mutex},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
 
+use crate::arma::{Direccion, TipoArma};
+use crate::chat::AYUDA_PREPARACION;
+use crate::codec::Codec;
+use crate::conexion::{Conexion, ConexionTcp};
+use crate::consola::Consola;
+use crate::evento::EventoPublico;
 use crate::instruccion::Instruccion;
+use crate::lobby::{EstadoPreparacion, ResumenSala};
 use crate::mensaje::Mensaje;
+use crate::protocolo::{Protocolo, ARMAS};
+use crate::tienda::{ArticuloCatalogo, Producto};
+use crate::vista::VistaTablero;
+use barcos::orientacion::Orientacion;
+
+/// Cantidad de veces que el cliente intenta volver a conectarse al servidor
+const INTENTOS_RECONEXION: u32 = 5;
+/// Tiempo entre intentos de reconexión
+const ESPERA_RECONEXION: Duration = Duration::from_secs(2);
+
 /// Struct que representa un cliente
+///
+/// Los mensajes del servidor se leen en otro hilo, que contesta los `Ping`
+/// aunque el jugador esté escribiendo su acción.
 pub struct Cliente {
-    shared_stream: Arc<Mutex<TcpStream>>,
+    conexion: Arc<dyn Conexion>,
+    mensajes: Receiver<Mensaje>,
+    direccion: String,
+    token: Option<String>,
+    codec: Codec,
+    protocolo: Protocolo,
     _id: usize,
     nombre: String,
     _puntos: u32,
+    barcos: Vec<(usize, Vec<(i32, i32)>)>,
+    tablero: Vec<Vec<char>>,
+    vista: Option<VistaTablero>,
+    en_cola: bool,
+    consola: Consola,
 }
 
 impl Cliente {
@@ -34,17 +68,55 @@ impl Cliente {
     ///
     /// `Result<Self, CustomError>` - Resultado de la creación del cliente
     pub fn new(addr: String, _id: usize, nombre: String) -> Result<Self, CustomError> {
-        let stream = TcpStream::connect(addr).map_err(|_| CustomError::ErrorCreatingSocket)?;
-        let shared_stream: Arc<Mutex<TcpStream>> = Arc::new(Mutex::new(stream));
+        let stream = TcpStream::connect(&addr).map_err(|_| CustomError::ErrorCreatingSocket)?;
+        let conexion: Arc<dyn Conexion> = Arc::new(ConexionTcp::new(stream)?);
+        let consola = Consola::lanzar(BufReader::new(io::stdin()), Arc::clone(&conexion));
         Ok(Cliente {
-            shared_stream,
+            mensajes: Self::escuchar(Arc::clone(&conexion)),
+            conexion,
+            direccion: addr,
+            token: None,
+            codec: Codec::Binario,
+            protocolo: Protocolo::sin_saludo(),
             _id,
             nombre,
             _puntos: 0,
+            barcos: Vec::new(),
+            tablero: Vec::new(),
+            vista: None,
+            en_cola: false,
+            consola,
         })
     }
+    /// Función que indica el token de una sesión anterior para volver a su partida
+    ///
+    /// # Args
+    ///
+    /// `token` - Token de la sesión
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn usar_token(&mut self, token: String) {
+        self.token = Some(token);
+    }
+    /// Función que indica el codec que el cliente propone al servidor
+    ///
+    /// # Args
+    ///
+    /// `codec` - Codec preferido
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn usar_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
     /// Función que ejecuta el cliente
     ///
+    /// Si se pierde la conexión y el cliente tiene una sesión, intenta volver
+    /// a conectarse para retomar la partida.
+    ///
     /// # Returns
     ///
     /// `Result<(), CustomError>` - Resultado de la ejecución del cliente
@@ -53,180 +125,450 @@ impl Cliente {
     ///
     /// Retorna un error si no se puede recibir el mensaje del servidor
     pub fn run(&mut self) -> Result<(), CustomError> {
+        Self::saludar(&self.conexion, self.codec)?;
         loop {
-            match self.recibir_mensaje() {
-                Ok(mensaje_serializado) => {
-                    if mensaje_serializado.is_empty() {
-                        println!("Mensaje vacío recibido");
-                        continue;
+            let mensaje = match self.recibir_mensaje() {
+                Ok(mensaje) => mensaje,
+                Err(_) => {
+                    self.reconectar()?;
+                    continue;
+                }
+            };
+            match mensaje {
+                Mensaje::Bienvenida {
+                    version,
+                    capacidades,
+                } => {
+                    println!("Conectado al servidor (protocolo versión {})", version);
+                    self.protocolo = Protocolo {
+                        version,
+                        capacidades,
+                    };
+                }
+                Mensaje::VersionRechazada(motivo) => {
+                    println!("{}", motivo);
+                    return Err(CustomError::ErrorVersionIncompatible);
+                }
+                Mensaje::Registro => match self.token.clone() {
+                    Some(token) => {
+                        println!("Volviendo a la partida...");
+                        self.enviar_respuesta(&Mensaje::Reconectar(token))?;
                     }
-                    match serde_json::from_str::<Mensaje>(&mensaje_serializado) {
-                        Ok(mensaje) => match mensaje {
-                            Mensaje::Registro => {
-                                println!("Ingrese su nombre de usuario: ");
-                                let mut respuesta = String::new();
-                                io::stdin()
-                                    .read_line(&mut respuesta)
-                                    .expect("Error al leer la respuesta.");
-                                self.enviar_respuesta(respuesta.trim())?;
-                            }
-                            Mensaje::NombreEnUso => {
-                                println!("El nombre de usuario ya está en uso. Por favor, ingrese otro nombre: ");
-                                let mut respuesta = String::new();
-                                io::stdin()
-                                    .read_line(&mut respuesta)
-                                    .expect("Error al leer la respuesta.");
-                                self.enviar_respuesta(respuesta.trim())?;
-                            }
-                            Mensaje::PreguntaComienzo => {
-                                println!("¿Ya hay jugadores suficientes.Deseas comenzar el juego? (si/no)");
-                                let mut respuesta = String::new();
-                                io::stdin()
-                                    .read_line(&mut respuesta)
-                                    .expect("Error al leer la respuesta.");
-                                self.enviar_respuesta(respuesta.trim())?;
-                            }
-                            Mensaje::RealiceAccion => {
-                                Self::imprimir_acciones();
-                            }
-                            Mensaje::Esperando => {
-                                println!("Esperando mas jugadores para comenzar el juego...");
-                            }
-                            Mensaje::Puntos(puntos) => {
-                                println!("Puntos: {}", puntos);
-                                println!("Juego en curso...Espera tu turno")
-                            }
-                            Mensaje::Tablero(tablero, barcos, monedas) => {
-                                for row in tablero {
-                                    for cell in row {
-                                        print!("{}", cell);
-                                    }
-                                    println!();
-                                }
-
-                                match Self::pedir_instrucciones(barcos, monedas) {
-                                    Ok((accion, nuevas_monedas)) => {
-                                        let mensaje_serializado = serde_json::to_string(
-                                            &Mensaje::Accion(accion, nuevas_monedas),
-                                        )
-                                        .unwrap();
-                                        self.enviar_respuesta(mensaje_serializado.as_str())?;
-                                    }
-                                    Err(err) => {
-                                        return Err(err);
-                                    }
-                                }
-                            }
-                            Mensaje::ComenzoJuego => {
-                                println!("El juego ha comenzado");
-                            }
-                            Mensaje::RepetirAccion(mensaje, barcos, monedas) => {
-                                println!("{}", mensaje);
-                                match Self::pedir_instrucciones(barcos, monedas) {
-                                    Ok((accion, nuevas_monedas)) => {
-                                        let mensaje_serializado = serde_json::to_string(
-                                            &Mensaje::Accion(accion, nuevas_monedas),
-                                        )
-                                        .unwrap();
-                                        self.enviar_respuesta(mensaje_serializado.as_str())?;
-                                    }
-                                    Err(err) => {
-                                        return Err(err);
-                                    }
-                                }
-                            }
+                    None => {
+                        println!("Ingrese su nombre de usuario: ");
+                        self.enviar_respuesta(&Mensaje::NombreUsuario(self.leer_linea()))?;
+                    }
+                },
+                Mensaje::Sesion(token) => {
+                    println!(
+                        "Tu token de sesión es {}. Podés usarlo con --token para volver a la partida.",
+                        token
+                    );
+                    self.token = Some(token);
+                }
+                Mensaje::SesionRechazada(motivo) => {
+                    println!("{}", motivo);
+                    self.token = None;
+                    println!("Ingrese su nombre de usuario: ");
+                    self.enviar_respuesta(&Mensaje::NombreUsuario(self.leer_linea()))?;
+                }
+                Mensaje::Reanudar(tablero, barcos, monedas) => {
+                    println!("Volviste a la partida");
+                    self.tablero = tablero.clone();
+                    Self::imprimir_tablero(tablero);
+                    println!("Monedas: {}", monedas);
+                    self.barcos = barcos;
+                    self.vista = None;
+                    println!("Juego en curso...Espera tu turno");
+                }
+                Mensaje::NotificacionDesconexion(nombre) => {
+                    println!("El jugador {} perdió la conexión", nombre);
+                }
+                Mensaje::NotificacionReconexion(nombre) => {
+                    println!("El jugador {} volvió a la partida", nombre);
+                }
+                Mensaje::TurnoAgotado => {
+                    println!("Se agotó el tiempo de tu turno, se salteó tu jugada");
+                }
+                Mensaje::NombreEnUso => {
+                    println!(
+                        "El nombre de usuario ya está en uso. Por favor, ingrese otro nombre: "
+                    );
+                    let respuesta = self.leer_linea();
+                    self.enviar_respuesta(&Mensaje::NombreUsuario(respuesta.trim().to_string()))?;
+                }
+                Mensaje::Lobby => {
+                    let eleccion = self.elegir_en_lobby();
+                    self.enviar_respuesta(&eleccion)?;
+                }
+                Mensaje::Salas(salas) => {
+                    Self::mostrar_salas(&salas);
+                }
+                Mensaje::SalaUnida(codigo) => {
+                    println!("Te uniste a la sala {}", codigo);
+                }
+                Mensaje::SalaRechazada(motivo) => {
+                    println!("{}", motivo);
+                }
+                Mensaje::Observando(codigo, administrador) => {
+                    println!("Estás mirando la sala {}", codigo);
+                    if administrador {
+                        println!("Como administrador, vas a ver todos los barcos");
+                    }
+                }
+                Mensaje::TableroEspectador(tablero) => {
+                    Self::imprimir_tablero(tablero);
+                }
+                Mensaje::Transmision(evento) => {
+                    let termino = matches!(evento, EventoPublico::Ganador { .. });
+                    Self::mostrar_evento_publico(&evento);
+                    if termino {
+                        break;
+                    }
+                }
+                Mensaje::PreguntaComienzo => {
+                    println!("¿Ya hay jugadores suficientes.Deseas comenzar el juego? (si/no)");
+                    let respuesta = self.leer_linea();
+                    self.enviar_respuesta(&Mensaje::RespuestaComienzo(respuesta.trim() == "si"))?;
+                }
+                Mensaje::RealiceAccion => {
+                    self.imprimir_acciones();
+                }
+                Mensaje::Esperando => {
+                    println!("Esperando mas jugadores para comenzar el juego...");
+                }
+                Mensaje::EstadoSala(estado) => {
+                    Self::mostrar_preparacion(&estado);
+                }
+                Mensaje::ComienzoRechazado(motivo) => {
+                    println!("{}", motivo);
+                }
+                Mensaje::EnCola(esperando, por_partida) => {
+                    self.en_cola = true;
+                    println!(
+                        "Buscando partida: {} de {} jugadores en espera",
+                        esperando, por_partida
+                    );
+                }
+                Mensaje::Puntos(puntos) => {
+                    println!("Puntos: {}", puntos);
+                    println!("Juego en curso...Espera tu turno")
+                }
+                Mensaje::Tablero(tablero, barcos, monedas) => {
+                    self.tablero = tablero.clone();
+                    Self::imprimir_tablero(tablero);
+                    println!("Monedas: {}", monedas);
+                    self.barcos = barcos;
+                    self.enviar_accion()?;
+                }
+                Mensaje::Instantanea(secuencia, tablero, barcos, monedas) => {
+                    self.vista = Some(VistaTablero::new(
+                        secuencia,
+                        tablero.clone(),
+                        barcos.clone(),
+                    ));
+                    self.tablero = tablero.clone();
+                    Self::imprimir_tablero(tablero);
+                    println!("Monedas: {}", monedas);
+                    self.barcos = barcos;
+                    self.enviar_accion()?;
+                }
+                Mensaje::Delta(secuencia, cambios, monedas) => {
+                    let aplicado = self
+                        .vista
+                        .as_mut()
+                        .map(|vista| vista.aplicar(secuencia, &cambios));
+                    match (aplicado, &self.vista) {
+                        (Some(Ok(())), Some(vista)) => {
+                            self.tablero = vista.tablero.clone();
+                            Self::imprimir_tablero(vista.tablero.clone());
+                            println!("Monedas: {}", monedas);
+                            self.barcos = vista.barcos.clone();
+                            self.enviar_accion()?;
+                        }
+                        _ => {
+                            println!(
+                                "Se perdieron cambios del tablero, pidiendo el tablero completo..."
+                            );
+                            self.enviar_respuesta(&Mensaje::PedirResincronizacion)?;
+                        }
+                    }
+                }
+                Mensaje::ComenzoJuego => {
+                    println!("El juego ha comenzado");
+                }
+                Mensaje::Despliegue(tablero, barcos, segundos) => {
+                    self.tablero = tablero.clone();
+                    Self::imprimir_tablero(tablero);
+                    println!(
+                        "Tenés {} segundos para ubicar tu flota. Escribí 'auto' para dejarla como está",
+                        segundos
+                    );
+                    self.barcos = barcos;
+                    let despliegue = self.pedir_despliegue();
+                    self.enviar_respuesta(&despliegue)?;
+                }
+                Mensaje::DespliegueRechazado(motivo) => {
+                    println!("{}", motivo);
+                    let despliegue = self.pedir_despliegue();
+                    self.enviar_respuesta(&despliegue)?;
+                }
+                Mensaje::FlotaDesplegada(barcos) => {
+                    println!("Tu flota quedó ubicada:");
+                    for (id, posiciones) in &barcos {
+                        println!("ID: {}, Posicion: {:?}", id, posiciones);
+                    }
+                    self.barcos = barcos;
+                    println!("Juego en curso...Espera tu turno");
+                }
+                Mensaje::RepetirAccion(mensaje, barcos, _) => {
+                    println!("{}", mensaje);
+                    self.barcos = barcos;
+                    self.enviar_accion()?;
+                }
+                Mensaje::AbrirTienda(catalogo, monedas) => {
+                    let catalogo: Vec<ArticuloCatalogo> =
+                        catalogo.into_iter().map(ArticuloCatalogo::from).collect();
+                    match self.abrir_tienda(&catalogo, &self.barcos, monedas) {
+                        Some(compra) => self.enviar_respuesta(&Mensaje::Accion(compra))?,
+                        None => self.enviar_accion()?,
+                    }
+                }
+                Mensaje::Catalogo(catalogo, monedas) => {
+                    match self.abrir_tienda(&catalogo, &self.barcos, monedas) {
+                        Some(compra) => self.enviar_respuesta(&Mensaje::Accion(compra))?,
+                        None => self.enviar_accion()?,
+                    }
+                }
+                Mensaje::CompraRechazada(motivo, monedas) => {
+                    println!("{} Monedas disponibles: {}", motivo, monedas);
+                    self.enviar_accion()?;
+                }
 
-                            Mensaje::EventoSorpresa => {
-                                std::thread::sleep(std::time::Duration::from_secs(1));
-                                println!("¡Un cargamento con recursos apareció de repente! Sé el primero en reclamarlo ingresando: primero");
+                Mensaje::EventoSorpresa => {
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                    println!("¡Un cargamento con recursos apareció de repente! Sé el primero en reclamarlo ingresando: primero");
 
-                                let mut respuesta = String::new();
-                                io::stdin()
-                                    .read_line(&mut respuesta)
-                                    .expect("Error al leer la respuesta.");
+                    let respuesta = self.leer_linea();
 
-                                // Trim de la respuesta para eliminar espacios adicionales
-                                let respuesta_trim = respuesta.trim();
+                    // Trim de la respuesta para eliminar espacios adicionales
+                    let respuesta_trim = respuesta.trim();
 
-                                // Enviar la respuesta al servidor
-                                if let Err(e) = self.enviar_respuesta(respuesta_trim) {
-                                    eprintln!("Error al enviar la respuesta: {}", e);
-                                }
-                            }
+                    // Enviar la respuesta al servidor
+                    if let Err(e) = self.enviar_respuesta(&Mensaje::RespuestaEventoSorpresa(
+                        respuesta_trim.to_string(),
+                    )) {
+                        eprintln!("Error al enviar la respuesta: {}", e);
+                    }
+                }
 
-                            Mensaje::MensajeInfoAtaque(puntos, monedas) => {
-                                if puntos == 0 {
-                                    println!(
-                                        "Has fallado el ataque, no has ganado puntos ni monedas"
-                                    );
-                                } else {
-                                    println!("Has golpeado a un barco enemigo, has ganado {} puntos y {} monedas", puntos, monedas);
-                                }
-                            }
-                            Mensaje::BarcoHundido => {
-                                println!("Han golpeado un barco tuyo y se ha hundido");
-                            }
-                            Mensaje::BarcoGolpead(coordenadas) => {
-                                println!(
-                                    "Han golpeado un barco tuyo en las coordenadas {:?}",
-                                    coordenadas
-                                );
-                            }
-                            Mensaje::EventoSorpresaResultado(resultado) => {
-                                if resultado {
-                                    println!("Felicidades, fuiste el primero en reclamar el premio, ahora es tuyo");
-                                } else {
-                                    println!(
-                                        "Una lastima, alguien se te adelanto, perdiste el premio"
-                                    );
-                                }
-                            }
-                            Mensaje::Ranking(ranking) => {
-                                Self::mostrar_ranking(ranking)?;
-                            }
-                            Mensaje::Perdiste(puntos) => {
-                                println!("Has perdido con {} puntos", puntos);
-                                break;
-                            }
-                            Mensaje::NotificacionEliminacion(nombre) => {
-                                println!("El jugador {} ha sido eliminado", nombre);
-                            }
-                            Mensaje::CompraExitosa(tipo_barco, _) => match tipo_barco {
-                                1 => {
-                                    println!("Has comprado una fragata");
-                                }
-                                2 => {
-                                    println!("Has comprado un buque");
-                                }
-                                3 => {
-                                    println!("Has comprado un acorazado");
-                                }
-                                _ => {}
-                            },
+                Mensaje::MensajeInfoAtaque(puntos, monedas) => {
+                    if puntos == 0 {
+                        println!("Has fallado el ataque, no has ganado puntos ni monedas");
+                    } else {
+                        println!(
+                            "Has golpeado a un barco enemigo, has ganado {} puntos y {} monedas",
+                            puntos, monedas
+                        );
+                    }
+                }
+                Mensaje::ResultadoBomba(impactos, puntos, monedas) => {
+                    if impactos.is_empty() {
+                        println!("La bomba no alcanzó ningún barco enemigo");
+                    } else {
+                        println!(
+                            "La bomba golpeó barcos enemigos en {:?}, has ganado {} puntos y {} monedas",
+                            impactos, puntos, monedas
+                        );
+                    }
+                }
+                Mensaje::ResultadoTorpedo(impacto, puntos, monedas) => match impacto {
+                    Some(coordenadas) => println!(
+                        "El torpedo golpeó un barco enemigo en {:?}, has ganado {} puntos y {} monedas",
+                        coordenadas, puntos, monedas
+                    ),
+                    None => println!("El torpedo salió del tablero sin golpear ningún barco"),
+                },
+                Mensaje::ResultadoSonar(centro, detectado) => {
+                    if detectado {
+                        println!("El sonar detectó barcos enemigos cerca de {:?}", centro);
+                    } else {
+                        println!("El sonar no detectó barcos enemigos cerca de {:?}", centro);
+                    }
+                }
+                Mensaje::BarcoHundido => {
+                    println!("Han golpeado un barco tuyo y se ha hundido");
+                }
+                Mensaje::BarcoGolpeado(coordenadas) => {
+                    println!(
+                        "Han golpeado un barco tuyo en las coordenadas {:?}",
+                        coordenadas
+                    );
+                }
+                Mensaje::EventoSorpresaResultado(resultado) => {
+                    if resultado {
+                        println!(
+                            "Felicidades, fuiste el primero en reclamar el premio, ahora es tuyo"
+                        );
+                    } else {
+                        println!("Una lastima, alguien se te adelanto, perdiste el premio");
+                    }
+                }
+                Mensaje::Ranking(ranking) => {
+                    Self::mostrar_ranking(ranking)?;
+                }
+                Mensaje::Perdiste(puntos) => {
+                    println!("Has perdido con {} puntos", puntos);
+                    if !self.volver_a_la_cola() {
+                        break;
+                    }
+                }
+                Mensaje::NotificacionEliminacion(nombre) => {
+                    println!("El jugador {} ha sido eliminado", nombre);
+                }
+                Mensaje::CompraExitosa(tipo, monedas) => {
+                    println!("Has comprado: {}", tipo.nombre());
+                    println!("Monedas restantes: {}", monedas);
+                }
+                Mensaje::ArmaComprada(arma, cantidad, monedas) => {
+                    println!("Has comprado: {} (tenés {})", arma.nombre(), cantidad);
+                    println!("Monedas restantes: {}", monedas);
+                }
+                Mensaje::ReparacionExitosa(coordenadas, monedas) => {
+                    println!("Reparaste el casillero {:?}", coordenadas);
+                    println!("Monedas restantes: {}", monedas);
+                }
 
-                            Mensaje::NotificacionCompra(mensaje, monedas) => {
-                                println!("{} Monedas restantes: {}", mensaje, monedas);
-                            }
-                            Mensaje::Ganaste(puntos) => {
-                                println!("Has ganado con {} puntos", puntos);
-                                break;
-                            }
-                            _ => {
-                                Err(CustomError::ErrorRecibiendoMensaje)?;
-                            }
-                        },
-                        Err(_) => {
-                            return Err(CustomError::ErrorDeserealizandoMensaje);
-                        }
+                Mensaje::NotificacionCompra(mensaje, monedas) => {
+                    println!("{} Monedas restantes: {}", mensaje, monedas);
+                }
+                Mensaje::Ganaste(puntos) => {
+                    println!("Has ganado con {} puntos", puntos);
+                    if !self.volver_a_la_cola() {
+                        break;
                     }
                 }
-                Err(_) => {
-                    return Err(CustomError::ErrorRecibiendoMensaje);
+                _ => {
+                    Err(CustomError::ErrorRecibiendoMensaje)?;
                 }
             }
         }
         Ok(())
     }
+    /// Función que vuelve a conectarse al servidor después de perder la conexión
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si se abrió una nueva conexión
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoMensaje` - Si el cliente no tiene sesión o no logró conectarse
+    fn reconectar(&mut self) -> Result<(), CustomError> {
+        if self.token.is_none() {
+            return Err(CustomError::ErrorRecibiendoMensaje);
+        }
+        println!("Se perdió la conexión con el servidor. Reconectando...");
+        for _ in 0..INTENTOS_RECONEXION {
+            thread::sleep(ESPERA_RECONEXION);
+            if let Ok(stream) = TcpStream::connect(&self.direccion) {
+                self.conexion = Arc::new(ConexionTcp::new(stream)?);
+                self.consola.usar_conexion(Arc::clone(&self.conexion));
+                Self::saludar(&self.conexion, self.codec)?;
+                self.mensajes = Self::escuchar(Arc::clone(&self.conexion));
+                return Ok(());
+            }
+        }
+        Err(CustomError::ErrorRecibiendoMensaje)
+    }
+    /// Función que presenta al cliente con la versión y las capacidades de su protocolo
+    ///
+    /// # Args
+    ///
+    /// `conexion` - Conexión con el servidor
+    ///
+    /// `codec` - Codec que el cliente propone
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado del envío
+    fn saludar(conexion: &Arc<dyn Conexion>, codec: Codec) -> Result<(), CustomError> {
+        let protocolo = Protocolo::con_codec(codec);
+        conexion.enviar(&Mensaje::Hola {
+            version: protocolo.version,
+            capacidades: protocolo.capacidades,
+        })
+    }
+    /// Función que lanza el hilo que lee los mensajes del servidor
+    ///
+    /// Los `Ping` se contestan y los mensajes de chat se muestran en el mismo
+    /// hilo, para no esperar a que el jugador termine de responder; el resto
+    /// de los mensajes se pasan al cliente. Al llegar la `Bienvenida` se cambia al codec
+    /// acordado antes de leer la siguiente trama. El canal se cierra cuando
+    /// se corta la conexión.
+    ///
+    /// # Args
+    ///
+    /// `conexion` - Conexión con el servidor
+    ///
+    /// # Returns
+    ///
+    /// `Receiver<Mensaje>` - Canal por el que llegan los mensajes del servidor
+    fn escuchar(conexion: Arc<dyn Conexion>) -> Receiver<Mensaje> {
+        let (emisor, receptor) = mpsc::channel();
+        thread::spawn(move || loop {
+            match conexion.recibir() {
+                Ok(Mensaje::Ping) => {
+                    if conexion.enviar(&Mensaje::Pong).is_err() {
+                        return;
+                    }
+                }
+                Ok(Mensaje::ChatRecibido { de, texto, privado }) => {
+                    if privado {
+                        println!("[privado] {}: {}", de, texto);
+                    } else {
+                        println!("{}: {}", de, texto);
+                    }
+                }
+                Ok(Mensaje::ChatRechazado(motivo)) => println!("{}", motivo),
+                Ok(mensaje) => {
+                    if let Mensaje::Bienvenida {
+                        version,
+                        capacidades,
+                    } = &mensaje
+                    {
+                        let acordado = Protocolo {
+                            version: *version,
+                            capacidades: capacidades.clone(),
+                        };
+                        conexion.usar_codec(acordado.codec());
+                    }
+                    if emisor.send(mensaje).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            }
+        });
+        receptor
+    }
+    /// Función que imprime el tablero recibido del servidor
+    ///
+    /// # Args
+    ///
+    /// `tablero` - Tablero a imprimir
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn imprimir_tablero(tablero: Vec<Vec<char>>) {
+        for row in tablero {
+            for cell in row {
+                print!("{}", cell);
+            }
+            println!();
+        }
+    }
     /// Función que envía una respuesta al servidor
     ///
     /// # Args
@@ -240,15 +582,53 @@ impl Cliente {
     /// # Errors
     ///
     /// Retorna un error si no se puede enviar la respuesta
-    pub fn enviar_respuesta(&mut self, respuesta: &str) -> Result<(), CustomError> {
-        let mut stream = self.shared_stream.lock().unwrap();
-        stream
-            .write_all(respuesta.as_bytes())
-            .map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        stream
-            .flush()
-            .map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        Ok(())
+    pub fn enviar_respuesta(&mut self, respuesta: &Mensaje) -> Result<(), CustomError> {
+        self.conexion.enviar(respuesta)
+    }
+    /// Función que pide una acción al jugador y la envía al servidor
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado del envío de la acción
+    ///
+    /// # Errors
+    ///
+    /// Retorna un error si no se puede enviar la acción
+    fn enviar_accion(&mut self) -> Result<(), CustomError> {
+        self.mostrar_barcos();
+        let accion = self.pedir_instrucciones(self.barcos.clone())?;
+        self.enviar_respuesta(&Mensaje::Accion(accion))
+    }
+    /// Función que muestra los barcos del jugador y sus casilleros dañados
+    ///
+    /// Un casillero está dañado si el tablero muestra una 'X' en una de las
+    /// posiciones del barco.
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn mostrar_barcos(&self) {
+        println!("Tus barcos:");
+        for (id, posiciones) in &self.barcos {
+            let dañadas: Vec<(i32, i32)> = posiciones
+                .iter()
+                .filter(|(x, y)| {
+                    self.tablero
+                        .get(*y as usize)
+                        .and_then(|fila| fila.get(*x as usize))
+                        == Some(&'X')
+                })
+                .copied()
+                .collect();
+            if dañadas.is_empty() {
+                println!("ID: {}, Posicion: {:?}, sin daños", id, posiciones);
+            } else {
+                println!(
+                    "ID: {}, Posicion: {:?}, dañado en {:?}",
+                    id, posiciones, dañadas
+                );
+            }
+        }
     }
     /// Función que muestra el ranking de los jugadores
     ///
@@ -271,92 +651,348 @@ impl Cliente {
         Ok(())
     }
 
-    /// Funcion que permite al jugador abrir la tienda y comprar barcos
-    /// # Args
-    /// `coordenadas_ataque` - Coordenadas del ataque realizado por el jugador
-    ///
-    /// `mapa` - Mapa en el que se encuentra el jugador
+    /// Función que permite al jugador elegir qué hacer en el lobby
     ///
     /// # Returns
     ///
-    /// `usize` - Puntos ganados por el jugador
-    fn abrir_tienda(monedas: usize) -> Result<(Instruccion, usize), CustomError> {
-        println!("Opciones para comprar: ");
-        println!("(a) Acorazado de 3 casilleros: $300");
-        println!("(b) Buque de 2 casilleros: $200");
-        println!("(c) Fragata de 1 casillero: $100");
-        println!("Usted cuenta con ${}", monedas);
-
-        let mut compra = String::new();
-        io::stdin()
-            .read_line(&mut compra)
-            .expect("Error al leer la respuesta.");
-
-        let mut exitosa = true;
-        let tipo_barco;
-        let monedas_gastadas;
-
-        match compra.trim() {
-            "a" => {
-                if monedas < 300 {
-                    exitosa = false;
-                    monedas_gastadas = 0;
-                    tipo_barco = 0;
-                } else {
-                    monedas_gastadas = 300;
-                    tipo_barco = 3;
+    /// `Mensaje` - Pedido a enviar al servidor
+    fn elegir_en_lobby(&self) -> Mensaje {
+        loop {
+            println!("Puede listar las salas: (l)");
+            println!("Puede crear una sala: (c)");
+            println!("Puede unirse a una sala: (u)");
+            println!("Puede mirar la partida de una sala: (o)");
+            println!("Puede buscar una partida con otros jugadores: (b)");
+            match self.leer_linea().as_str() {
+                "l" => return Mensaje::ListarSalas,
+                "b" => return Mensaje::BuscarPartida,
+                "c" => {
+                    println!("Ingrese el nombre de la sala: ");
+                    let nombre = self.leer_linea();
+                    return Mensaje::CrearSala(nombre, self.pedir_contraseña());
                 }
-            }
-            "b" => {
-                if monedas < 200 {
-                    exitosa = false;
-                    monedas_gastadas = 0;
-                    tipo_barco = 0;
-                } else {
-                    monedas_gastadas = 200;
-                    tipo_barco = 2;
+                "u" => {
+                    println!("Ingrese el código de la sala: ");
+                    let codigo = self.leer_linea();
+                    return Mensaje::UnirseSala(codigo, self.pedir_contraseña());
+                }
+                "o" => {
+                    println!("Ingrese el código de la sala: ");
+                    let codigo = self.leer_linea();
+                    let contraseña = self.pedir_contraseña();
+                    println!("Ingrese la clave de administrador (vacío si no tiene): ");
+                    let clave_administrador =
+                        Some(self.leer_linea()).filter(|clave| !clave.is_empty());
+                    return Mensaje::Observar {
+                        codigo,
+                        contraseña,
+                        clave_administrador,
+                    };
+                }
+                _ => {
+                    println!("Opción invalida. Por favor, elige una opción valida (l, c, u, o, b).")
                 }
             }
-            "c" => {
-                if monedas < 100 {
-                    exitosa = false;
-                    monedas_gastadas = 0;
-                    tipo_barco = 0;
-                } else {
-                    monedas_gastadas = 100;
-                    tipo_barco = 1;
+        }
+    }
+    /// Función que prepara al cliente para la próxima partida de la cola de emparejamiento
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si el jugador buscó partida en la cola, por lo que el servidor lo devuelve a ella
+    fn volver_a_la_cola(&mut self) -> bool {
+        if self.en_cola {
+            println!("Volviendo a la cola para jugar otra partida...");
+            self.barcos = Vec::new();
+            self.vista = None;
+        }
+        self.en_cola
+    }
+    /// Función que pide al jugador dónde ubicar cada uno de sus barcos
+    ///
+    /// Los barcos para los que no se indica un origen quedan donde están.
+    ///
+    /// # Returns
+    ///
+    /// `Mensaje` - Ubicaciones elegidas, o `AutoDespliegue` si el jugador no cambió nada
+    fn pedir_despliegue(&self) -> Mensaje {
+        let mut ubicaciones = Vec::new();
+        for (id, posiciones) in &self.barcos {
+            let origen = loop {
+                println!(
+                    "Barco {} en {:?}. Ingresa el origen en formato 'x,y' (vacío para dejarlo): ",
+                    id, posiciones
+                );
+                let respuesta = self.leer_linea();
+                if respuesta == "auto" {
+                    return Mensaje::AutoDespliegue;
+                }
+                if respuesta.is_empty() {
+                    break None;
+                }
+                let mut iter = respuesta.split(',');
+                if let (Some(Ok(x)), Some(Ok(y))) = (
+                    iter.next().map(|x| x.trim().parse::<i32>()),
+                    iter.next().map(|y| y.trim().parse::<i32>()),
+                ) {
+                    break Some((x, y));
                 }
+                println!("Error: Coordenadas incorrectas. Por favor, intenta de nuevo.");
+            };
+            let Some(origen) = origen else {
+                continue;
+            };
+            ubicaciones.push((*id, origen, self.pedir_orientacion()));
+        }
+        if ubicaciones.is_empty() {
+            Mensaje::AutoDespliegue
+        } else {
+            Mensaje::DesplegarFlota(ubicaciones)
+        }
+    }
+    /// Función que muestra quiénes están listos en la sala antes de comenzar
+    ///
+    /// # Args
+    ///
+    /// `estado` - Estado de preparación de la sala
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn mostrar_preparacion(estado: &EstadoPreparacion) {
+        println!("Jugadores en la sala:");
+        for (nombre, listo) in &estado.jugadores {
+            let anfitrion = if *nombre == estado.anfitrion {
+                " (anfitrión)"
+            } else {
+                ""
+            };
+            let listo = if *listo { "listo" } else { "esperando" };
+            println!("  {}{}: {}", nombre, anfitrion, listo);
+        }
+        match estado.segundos_para_comenzar {
+            Some(segundos) => println!("La partida comienza en {} segundos", segundos),
+            None if estado.jugadores.len() < estado.minimo_jugadores => println!(
+                "Se necesitan al menos {} jugadores para comenzar",
+                estado.minimo_jugadores
+            ),
+            None => println!("{}", AYUDA_PREPARACION),
+        }
+    }
+    /// Función que muestra a un espectador lo que pasó en la partida
+    ///
+    /// # Args
+    ///
+    /// `evento` - Evento público recibido
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn mostrar_evento_publico(evento: &EventoPublico) {
+        match evento {
+            EventoPublico::Ataque {
+                atacante,
+                coordenadas,
+                puntos,
+            } => println!(
+                "{} atacó las coordenadas {:?} y ganó {} puntos",
+                atacante, coordenadas, puntos
+            ),
+            EventoPublico::Bombardeo {
+                atacante,
+                centro,
+                puntos,
+            } => println!(
+                "{} lanzó una bomba sobre {:?} y ganó {} puntos",
+                atacante, centro, puntos
+            ),
+            EventoPublico::Torpedo {
+                atacante,
+                impacto: Some(coordenadas),
+                puntos,
+            } => println!(
+                "El torpedo de {} golpeó en {:?} y ganó {} puntos",
+                atacante, coordenadas, puntos
+            ),
+            EventoPublico::Torpedo {
+                atacante,
+                impacto: None,
+                ..
+            } => println!("El torpedo de {} no golpeó ningún barco", atacante),
+            EventoPublico::BarcoGolpeado {
+                jugador,
+                coordenadas,
+            } => println!(
+                "Golpearon un barco de {} en las coordenadas {:?}",
+                jugador, coordenadas
+            ),
+            EventoPublico::BarcoHundido {
+                jugador,
+                coordenadas,
+            } => println!(
+                "Hundieron un barco de {} en las coordenadas {:?}",
+                jugador, coordenadas
+            ),
+            EventoPublico::JugadorEliminado { jugador } => {
+                println!("El jugador {} ha sido eliminado", jugador)
             }
-            _ => {
-                return Err(CustomError::ErrorCompraBarco);
+            EventoPublico::Compra { jugador, tipo } => {
+                println!("{} compró un {}", jugador, tipo.nombre())
+            }
+            EventoPublico::Reparacion {
+                jugador,
+                coordenadas,
+            } => println!(
+                "{} reparó su barco en las coordenadas {:?}",
+                jugador, coordenadas
+            ),
+            EventoPublico::EventoSorpresa => {
+                println!("¡Apareció un cargamento sorpresa!")
+            }
+            EventoPublico::PremioObtenido { jugador, monedas } => println!(
+                "{} reclamó el cargamento y ganó {} monedas",
+                jugador, monedas
+            ),
+            EventoPublico::Ganador { jugador, puntos } => {
+                println!("El ganador es {} con {} puntos", jugador, puntos)
             }
         }
+    }
+    /// Función que pide la contraseña de una sala
+    ///
+    /// # Returns
+    ///
+    /// `Option<String>` - Contraseña ingresada, `None` si se dejó vacía
+    fn pedir_contraseña(&self) -> Option<String> {
+        println!("Ingrese la contraseña de la sala (vacío si no tiene): ");
+        Some(self.leer_linea()).filter(|contraseña| !contraseña.is_empty())
+    }
+    /// Función que lee una línea de la entrada estándar
+    ///
+    /// Los comandos de chat no se devuelven; los envía `consola` al servidor.
+    ///
+    /// # Returns
+    ///
+    /// `String` - Línea leída, sin espacios al principio ni al final
+    fn leer_linea(&self) -> String {
+        self.consola.leer_linea()
+    }
+    /// Función que muestra las salas abiertas en el servidor
+    ///
+    /// # Args
+    ///
+    /// `salas` - Salas abiertas
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn mostrar_salas(salas: &[ResumenSala]) {
+        if salas.is_empty() {
+            println!("No hay salas abiertas");
+            return;
+        }
+        println!("Salas:");
+        for sala in salas {
+            let estado = if sala.en_curso {
+                "en curso"
+            } else {
+                "esperando"
+            };
+            let acceso = if sala.privada { "privada" } else { "pública" };
+            println!(
+                "{:<6} {:<20} {}/{} {:<10} {}",
+                sala.codigo, sala.nombre, sala.jugadores, sala.maximo_jugadores, estado, acceso
+            );
+        }
+    }
 
-        if !exitosa {
-            println!("No cuenta con el dinero suficiente para comprar ese barco");
-            return Err(CustomError::ErrorCompraBarco);
+    /// Funcion que permite al jugador elegir un artículo del catálogo de la tienda
+    ///
+    /// # Args
+    ///
+    /// `catalogo` - Artículos a la venta enviados por el servidor
+    ///
+    /// `barcos` - Barcos del jugador, para elegir cuál reparar
+    ///
+    /// `monedas` - Monedas del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Option<Instruccion>` - Compra elegida, o `None` si el jugador vuelve al menú
+    fn abrir_tienda(
+        &self,
+        catalogo: &[ArticuloCatalogo],
+        barcos: &[(usize, Vec<(i32, i32)>)],
+        monedas: usize,
+    ) -> Option<Instruccion> {
+        println!("Opciones para comprar: ");
+        for (indice, articulo) in catalogo.iter().enumerate() {
+            match articulo.producto {
+                Producto::Barco(tipo) => println!(
+                    "({}) {} de {} casilleros: ${}",
+                    indice,
+                    tipo.nombre(),
+                    tipo.tamaño(),
+                    articulo.precio
+                ),
+                Producto::Reparacion => println!(
+                    "({}) Reparación de un casillero dañado: ${}",
+                    indice, articulo.precio
+                ),
+                Producto::Arma(arma) => println!(
+                    "({}) {}, {}: ${}",
+                    indice,
+                    arma.nombre(),
+                    arma.descripcion(),
+                    articulo.precio
+                ),
+            }
         }
+        println!("(v) Volver");
+        println!("Usted cuenta con ${}", monedas);
 
-        Ok((Instruccion::Compra(tipo_barco), monedas_gastadas))
+        loop {
+            let compra = self.leer_linea();
+
+            if compra.trim() == "v" {
+                return None;
+            }
+            match compra
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|indice| catalogo.get(indice))
+            {
+                Some(articulo) => match articulo.producto {
+                    Producto::Barco(tipo) => {
+                        return Some(Instruccion::CompraOrientada(tipo, self.pedir_orientacion()))
+                    }
+                    Producto::Reparacion if barcos.is_empty() => {
+                        println!("No tenés barcos para reparar.")
+                    }
+                    Producto::Reparacion => {
+                        return Some(Instruccion::Reparacion(self.elegir_barco(barcos, REP)))
+                    }
+                    Producto::Arma(arma) => return Some(Instruccion::CompraArma(arma)),
+                },
+                _ => println!("Opción invalida. Por favor, elige un artículo de la lista."),
+            }
+        }
     }
 
     /// Función que recibe un mensaje del servidor
     ///
     /// # Returns
     ///
-    /// `Result<String, CustomError>` - Resultado del mensaje recibido
+    /// `Result<Mensaje, CustomError>` - Resultado del mensaje recibido
     ///
     /// # Errors
     ///
     /// Retorna un error si no se puede recibir el mensaje
-    pub fn recibir_mensaje(&mut self) -> Result<String, CustomError> {
-        let mut buffer = [0; 2048];
-        let mut stream = self.shared_stream.lock().unwrap();
-        let bytes_read = stream
-            .read(&mut buffer)
-            .map_err(|_| CustomError::ErrorRecibiendoInstruccion)?;
-        let message = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
-        Ok(message)
+    pub fn recibir_mensaje(&mut self) -> Result<Mensaje, CustomError> {
+        self.mensajes
+            .recv()
+            .map_err(|_| CustomError::ErrorRecibiendoMensaje)
     }
 
     /// Función que cambia el nombre del jugador
@@ -381,13 +1017,19 @@ impl Cliente {
     }
     /// Función que imprime las acciones que puede realizar el jugador
     ///
+    /// Las armas especiales solo se ofrecen si el servidor las admite.
+    ///
     /// # Returns
     ///
     /// `()` - No retorna nada
-    fn imprimir_acciones() {
+    fn imprimir_acciones(&self) {
         println!("Realice una accion: ");
         println!("Puede moverse: (m)");
+        println!("Puede girar un barco: (g)");
         println!("Puede atacar: (a)");
+        if self.protocolo.admite(ARMAS) {
+            println!("Puede usar un arma especial: (e)");
+        }
         println!("Puede abrir la tienda: (t)");
         println!("Puede saltar turno: (s)");
         println!("Puede ver el ranking: (r)")
@@ -398,29 +1040,28 @@ impl Cliente {
     ///
     /// `barcos` - Barcos del jugador
     ///
-    /// `monedas` - Monedas del jugador
-    ///
     /// # Returns
     ///
     /// `Instruccion` - Instrucción del jugador
     fn pedir_instrucciones(
+        &self,
         barcos: Vec<(usize, Vec<(i32, i32)>)>,
-        monedas: usize,
-    ) -> Result<(Instruccion, usize), CustomError> {
-        let mut accion = String::new();
-        io::stdin()
-            .read_line(&mut accion)
-            .expect("Error al leer la entrada");
+    ) -> Result<Instruccion, CustomError> {
+        let accion = self.leer_linea();
 
         match accion.trim() {
-            "m" => Self::moverse(barcos),
-            "a" => Self::atacar(barcos),
-            "t" => Self::abrir_tienda(monedas),
+            "m" => self.moverse(barcos),
+            "g" => self.girar(barcos),
+            "a" => self.atacar(barcos),
+            "e" if self.protocolo.admite(ARMAS) => self.usar_arma(barcos),
+            "t" => Ok(Instruccion::Tienda),
             "s" => Self::saltar(),
             "r" => Self::ranking(),
             _ => {
-                println!("Error en la accion. Por favor, elige una accion valida (m, a, t, s).");
-                Self::pedir_instrucciones(barcos, monedas)
+                println!(
+                    "Error en la accion. Por favor, elige una accion valida (m, g, a, e, t, s, r)."
+                );
+                self.pedir_instrucciones(barcos)
             }
         }
     }
@@ -437,9 +1078,68 @@ impl Cliente {
     /// # Errors
     ///
     /// Retorna un error si no se puede obtener el barco
-    fn moverse(barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<(Instruccion, usize), CustomError> {
-        let (id, posicion) = Self::obtener_barco(barcos, MOV).unwrap();
-        Ok((Instruccion::Movimiento(id, posicion), 0))
+    fn moverse(&self, barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<Instruccion, CustomError> {
+        let (id, posicion) = self.obtener_barco(barcos, MOV).unwrap();
+        Ok(Instruccion::MovimientoOrientado(
+            id,
+            posicion,
+            self.pedir_orientacion(),
+        ))
+    }
+    /// Función que permite al jugador girar un barco sobre su primer casillero
+    ///
+    /// # Args
+    ///
+    /// `barcos` - Barcos del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<Instruccion, CustomError>` - Resultado de la instrucción
+    fn girar(&self, barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<Instruccion, CustomError> {
+        Ok(Instruccion::Rotacion(self.elegir_barco(&barcos, GIRO)))
+    }
+    /// Función que permite al jugador usar una de las armas especiales compradas en la tienda
+    ///
+    /// # Args
+    ///
+    /// `barcos` - Barcos del jugador, para elegir desde cuál se lanza el torpedo
+    ///
+    /// # Returns
+    ///
+    /// `Result<Instruccion, CustomError>` - Resultado de la instrucción
+    fn usar_arma(&self, barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<Instruccion, CustomError> {
+        for (tecla, arma) in ["b", "t", "s"].iter().zip(TipoArma::TODAS) {
+            println!("({}) {}: {}", tecla, arma.nombre(), arma.descripcion());
+        }
+        loop {
+            match self.leer_linea().as_str() {
+                "b" => return Ok(Instruccion::Bomba(self.pedir_coordenadas()?)),
+                "t" if barcos.is_empty() => println!("No tenés barcos desde donde lanzarlo."),
+                "t" => {
+                    let barco = self.elegir_barco(&barcos, TORP);
+                    return Ok(Instruccion::Torpedo(barco, self.pedir_direccion()));
+                }
+                "s" => return Ok(Instruccion::Sonar(self.pedir_coordenadas()?)),
+                _ => println!("Opción invalida. Por favor, elige b, t o s."),
+            }
+        }
+    }
+    /// Función que pide al jugador hacia dónde avanza el torpedo
+    ///
+    /// # Returns
+    ///
+    /// `Direccion` - Dirección elegida
+    fn pedir_direccion(&self) -> Direccion {
+        loop {
+            println!("Dirección: (w) arriba, (s) abajo, (a) izquierda o (d) derecha");
+            match self.leer_linea().as_str() {
+                "w" => return Direccion::Arriba,
+                "s" => return Direccion::Abajo,
+                "a" => return Direccion::Izquierda,
+                "d" => return Direccion::Derecha,
+                _ => println!("Dirección invalida."),
+            }
+        }
     }
     /// Función que permite al jugador atacar
     ///
@@ -454,17 +1154,17 @@ impl Cliente {
     /// # Errors
     ///
     /// Retorna un error si no se puede obtener el barco
-    fn atacar(barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<(Instruccion, usize), CustomError> {
-        let (id, posicion) = Self::obtener_barco(barcos, ATAQ).unwrap();
+    fn atacar(&self, barcos: Vec<(usize, Vec<(i32, i32)>)>) -> Result<Instruccion, CustomError> {
+        let (id, posicion) = self.obtener_barco(barcos, ATAQ).unwrap();
 
-        Ok((Instruccion::Ataque(id, posicion), 0))
+        Ok(Instruccion::Ataque(id, posicion))
     }
 
-    fn saltar() -> Result<(Instruccion, usize), CustomError> {
-        Ok((Instruccion::Saltar, 0))
+    fn saltar() -> Result<Instruccion, CustomError> {
+        Ok(Instruccion::Saltar)
     }
-    fn ranking() -> Result<(Instruccion, usize), CustomError> {
-        Ok((Instruccion::Ranking, 0))
+    fn ranking() -> Result<Instruccion, CustomError> {
+        Ok(Instruccion::Ranking)
     }
     /// Función que permite al jugador obtener un barco
     ///
@@ -482,36 +1182,61 @@ impl Cliente {
     ///
     /// Retorna un error si no se puede obtener el barco
     fn obtener_barco(
+        &self,
         barcos: Vec<(usize, Vec<(i32, i32)>)>,
         accion: &str,
     ) -> Result<(usize, (i32, i32)), CustomError> {
+        let barco_seleccionado = self.elegir_barco(&barcos, accion);
+        let cordenadas = self.pedir_coordenadas().unwrap();
+        Ok((barco_seleccionado, cordenadas))
+    }
+    /// Función que pide al jugador que elija uno de sus barcos
+    ///
+    /// # Args
+    ///
+    /// `barcos` - Barcos del jugador
+    ///
+    /// `accion` - Acción a realizar
+    ///
+    /// # Returns
+    ///
+    /// `usize` - ID del barco elegido
+    fn elegir_barco(&self, barcos: &[(usize, Vec<(i32, i32)>)], accion: &str) -> usize {
         println!("Elige un barco para {}:", accion);
         for (i, (id, posicion)) in barcos.iter().enumerate() {
             println!("{}: ID: {}, Posicion: {:?}", i, id, posicion);
         }
 
-        let mut barco_seleccionado = String::new();
-        io::stdout().flush().expect("Error");
-        io::stdin()
-            .read_line(&mut barco_seleccionado)
-            .expect("Error");
+        let barco_seleccionado = self.leer_linea();
         let barco_seleccionado: usize = match barco_seleccionado.trim().parse() {
             Ok(numero) => numero,
             Err(_) => {
                 println!(
                     "Numero de barco invalido. Por favor, ingrese un numero dentro del rango."
                 );
-                return Self::obtener_barco(barcos, accion);
+                return self.elegir_barco(barcos, accion);
             }
         };
 
-        if barco_seleccionado >= barcos.len() {
-            println!("Numero de barco invalido. Por favor, elige un numero dentro del rango.");
-            return Self::obtener_barco(barcos, accion);
+        match barcos.get(barco_seleccionado) {
+            Some((id, _)) => *id,
+            None => {
+                println!("Numero de barco invalido. Por favor, elige un numero dentro del rango.");
+                self.elegir_barco(barcos, accion)
+            }
+        }
+    }
+    /// Función que pide al jugador hacia dónde se extiende el barco
+    ///
+    /// # Returns
+    ///
+    /// `Orientacion` - Orientación elegida, horizontal si no se indica otra
+    fn pedir_orientacion(&self) -> Orientacion {
+        println!("Orientación: (h) horizontal o (v) vertical");
+        match self.leer_linea().as_str() {
+            "v" => Orientacion::Vertical,
+            _ => Orientacion::Horizontal,
         }
-
-        let cordenadas = Self::pedir_coordenadas().unwrap();
-        Ok((barco_seleccionado, cordenadas))
     }
     /// Función que pide las coordenadas al usuario
     ///
@@ -522,14 +1247,11 @@ impl Cliente {
     /// # Errors
     ///
     /// Retorna un error si las coordenadas ingresadas no son válidas
-    fn pedir_coordenadas() -> Result<(i32, i32), CustomError> {
+    fn pedir_coordenadas(&self) -> Result<(i32, i32), CustomError> {
         loop {
             println!("Ingresa las coordenadas en formato 'x,y': ");
 
-            let mut coordenadas = String::new();
-            io::stdin()
-                .read_line(&mut coordenadas)
-                .expect("Error al leer la entrada");
+            let coordenadas = self.leer_linea();
 
             let mut iter = coordenadas.trim().split(',');
             if let (Some(x_str), Some(y_str)) = (iter.next(), iter.next()) {
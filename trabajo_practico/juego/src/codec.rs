@@ -0,0 +1,393 @@
+use std::str::FromStr;
+
+use libreria::custom_error::CustomError;
+use serde::{Deserialize, Serialize};
+
+use crate::mensaje::Mensaje;
+
+/// Formato con el que se codifican los mensajes dentro de cada trama
+///
+/// JSON se puede leer a simple vista y sirve para depurar; el binario es
+/// más compacto. El saludo siempre viaja en JSON y el binario solo se usa
+/// si ambas partes lo acordaron.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    #[default]
+    Json,
+    Binario,
+}
+
+impl Codec {
+    /// Función que codifica un mensaje
+    ///
+    /// # Args
+    ///
+    /// `mensaje` - Mensaje a codificar
+    ///
+    /// # Returns
+    ///
+    /// `Result<Vec<u8>, CustomError>` - Contenido de la trama
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSerializacion` - Si no se pudo codificar el mensaje
+    pub fn codificar(&self, mensaje: &Mensaje) -> Result<Vec<u8>, CustomError> {
+        match self {
+            Codec::Json => serde_json::to_vec(mensaje).map_err(|_| CustomError::ErrorSerializacion),
+            Codec::Binario => {
+                bincode::serialize(mensaje).map_err(|_| CustomError::ErrorSerializacion)
+            }
+        }
+    }
+
+    /// Función que decodifica el contenido de una trama
+    ///
+    /// # Args
+    ///
+    /// `datos` - Contenido de la trama
+    ///
+    /// # Returns
+    ///
+    /// `Result<Mensaje, CustomError>` - Mensaje decodificado
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorDeserealizandoMensaje` - Si el contenido no es un mensaje válido
+    pub fn decodificar(&self, datos: &[u8]) -> Result<Mensaje, CustomError> {
+        match self {
+            Codec::Json => {
+                serde_json::from_slice(datos).map_err(|_| CustomError::ErrorDeserealizandoMensaje)
+            }
+            Codec::Binario => {
+                bincode::deserialize(datos).map_err(|_| CustomError::ErrorDeserealizandoMensaje)
+            }
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = CustomError;
+
+    fn from_str(nombre: &str) -> Result<Self, Self::Err> {
+        match nombre {
+            "json" => Ok(Codec::Json),
+            "binario" => Ok(Codec::Binario),
+            _ => Err(CustomError::ErrorConfiguracion),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arma::{Direccion, TipoArma},
+        evento::EventoPublico,
+        instruccion::Instruccion,
+        lobby::{EstadoPreparacion, ResumenSala},
+        tienda::{ArticuloCatalogo, ArticuloTienda, Producto},
+        vista::CambioTablero,
+    };
+    use barcos::{orientacion::Orientacion, tipo_barco::TipoBarco};
+
+    fn instrucciones() -> Vec<Instruccion> {
+        vec![
+            Instruccion::Ataque(1, (2, 3)),
+            Instruccion::Movimiento(0, (-1, 4)),
+            Instruccion::Tienda,
+            Instruccion::Compra(TipoBarco::Buque),
+            Instruccion::Rotacion(1),
+            Instruccion::MovimientoOrientado(0, (-1, 4), Orientacion::Vertical),
+            Instruccion::CompraOrientada(TipoBarco::Buque, Orientacion::Horizontal),
+            Instruccion::Reparacion(0),
+            Instruccion::CompraArma(TipoArma::Torpedo),
+            Instruccion::Bomba((4, 4)),
+            Instruccion::Torpedo(1, Direccion::Izquierda),
+            Instruccion::Sonar((7, 2)),
+            Instruccion::Saltar,
+            Instruccion::Ranking,
+        ]
+    }
+
+    fn mensajes() -> Vec<Mensaje> {
+        let tablero = vec![vec!['.', '1'], vec!['X', 'ñ']];
+        let barcos = vec![(0, vec![(1, 1), (1, 2)])];
+        let mut mensajes = vec![
+            Mensaje::PreguntaComienzo,
+            Mensaje::RealiceAccion,
+            Mensaje::Esperando,
+            Mensaje::ComenzoJuego,
+            Mensaje::NombreEnUso,
+            Mensaje::Lobby,
+            Mensaje::ListarSalas,
+            Mensaje::Salas(vec![ResumenSala {
+                codigo: "ABCD".to_string(),
+                nombre: "sala".to_string(),
+                jugadores: 2,
+                maximo_jugadores: 4,
+                privada: true,
+                en_curso: false,
+            }]),
+            Mensaje::CrearSala("sala".to_string(), Some("clave".to_string())),
+            Mensaje::UnirseSala("ABCD".to_string(), None),
+            Mensaje::SalaUnida("ABCD".to_string()),
+            Mensaje::SalaRechazada("llena".to_string()),
+            Mensaje::Puntos(10),
+            Mensaje::Perdiste(3),
+            Mensaje::Ganaste(40),
+            Mensaje::NotificacionEliminacion("ana".to_string()),
+            Mensaje::Tablero(tablero.clone(), barcos.clone(), 500),
+            Mensaje::Instantanea(3, tablero.clone(), barcos.clone(), 500),
+            Mensaje::Delta(
+                4,
+                vec![
+                    CambioTablero::Celda(0, 1, 'X'),
+                    CambioTablero::BarcoMovido(0, vec![(2, 2)]),
+                    CambioTablero::BarcoHundido(1),
+                ],
+                450,
+            ),
+            Mensaje::PedirResincronizacion,
+            Mensaje::AbrirTienda(
+                vec![ArticuloTienda {
+                    tipo: TipoBarco::Fragata,
+                    precio: 300,
+                }],
+                500,
+            ),
+            Mensaje::RepetirAccion("otra vez".to_string(), barcos.clone(), 500),
+            Mensaje::BarcoGolpeado((4, 5)),
+            Mensaje::BarcoHundido,
+            Mensaje::MensajeInfoAtaque(10, 20),
+            Mensaje::EventoSorpresa,
+            Mensaje::EventoSorpresaResultado(true),
+            Mensaje::Hola {
+                version: 2,
+                capacidades: vec!["latido".to_string()],
+            },
+            Mensaje::Bienvenida {
+                version: 2,
+                capacidades: Vec::new(),
+            },
+            Mensaje::VersionRechazada("vieja".to_string()),
+            Mensaje::Registro,
+            Mensaje::NombreUsuario("ana".to_string()),
+            Mensaje::Sesion("0123abcd".to_string()),
+            Mensaje::Reconectar("0123abcd".to_string()),
+            Mensaje::SesionRechazada("vencida".to_string()),
+            Mensaje::Reanudar(tablero.clone(), barcos.clone(), 100),
+            Mensaje::NotificacionDesconexion("beto".to_string()),
+            Mensaje::NotificacionReconexion("beto".to_string()),
+            Mensaje::Ping,
+            Mensaje::Pong,
+            Mensaje::TurnoAgotado,
+            Mensaje::RespuestaComienzo(false),
+            Mensaje::RespuestaEventoSorpresa("primero".to_string()),
+            Mensaje::Ranking(vec![("ana".to_string(), 30)]),
+            Mensaje::CompraExitosa(TipoBarco::Acorazado, 200),
+            Mensaje::CompraRechazada("sin monedas".to_string(), 0),
+            Mensaje::NotificacionCompra("ana compró".to_string(), 200),
+            Mensaje::FinPartida("ana".to_string(), 40),
+            Mensaje::Observar {
+                codigo: "ABCD".to_string(),
+                contraseña: None,
+                clave_administrador: Some("faro".to_string()),
+            },
+            Mensaje::Observando("ABCD".to_string(), true),
+            Mensaje::Transmision(EventoPublico::Ataque {
+                atacante: "ana".to_string(),
+                coordenadas: (1, 2),
+                puntos: 5,
+            }),
+            Mensaje::Transmision(EventoPublico::Compra {
+                jugador: "beto".to_string(),
+                tipo: TipoBarco::Fragata,
+            }),
+            Mensaje::Transmision(EventoPublico::Reparacion {
+                jugador: "ana".to_string(),
+                coordenadas: (3, 1),
+            }),
+            Mensaje::Transmision(EventoPublico::Bombardeo {
+                atacante: "ana".to_string(),
+                centro: (4, 4),
+                puntos: 10,
+            }),
+            Mensaje::Transmision(EventoPublico::Torpedo {
+                atacante: "beto".to_string(),
+                impacto: Some((0, 6)),
+                puntos: 5,
+            }),
+            Mensaje::Transmision(EventoPublico::EventoSorpresa),
+            Mensaje::TableroEspectador(vec![vec!['0', 'X']]),
+            Mensaje::Chat {
+                para: Some("beto".to_string()),
+                texto: "¿atacamos a ana?".to_string(),
+            },
+            Mensaje::ChatRecibido {
+                de: "carla".to_string(),
+                texto: "hola".to_string(),
+                privado: false,
+            },
+            Mensaje::ChatRechazado("largo".to_string()),
+            Mensaje::Silenciar("beto".to_string(), true),
+            Mensaje::EstadoSala(EstadoPreparacion {
+                jugadores: vec![("ana".to_string(), true), ("beto".to_string(), false)],
+                anfitrion: "ana".to_string(),
+                minimo_jugadores: 2,
+                segundos_para_comenzar: Some(3),
+            }),
+            Mensaje::ForzarComienzo,
+            Mensaje::ComienzoRechazado("faltan jugadores".to_string()),
+            Mensaje::BuscarPartida,
+            Mensaje::EnCola(1, 2),
+            Mensaje::Despliegue(tablero.clone(), barcos.clone(), 30),
+            Mensaje::DesplegarFlota(vec![(0, (1, 2), Orientacion::Vertical)]),
+            Mensaje::AutoDespliegue,
+            Mensaje::DespliegueRechazado("superpuesto".to_string()),
+            Mensaje::FlotaDesplegada(barcos.clone()),
+            Mensaje::ReparacionExitosa((3, 1), 140),
+            Mensaje::Catalogo(
+                vec![
+                    ArticuloCatalogo {
+                        producto: Producto::Barco(TipoBarco::Fragata),
+                        precio: 300,
+                    },
+                    ArticuloCatalogo {
+                        producto: Producto::Reparacion,
+                        precio: 60,
+                    },
+                    ArticuloCatalogo {
+                        producto: Producto::Arma(TipoArma::Sonar),
+                        precio: 80,
+                    },
+                ],
+                500,
+            ),
+            Mensaje::ArmaComprada(TipoArma::Bomba, 2, 250),
+            Mensaje::ResultadoBomba(vec![(3, 3), (4, 3)], 10, 100),
+            Mensaje::ResultadoTorpedo(None, 0, 0),
+            Mensaje::ResultadoSonar((7, 2), true),
+        ];
+        mensajes.extend(instrucciones().into_iter().map(Mensaje::Accion));
+        mensajes
+    }
+
+    #[test]
+    fn test_ida_y_vuelta_de_todos_los_mensajes() {
+        for codec in [Codec::Json, Codec::Binario] {
+            for mensaje in mensajes() {
+                let datos = codec.codificar(&mensaje).unwrap();
+                assert_eq!(codec.decodificar(&datos).unwrap(), mensaje, "{:?}", codec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ida_y_vuelta_de_todas_las_instrucciones() {
+        for instruccion in instrucciones() {
+            let json = serde_json::to_vec(&instruccion).unwrap();
+            let binario = bincode::serialize(&instruccion).unwrap();
+            assert_eq!(
+                serde_json::from_slice::<Instruccion>(&json).unwrap(),
+                instruccion
+            );
+            assert_eq!(
+                bincode::deserialize::<Instruccion>(&binario).unwrap(),
+                instruccion
+            );
+        }
+    }
+
+    fn etiqueta(mensaje: &Mensaje) -> u32 {
+        let datos = Codec::Binario.codificar(mensaje).unwrap();
+        u32::from_le_bytes(datos[..4].try_into().unwrap())
+    }
+
+    fn etiqueta_binaria<T: Serialize>(valor: &T) -> u32 {
+        let datos = bincode::serialize(valor).unwrap();
+        u32::from_le_bytes(datos[..4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_binario_conserva_las_etiquetas_anteriores() {
+        // Las variantes nuevas se agregan al final para no cambiar la
+        // etiqueta con la que viajan las que ya conocen otros clientes
+        assert_eq!(etiqueta(&Mensaje::Tablero(Vec::new(), Vec::new(), 0)), 16);
+        assert_eq!(etiqueta(&Mensaje::Accion(Instruccion::Saltar)), 17);
+        assert_eq!(etiqueta(&Mensaje::Ping), 36);
+        assert_eq!(etiqueta(&Mensaje::NotificacionCompra(String::new(), 0)), 44);
+        assert_eq!(etiqueta(&Mensaje::FinPartida(String::new(), 0)), 45);
+        assert!(etiqueta(&Mensaje::PedirResincronizacion) > etiqueta(&Mensaje::Ping));
+        assert!(
+            etiqueta(&Mensaje::ReparacionExitosa((0, 0), 0))
+                > etiqueta(&Mensaje::FlotaDesplegada(Vec::new()))
+        );
+        assert_eq!(etiqueta(&Mensaje::ReparacionExitosa((0, 0), 0)), 67);
+        assert_eq!(etiqueta(&Mensaje::Catalogo(Vec::new(), 0)), 68);
+        assert_eq!(etiqueta(&Mensaje::ArmaComprada(TipoArma::Bomba, 0, 0)), 69);
+        assert_eq!(etiqueta(&Mensaje::ResultadoBomba(Vec::new(), 0, 0)), 70);
+        assert_eq!(etiqueta(&Mensaje::ResultadoTorpedo(None, 0, 0)), 71);
+        assert_eq!(etiqueta(&Mensaje::ResultadoSonar((0, 0), false)), 72);
+        assert_eq!(
+            bincode::serialize(&Instruccion::Saltar).unwrap(),
+            4u32.to_le_bytes()
+        );
+        assert_eq!(etiqueta_binaria(&Instruccion::Reparacion(0)), 9);
+        assert_eq!(
+            etiqueta_binaria(&Instruccion::CompraArma(TipoArma::Bomba)),
+            10
+        );
+        assert_eq!(etiqueta_binaria(&Instruccion::Bomba((0, 0))), 11);
+        assert_eq!(
+            etiqueta_binaria(&Instruccion::Torpedo(0, Direccion::Arriba)),
+            12
+        );
+        assert_eq!(etiqueta_binaria(&Instruccion::Sonar((0, 0))), 13);
+        assert_eq!(
+            etiqueta_binaria(&EventoPublico::Reparacion {
+                jugador: String::new(),
+                coordenadas: (0, 0),
+            }),
+            8
+        );
+        assert_eq!(
+            etiqueta_binaria(&EventoPublico::Bombardeo {
+                atacante: String::new(),
+                centro: (0, 0),
+                puntos: 0,
+            }),
+            9
+        );
+        assert_eq!(
+            etiqueta_binaria(&EventoPublico::Torpedo {
+                atacante: String::new(),
+                impacto: None,
+                puntos: 0,
+            }),
+            10
+        );
+        assert_eq!(
+            serde_json::from_str::<Instruccion>(r#"{"Movimiento":[0,[1,2]]}"#).unwrap(),
+            Instruccion::Movimiento(0, (1, 2))
+        );
+    }
+
+    #[test]
+    fn test_binario_es_mas_compacto() {
+        let tablero = Mensaje::Tablero(vec![vec!['.'; 20]; 20], Vec::new(), 500);
+        assert!(
+            Codec::Binario.codificar(&tablero).unwrap().len()
+                < Codec::Json.codificar(&tablero).unwrap().len()
+        );
+        assert!(Codec::Binario.decodificar(b"{}").is_err());
+        assert!(Codec::Json.decodificar(b"{").is_err());
+    }
+
+    #[test]
+    fn test_codec_desde_nombre() {
+        assert_eq!("json".parse::<Codec>().unwrap(), Codec::Json);
+        assert_eq!("binario".parse::<Codec>().unwrap(), Codec::Binario);
+        assert!("xml".parse::<Codec>().is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    io::ErrorKind,
+    net::TcpStream,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use libreria::custom_error::CustomError;
+
+use crate::{
+    codec::Codec,
+    mensaje::Mensaje,
+    trama::{escribir_trama, leer_trama},
+};
+
+/// Trait que representa el canal por el cual se intercambian mensajes con un jugador
+pub trait Conexion: Send + Sync {
+    /// Función que envía un mensaje por la conexión
+    ///
+    /// # Args
+    ///
+    /// `mensaje` - Mensaje a enviar
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado del envío
+    fn enviar(&self, mensaje: &Mensaje) -> Result<(), CustomError>;
+
+    /// Función que recibe el siguiente mensaje de la conexión
+    ///
+    /// Bloquea hasta que llegue un mensaje completo.
+    ///
+    /// # Returns
+    ///
+    /// `Result<Mensaje, CustomError>` - Mensaje recibido
+    fn recibir(&self) -> Result<Mensaje, CustomError>;
+
+    /// Función que espera el siguiente mensaje de la conexión durante un tiempo limitado
+    ///
+    /// # Args
+    ///
+    /// `plazo` - Tiempo máximo de espera
+    ///
+    /// # Returns
+    ///
+    /// `Result<Option<Mensaje>, CustomError>` - Mensaje recibido, o `None` si venció el plazo
+    fn recibir_con_plazo(&self, plazo: Duration) -> Result<Option<Mensaje>, CustomError>;
+
+    /// Función que cambia el codec con el que se intercambian los mensajes
+    ///
+    /// Las conexiones que no serializan los mensajes lo ignoran.
+    ///
+    /// # Args
+    ///
+    /// `codec` - Codec acordado con el servidor
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn usar_codec(&self, _codec: Codec) {}
+}
+
+/// Plazo mínimo de espera, ya que los sockets no aceptan un plazo nulo
+const PLAZO_MINIMO: Duration = Duration::from_millis(1);
+
+/// Conexión sobre un socket TCP que serializa los mensajes en tramas
+///
+/// Lectura y escritura usan handles separados del mismo socket, por lo que
+/// se puede enviar un mensaje mientras otro hilo espera una respuesta. Los
+/// mensajes viajan en JSON hasta que se acuerda otro codec.
+pub struct ConexionTcp {
+    lector: Mutex<TcpStream>,
+    escritor: Mutex<TcpStream>,
+    codec: Mutex<Codec>,
+}
+
+impl ConexionTcp {
+    /// Función que crea una conexión a partir de un socket
+    ///
+    /// # Args
+    ///
+    /// `stream` - Socket conectado
+    ///
+    /// # Returns
+    ///
+    /// `Result<ConexionTcp, CustomError>` - Conexión creada
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorCreatingSocket` - Si no se pudo clonar el socket
+    pub fn new(stream: TcpStream) -> Result<ConexionTcp, CustomError> {
+        let escritor = stream
+            .try_clone()
+            .map_err(|_| CustomError::ErrorCreatingSocket)?;
+        Ok(ConexionTcp {
+            lector: Mutex::new(stream),
+            escritor: Mutex::new(escritor),
+            codec: Mutex::new(Codec::Json),
+        })
+    }
+
+    /// Función que obtiene el codec con el que se intercambian los mensajes
+    ///
+    /// # Returns
+    ///
+    /// `Codec` - Codec en uso
+    fn codec(&self) -> Codec {
+        self.codec.lock().map(|codec| *codec).unwrap_or_default()
+    }
+}
+
+impl Conexion for ConexionTcp {
+    fn enviar(&self, mensaje: &Mensaje) -> Result<(), CustomError> {
+        let codec = if mensaje.es_saludo() {
+            Codec::Json
+        } else {
+            self.codec()
+        };
+        let mensaje_serializado = codec.codificar(mensaje)?;
+        let mut escritor = self
+            .escritor
+            .lock()
+            .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+        escribir_trama(&mut *escritor, &mensaje_serializado)
+    }
+
+    fn recibir(&self) -> Result<Mensaje, CustomError> {
+        let trama = {
+            let mut lector = self
+                .lector
+                .lock()
+                .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+            leer_trama(&mut *lector)?
+        };
+        self.codec().decodificar(&trama)
+    }
+
+    fn recibir_con_plazo(&self, plazo: Duration) -> Result<Option<Mensaje>, CustomError> {
+        let trama = {
+            let mut lector = self
+                .lector
+                .lock()
+                .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+            // Solo se espera con plazo el comienzo de la trama, para no
+            // cortar una lectura a la mitad y desalinear el socket.
+            lector
+                .set_read_timeout(Some(plazo.max(PLAZO_MINIMO)))
+                .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+            let disponible = lector.peek(&mut [0u8; 1]);
+            lector
+                .set_read_timeout(None)
+                .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+            match disponible {
+                Ok(0) => return Err(CustomError::ErrorRecibiendoMensaje),
+                Ok(_) => leer_trama(&mut *lector)?,
+                Err(error)
+                    if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Ok(None)
+                }
+                Err(_) => return Err(CustomError::ErrorRecibiendoMensaje),
+            }
+        };
+        self.codec().decodificar(&trama).map(Some)
+    }
+
+    fn usar_codec(&self, codec: Codec) {
+        if let Ok(mut actual) = self.codec.lock() {
+            *actual = codec;
+        }
+    }
+}
+
+/// Conexión en memoria basada en canales, útil para manejar el juego sin sockets
+pub struct ConexionCanal {
+    emisor: Sender<Mensaje>,
+    receptor: Mutex<Receiver<Mensaje>>,
+}
+
+impl ConexionCanal {
+    /// Función que crea dos extremos conectados entre sí
+    ///
+    /// Lo que se envía por uno de los extremos se recibe por el otro.
+    ///
+    /// # Returns
+    ///
+    /// `(ConexionCanal, ConexionCanal)` - Extremos de la conexión
+    pub fn par() -> (ConexionCanal, ConexionCanal) {
+        let (emisor_a, receptor_b) = mpsc::channel();
+        let (emisor_b, receptor_a) = mpsc::channel();
+        (
+            ConexionCanal {
+                emisor: emisor_a,
+                receptor: Mutex::new(receptor_a),
+            },
+            ConexionCanal {
+                emisor: emisor_b,
+                receptor: Mutex::new(receptor_b),
+            },
+        )
+    }
+}
+
+impl Conexion for ConexionCanal {
+    fn enviar(&self, mensaje: &Mensaje) -> Result<(), CustomError> {
+        self.emisor
+            .send(mensaje.clone())
+            .map_err(|_| CustomError::ErrorEnviarMensaje)
+    }
+
+    fn recibir(&self) -> Result<Mensaje, CustomError> {
+        self.receptor
+            .lock()
+            .map_err(|_| CustomError::ErrorRecibiendoMensaje)?
+            .recv()
+            .map_err(|_| CustomError::ErrorRecibiendoMensaje)
+    }
+
+    fn recibir_con_plazo(&self, plazo: Duration) -> Result<Option<Mensaje>, CustomError> {
+        let receptor = self
+            .receptor
+            .lock()
+            .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+        match receptor.recv_timeout(plazo) {
+            Ok(mensaje) => Ok(Some(mensaje)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(CustomError::ErrorRecibiendoMensaje),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_conexion_canal() {
+        let (servidor, cliente) = ConexionCanal::par();
+        servidor.enviar(&Mensaje::Puntos(10)).unwrap();
+        cliente.enviar(&Mensaje::RespuestaComienzo(true)).unwrap();
+
+        assert!(matches!(cliente.recibir().unwrap(), Mensaje::Puntos(10)));
+        assert!(matches!(
+            servidor.recibir().unwrap(),
+            Mensaje::RespuestaComienzo(true)
+        ));
+    }
+
+    #[test]
+    fn test_conexion_canal_cerrada() {
+        let (servidor, cliente) = ConexionCanal::par();
+        drop(cliente);
+        assert!(servidor.recibir().is_err());
+        assert!(servidor.enviar(&Mensaje::Esperando).is_err());
+    }
+
+    #[test]
+    fn test_conexion_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let direccion = listener.local_addr().unwrap();
+        let cliente = ConexionTcp::new(TcpStream::connect(direccion).unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let servidor = ConexionTcp::new(stream).unwrap();
+
+        servidor.enviar(&Mensaje::Puntos(5)).unwrap();
+        servidor
+            .enviar(&Mensaje::NotificacionEliminacion("pedro".to_string()))
+            .unwrap();
+
+        assert!(matches!(cliente.recibir().unwrap(), Mensaje::Puntos(5)));
+        match cliente.recibir().unwrap() {
+            Mensaje::NotificacionEliminacion(nombre) => assert_eq!(nombre, "pedro"),
+            otro => panic!("Mensaje inesperado: {:?}", otro),
+        }
+    }
+
+    #[test]
+    fn test_recibir_con_plazo() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let direccion = listener.local_addr().unwrap();
+        let cliente = ConexionTcp::new(TcpStream::connect(direccion).unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let servidor = ConexionTcp::new(stream).unwrap();
+        let plazo = Duration::from_millis(20);
+
+        assert!(servidor.recibir_con_plazo(plazo).unwrap().is_none());
+        cliente.enviar(&Mensaje::Pong).unwrap();
+        assert!(matches!(
+            servidor.recibir_con_plazo(plazo).unwrap(),
+            Some(Mensaje::Pong)
+        ));
+        drop(cliente);
+        assert!(servidor.recibir_con_plazo(plazo).is_err());
+
+        let (servidor, cliente) = ConexionCanal::par();
+        assert!(servidor.recibir_con_plazo(plazo).unwrap().is_none());
+        drop(cliente);
+        assert!(servidor.recibir_con_plazo(plazo).is_err());
+    }
+}
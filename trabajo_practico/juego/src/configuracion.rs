@@ -0,0 +1,432 @@
+use std::{fs::File, io::BufReader, str::FromStr};
+
+use libreria::{
+    constantes::{COLUMNAS_MAPA, FILAS_MAPA},
+    custom_error::CustomError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{codec::Codec, reglas::Reglas};
+
+/// Cantidad máxima de jugadores por partida
+///
+/// Cada jugador se dibuja en el tablero con el primer dígito de su ID, por lo
+/// que a partir de 10 jugadores los barcos no se podrían distinguir.
+const LIMITE_JUGADORES: usize = 10;
+
+/// Segundos que se guarda el lugar de un jugador desconectado antes de eliminarlo
+const SEGUNDOS_RECONEXION: u64 = 60;
+
+/// Segundos que tiene un jugador para actuar antes de que se saltee su turno
+const SEGUNDOS_TURNO: u64 = 120;
+
+/// Segundos entre cada `Ping` que el servidor envía para detectar conexiones caídas
+const SEGUNDOS_LATIDO: u64 = 10;
+
+/// Segundos que pasan desde que todos los jugadores están listos hasta que comienza la partida
+const SEGUNDOS_CUENTA_REGRESIVA: u64 = 5;
+
+/// Cantidad de jugadores de cada partida que arma la cola de emparejamiento
+const JUGADORES_POR_PARTIDA: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+/// Estructura que representa la configuración del servidor
+pub struct ConfiguracionServidor {
+    pub host: String,
+    pub puerto: u16,
+    pub minimo_jugadores: usize,
+    pub maximo_jugadores: usize,
+    pub filas: usize,
+    pub columnas: usize,
+    pub segundos_reconexion: u64,
+    pub segundos_turno: u64,
+    pub segundos_latido: u64,
+    pub segundos_cuenta_regresiva: u64,
+    pub segundos_despliegue: Option<u64>,
+    pub jugadores_por_partida: usize,
+    pub emparejar_por_ranking: bool,
+    pub codec: Codec,
+    pub puerto_websocket: Option<u16>,
+    pub clave_administrador: Option<String>,
+    pub reglas: Reglas,
+}
+
+impl Default for ConfiguracionServidor {
+    fn default() -> Self {
+        ConfiguracionServidor {
+            host: "127.0.0.1".to_string(),
+            puerto: 8080,
+            minimo_jugadores: 3,
+            maximo_jugadores: LIMITE_JUGADORES,
+            filas: FILAS_MAPA,
+            columnas: COLUMNAS_MAPA,
+            segundos_reconexion: SEGUNDOS_RECONEXION,
+            segundos_turno: SEGUNDOS_TURNO,
+            segundos_latido: SEGUNDOS_LATIDO,
+            segundos_cuenta_regresiva: SEGUNDOS_CUENTA_REGRESIVA,
+            segundos_despliegue: None,
+            jugadores_por_partida: JUGADORES_POR_PARTIDA,
+            emparejar_por_ranking: false,
+            codec: Codec::Binario,
+            puerto_websocket: None,
+            clave_administrador: None,
+            reglas: Reglas::default(),
+        }
+    }
+}
+
+impl ConfiguracionServidor {
+    /// Función que carga la configuración desde un archivo JSON
+    ///
+    /// Los campos que no estén en el archivo toman su valor por defecto.
+    ///
+    /// # Args
+    ///
+    /// `ruta` - Ruta del archivo de configuración
+    ///
+    /// # Returns
+    ///
+    /// `Result<ConfiguracionServidor, CustomError>` - Configuración cargada
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorConfiguracion` - Si no se pudo leer el archivo o sus valores son inválidos
+    pub fn desde_archivo(ruta: &str) -> Result<ConfiguracionServidor, CustomError> {
+        let archivo = File::open(ruta).map_err(|_| CustomError::ErrorConfiguracion)?;
+        let configuracion: ConfiguracionServidor = serde_json::from_reader(BufReader::new(archivo))
+            .map_err(|_| CustomError::ErrorConfiguracion)?;
+        configuracion.validar()?;
+        Ok(configuracion)
+    }
+
+    /// Función que arma la configuración a partir de los argumentos de línea de comandos
+    ///
+    /// Si se indica `--config <ruta>` se parte de ese archivo y si se indica
+    /// `--reglas <ruta>` se reemplazan sus reglas; el resto de las opciones
+    /// (`--host`, `--puerto`, `--min-jugadores`, `--max-jugadores`, `--filas`,
+    /// `--columnas`, `--segundos-reconexion`, `--segundos-turno`,
+    /// `--segundos-latido`, `--segundos-cuenta-regresiva`,
+    /// `--segundos-despliegue`, `--codec`, `--puerto-websocket`,
+    /// `--clave-administrador`, `--jugadores-por-partida`,
+    /// `--emparejar-por-ranking`, `--monedas-iniciales`, `--ronda-sorpresa`)
+    /// pisan los valores de los archivos. Con `--codec json` el servidor no
+    /// acepta el codec binario, con `--puerto-websocket` también acepta
+    /// clientes por WebSocket, con `--clave-administrador` los espectadores
+    /// que la indiquen ven el tablero sin ocultar, con
+    /// `--emparejar-por-ranking true` la cola de emparejamiento agrupa a los
+    /// jugadores por sus puntos y con `--segundos-despliegue` cada partida
+    /// empieza con una fase en la que los jugadores ubican su flota.
+    ///
+    /// # Args
+    ///
+    /// `argumentos` - Argumentos recibidos, sin el nombre del programa
+    ///
+    /// # Returns
+    ///
+    /// `Result<ConfiguracionServidor, CustomError>` - Configuración resultante
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorConfiguracion` - Si hay una opción desconocida o un valor inválido
+    pub fn desde_argumentos<I: IntoIterator<Item = String>>(
+        argumentos: I,
+    ) -> Result<ConfiguracionServidor, CustomError> {
+        let opciones = parsear_opciones(argumentos)?;
+        let mut configuracion = match opciones.iter().find(|(opcion, _)| opcion == "--config") {
+            Some((_, ruta)) => ConfiguracionServidor::desde_archivo(ruta)?,
+            None => ConfiguracionServidor::default(),
+        };
+        if let Some((_, ruta)) = opciones.iter().find(|(opcion, _)| opcion == "--reglas") {
+            configuracion.reglas = Reglas::desde_archivo(ruta)?;
+        }
+
+        for (opcion, valor) in &opciones {
+            match opcion.as_str() {
+                "--config" | "--reglas" => {}
+                "--host" => configuracion.host = valor.clone(),
+                "--puerto" => configuracion.puerto = parsear_valor(valor)?,
+                "--min-jugadores" => configuracion.minimo_jugadores = parsear_valor(valor)?,
+                "--max-jugadores" => configuracion.maximo_jugadores = parsear_valor(valor)?,
+                "--filas" => configuracion.filas = parsear_valor(valor)?,
+                "--columnas" => configuracion.columnas = parsear_valor(valor)?,
+                "--segundos-reconexion" => {
+                    configuracion.segundos_reconexion = parsear_valor(valor)?
+                }
+                "--segundos-turno" => configuracion.segundos_turno = parsear_valor(valor)?,
+                "--segundos-latido" => configuracion.segundos_latido = parsear_valor(valor)?,
+                "--segundos-cuenta-regresiva" => {
+                    configuracion.segundos_cuenta_regresiva = parsear_valor(valor)?
+                }
+                "--segundos-despliegue" => {
+                    configuracion.segundos_despliegue = Some(parsear_valor(valor)?)
+                }
+                "--codec" => configuracion.codec = parsear_valor(valor)?,
+                "--puerto-websocket" => {
+                    configuracion.puerto_websocket = Some(parsear_valor(valor)?)
+                }
+                "--clave-administrador" => configuracion.clave_administrador = Some(valor.clone()),
+                "--jugadores-por-partida" => {
+                    configuracion.jugadores_por_partida = parsear_valor(valor)?
+                }
+                "--emparejar-por-ranking" => {
+                    configuracion.emparejar_por_ranking = parsear_valor(valor)?
+                }
+                "--monedas-iniciales" => {
+                    configuracion.reglas.monedas_iniciales = parsear_valor(valor)?
+                }
+                "--ronda-sorpresa" => {
+                    configuracion.reglas.ronda_evento_sorpresa = parsear_valor(valor)?
+                }
+                _ => return Err(CustomError::ErrorConfiguracion),
+            }
+        }
+        configuracion.validar()?;
+        Ok(configuracion)
+    }
+
+    /// Función que obtiene la dirección en la que escucha el servidor
+    ///
+    /// # Returns
+    ///
+    /// `String` - Dirección con formato `host:puerto`
+    pub fn direccion(&self) -> String {
+        format!("{}:{}", self.host, self.puerto)
+    }
+
+    /// Función que obtiene la dirección en la que se aceptan clientes por WebSocket
+    ///
+    /// # Returns
+    ///
+    /// `Option<String>` - Dirección con formato `host:puerto`, si se habilitó el WebSocket
+    pub fn direccion_websocket(&self) -> Option<String> {
+        self.puerto_websocket
+            .map(|puerto| format!("{}:{}", self.host, puerto))
+    }
+
+    /// Función que verifica que los valores de la configuración sean coherentes
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si la configuración es válida
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorConfiguracion` - Si algún valor está fuera de rango
+    ///
+    /// `CustomError::ErrorReglas` - Si las reglas no permiten jugar en el tablero configurado
+    fn validar(&self) -> Result<(), CustomError> {
+        self.reglas.validar()?;
+        let (x_bomba, y_bomba) = self.reglas.coordenadas_bomba;
+        if x_bomba as usize >= self.columnas || y_bomba as usize >= self.filas {
+            return Err(CustomError::ErrorReglas);
+        }
+        let jugadores_validos = 2 <= self.minimo_jugadores
+            && self.minimo_jugadores <= self.maximo_jugadores
+            && self.maximo_jugadores <= LIMITE_JUGADORES
+            && 2 <= self.jugadores_por_partida
+            && self.jugadores_por_partida <= self.maximo_jugadores;
+        let tablero_valido = self.filas > 0
+            && self.columnas > 0
+            && self.filas * self.columnas >= self.maximo_jugadores;
+        let plazos_validos = self.segundos_turno > 0 && self.segundos_latido > 0;
+        let puertos_validos = self.puerto == 0 || self.puerto_websocket != Some(self.puerto);
+        if jugadores_validos && tablero_valido && plazos_validos && puertos_validos {
+            Ok(())
+        } else {
+            Err(CustomError::ErrorConfiguracion)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Estructura que representa la configuración del jugador
+pub struct ConfiguracionCliente {
+    pub host: String,
+    pub puerto: u16,
+    pub token: Option<String>,
+    pub codec: Codec,
+}
+
+impl Default for ConfiguracionCliente {
+    fn default() -> Self {
+        let servidor = ConfiguracionServidor::default();
+        ConfiguracionCliente {
+            host: servidor.host,
+            puerto: servidor.puerto,
+            token: None,
+            codec: servidor.codec,
+        }
+    }
+}
+
+impl ConfiguracionCliente {
+    /// Función que arma la configuración a partir de los argumentos de línea de comandos
+    ///
+    /// Acepta las opciones `--host`, `--puerto`, `--token`, para volver a una
+    /// partida con la sesión de una conexión anterior, y `--codec`, para
+    /// pedir mensajes en `binario` o en `json`.
+    ///
+    /// # Args
+    ///
+    /// `argumentos` - Argumentos recibidos, sin el nombre del programa
+    ///
+    /// # Returns
+    ///
+    /// `Result<ConfiguracionCliente, CustomError>` - Configuración resultante
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorConfiguracion` - Si hay una opción desconocida o un valor inválido
+    pub fn desde_argumentos<I: IntoIterator<Item = String>>(
+        argumentos: I,
+    ) -> Result<ConfiguracionCliente, CustomError> {
+        let mut configuracion = ConfiguracionCliente::default();
+        for (opcion, valor) in parsear_opciones(argumentos)? {
+            match opcion.as_str() {
+                "--host" => configuracion.host = valor,
+                "--puerto" => configuracion.puerto = parsear_valor(&valor)?,
+                "--token" => configuracion.token = Some(valor),
+                "--codec" => configuracion.codec = parsear_valor(&valor)?,
+                _ => return Err(CustomError::ErrorConfiguracion),
+            }
+        }
+        Ok(configuracion)
+    }
+
+    /// Función que obtiene la dirección del servidor al que conectarse
+    ///
+    /// # Returns
+    ///
+    /// `String` - Dirección con formato `host:puerto`
+    pub fn direccion(&self) -> String {
+        format!("{}:{}", self.host, self.puerto)
+    }
+}
+
+/// Función que agrupa los argumentos en pares `(opcion, valor)`
+///
+/// # Args
+///
+/// `argumentos` - Argumentos recibidos
+///
+/// # Returns
+///
+/// `Result<Vec<(String, String)>, CustomError>` - Opciones con su valor
+///
+/// # Errors
+///
+/// `CustomError::ErrorConfiguracion` - Si una opción no empieza con `--` o no tiene valor
+fn parsear_opciones<I: IntoIterator<Item = String>>(
+    argumentos: I,
+) -> Result<Vec<(String, String)>, CustomError> {
+    let mut opciones = Vec::new();
+    let mut argumentos = argumentos.into_iter();
+    while let Some(opcion) = argumentos.next() {
+        if !opcion.starts_with("--") {
+            return Err(CustomError::ErrorConfiguracion);
+        }
+        let valor = argumentos.next().ok_or(CustomError::ErrorConfiguracion)?;
+        opciones.push((opcion, valor));
+    }
+    Ok(opciones)
+}
+
+fn parsear_valor<T: FromStr>(valor: &str) -> Result<T, CustomError> {
+    valor.parse().map_err(|_| CustomError::ErrorConfiguracion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argumentos(texto: &str) -> Vec<String> {
+        texto.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_configuracion_por_defecto() {
+        let configuracion = ConfiguracionServidor::desde_argumentos(Vec::new()).unwrap();
+        assert_eq!(configuracion, ConfiguracionServidor::default());
+        assert_eq!(configuracion.direccion(), "127.0.0.1:8080");
+        assert!(configuracion.direccion_websocket().is_none());
+    }
+
+    #[test]
+    fn test_configuracion_desde_argumentos() {
+        let configuracion = ConfiguracionServidor::desde_argumentos(argumentos(
+            "--host 0.0.0.0 --puerto 9000 --min-jugadores 2 --filas 8 --columnas 12 --segundos-reconexion 30 --segundos-turno 45 --segundos-cuenta-regresiva 3 --segundos-despliegue 40 --puerto-websocket 9001 --clave-administrador faro --jugadores-por-partida 4 --emparejar-por-ranking true",
+        ))
+        .unwrap();
+        assert_eq!(configuracion.segundos_reconexion, 30);
+        assert_eq!(configuracion.segundos_turno, 45);
+        assert_eq!(configuracion.segundos_cuenta_regresiva, 3);
+        assert_eq!(configuracion.segundos_despliegue, Some(40));
+        assert_eq!(configuracion.direccion(), "0.0.0.0:9000");
+        assert_eq!(
+            configuracion.direccion_websocket().as_deref(),
+            Some("0.0.0.0:9001")
+        );
+        assert_eq!(configuracion.clave_administrador.as_deref(), Some("faro"));
+        assert_eq!(configuracion.jugadores_por_partida, 4);
+        assert!(configuracion.emparejar_por_ranking);
+        assert_eq!(configuracion.minimo_jugadores, 2);
+        assert_eq!((configuracion.filas, configuracion.columnas), (8, 12));
+    }
+
+    #[test]
+    fn test_configuracion_invalida() {
+        for texto in [
+            "--puerto",
+            "--puerto abc",
+            "--opcion 1",
+            "--min-jugadores 5 --max-jugadores 4",
+            "--max-jugadores 11",
+            "--jugadores-por-partida 1",
+            "--min-jugadores 2 --max-jugadores 3 --jugadores-por-partida 4",
+            "--emparejar-por-ranking quizas",
+            "--filas 0",
+            "--filas 4",
+            "--segundos-turno 0",
+            "--segundos-latido 0",
+            "--codec yaml",
+            "--puerto 9000 --puerto-websocket 9000",
+        ] {
+            assert!(
+                ConfiguracionServidor::desde_argumentos(argumentos(texto)).is_err(),
+                "{}",
+                texto
+            );
+        }
+    }
+
+    #[test]
+    fn test_configuracion_desde_archivo() {
+        let ruta = std::env::temp_dir().join("configuracion_servidor_test.json");
+        std::fs::write(
+            &ruta,
+            r#"{ "puerto": 7000, "reglas": { "monedas_iniciales": 800 } }"#,
+        )
+        .unwrap();
+        let texto = format!("--config {} --puerto 7001", ruta.display());
+
+        let configuracion = ConfiguracionServidor::desde_argumentos(argumentos(&texto)).unwrap();
+        assert_eq!(configuracion.puerto, 7001);
+        assert_eq!(configuracion.reglas.monedas_iniciales, 800);
+        assert_eq!(configuracion.filas, FILAS_MAPA);
+    }
+
+    #[test]
+    fn test_configuracion_cliente() {
+        let configuracion =
+            ConfiguracionCliente::desde_argumentos(argumentos("--host 10.0.0.1 --token abc"))
+                .unwrap();
+        assert_eq!(configuracion.direccion(), "10.0.0.1:8080");
+        assert_eq!(configuracion.token.as_deref(), Some("abc"));
+        assert_eq!(configuracion.codec, Codec::Binario);
+        let configuracion =
+            ConfiguracionCliente::desde_argumentos(argumentos("--codec json")).unwrap();
+        assert_eq!(configuracion.codec, Codec::Json);
+        assert!(ConfiguracionCliente::desde_argumentos(argumentos("--codec xml")).is_err());
+    }
+}
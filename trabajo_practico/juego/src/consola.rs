@@ -0,0 +1,141 @@
+use std::{
+    io::BufRead,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    chat::{self, AYUDA_CHAT},
+    conexion::Conexion,
+};
+
+/// Estructura que separa las respuestas del juego de los comandos de chat escritos en la consola
+///
+/// Un único hilo lee la entrada. Los comandos de chat se envían al servidor
+/// en ese hilo, aunque el juego esté esperando otra respuesta, y el resto de
+/// las líneas quedan en un canal para quien las pida.
+pub struct Consola {
+    lineas: Receiver<String>,
+    conexiones: Sender<Arc<dyn Conexion>>,
+}
+
+impl Consola {
+    /// Función que lanza el hilo que lee la entrada
+    ///
+    /// # Args
+    ///
+    /// `entrada` - Entrada de la que se leen las líneas, normalmente la entrada estándar
+    ///
+    /// `conexion` - Conexión por la que se envían los comandos de chat
+    ///
+    /// # Returns
+    ///
+    /// `Consola` - Consola que entrega las líneas que no son comandos de chat
+    pub fn lanzar<R: BufRead + Send + 'static>(entrada: R, conexion: Arc<dyn Conexion>) -> Consola {
+        let (emisor, lineas) = mpsc::channel();
+        let (conexiones, nuevas_conexiones) = mpsc::channel();
+        println!("{}", AYUDA_CHAT);
+        thread::spawn(move || leer_entrada(entrada, conexion, nuevas_conexiones, emisor));
+        Consola { lineas, conexiones }
+    }
+
+    /// Función que indica la conexión por la que se envían los comandos de chat
+    ///
+    /// Se vuelve a llamar cada vez que el cliente se reconecta.
+    ///
+    /// # Args
+    ///
+    /// `conexion` - Conexión con el servidor
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn usar_conexion(&self, conexion: Arc<dyn Conexion>) {
+        let _ = self.conexiones.send(conexion);
+    }
+
+    /// Función que lee una línea para responder una pregunta del juego
+    ///
+    /// Los comandos de chat nunca llegan a esta función.
+    ///
+    /// # Returns
+    ///
+    /// `String` - Línea leída, sin espacios al principio ni al final; vacía si se cerró la entrada
+    pub fn leer_linea(&self) -> String {
+        self.lineas.recv().unwrap_or_default()
+    }
+}
+
+/// Función que lee la entrada hasta que se cierra y reparte sus líneas
+///
+/// # Args
+///
+/// `entrada` - Entrada de la que se leen las líneas
+///
+/// `conexion` - Conexión por la que se envían los comandos de chat
+///
+/// `nuevas_conexiones` - Canal por el que llegan las conexiones abiertas al reconectarse
+///
+/// `lineas` - Canal por el que se entregan las líneas que no son comandos de chat
+///
+/// # Returns
+///
+/// `()` - No retorna nada
+fn leer_entrada<R: BufRead>(
+    entrada: R,
+    mut conexion: Arc<dyn Conexion>,
+    nuevas_conexiones: Receiver<Arc<dyn Conexion>>,
+    lineas: Sender<String>,
+) {
+    for linea in entrada.lines() {
+        let Ok(linea) = linea else {
+            return;
+        };
+        match chat::interpretar_comando(&linea) {
+            None => {
+                if lineas.send(linea.trim().to_string()).is_err() {
+                    return;
+                }
+            }
+            Some(Ok(mensaje)) => {
+                if let Some(nueva) = nuevas_conexiones.try_iter().last() {
+                    conexion = nueva;
+                }
+                if conexion.enviar(&mensaje).is_err() {
+                    println!("No se pudo enviar el mensaje de chat");
+                }
+            }
+            Some(Err(err)) => println!("{}\n{}", err, AYUDA_CHAT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{conexion::ConexionCanal, mensaje::Mensaje};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_cada_consola_envia_el_chat_por_su_conexion() {
+        let (ana, servidor_ana) = ConexionCanal::par();
+        let (beto, servidor_beto) = ConexionCanal::par();
+        let consola_ana = Consola::lanzar(Cursor::new("/todos hola\natacar\n"), Arc::new(ana));
+        let consola_beto = Consola::lanzar(Cursor::new("s\n/todos chau\n"), Arc::new(beto));
+
+        assert_eq!(consola_ana.leer_linea(), "atacar");
+        assert_eq!(consola_beto.leer_linea(), "s");
+        assert!(matches!(
+            servidor_ana.recibir().unwrap(),
+            Mensaje::Chat { para: None, texto } if texto == "hola"
+        ));
+        assert!(matches!(
+            servidor_beto.recibir().unwrap(),
+            Mensaje::Chat { para: None, texto } if texto == "chau"
+        ));
+        assert_eq!(consola_ana.leer_linea(), "");
+    }
+}
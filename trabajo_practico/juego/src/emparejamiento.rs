@@ -0,0 +1,298 @@
+use std::{sync::Arc, time::Duration};
+
+use libreria::custom_error::CustomError;
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time,
+};
+
+use crate::{
+    configuracion::ConfiguracionServidor,
+    lobby::{JugadorEntrante, Lobby},
+    mensaje::Mensaje,
+    protocolo::CHAT,
+    ranking,
+    sala::Sala,
+};
+
+/// Nombre con el que aparecen en el lobby las salas que arma la cola
+const NOMBRE_SALA_EMPAREJADA: &str = "Partida emparejada";
+/// Cada cuánto se revisa si los jugadores que esperan siguen conectados
+const INTERVALO_COLA: Duration = Duration::from_millis(500);
+
+/// Jugador que espera en la cola, junto con sus puntos del ranking
+struct EnEspera {
+    jugador: JugadorEntrante,
+    puntos: usize,
+}
+
+/// Estructura que junta a los jugadores que buscan partida y arma una sala por cada grupo
+///
+/// Corre en su propia tarea y recibe a los jugadores por un canal, tanto a
+/// los que llegan desde el lobby como a los que vuelven de una partida
+/// emparejada. Si se configuró, agrupa a los jugadores con puntajes del
+/// ranking parecidos.
+pub struct Emparejamiento {
+    en_espera: Vec<EnEspera>,
+    retorno: UnboundedSender<JugadorEntrante>,
+    configuracion: ConfiguracionServidor,
+    lobby: Arc<Lobby>,
+}
+
+impl Emparejamiento {
+    /// Función que lanza la tarea de la cola de emparejamiento
+    ///
+    /// # Args
+    ///
+    /// `configuracion` - Configuración del servidor
+    ///
+    /// `lobby` - Lobby en el que se registran las salas de cada partida
+    ///
+    /// # Returns
+    ///
+    /// `UnboundedSender<JugadorEntrante>` - Canal por el que se suman jugadores a la cola
+    pub fn lanzar(
+        configuracion: ConfiguracionServidor,
+        lobby: Arc<Lobby>,
+    ) -> UnboundedSender<JugadorEntrante> {
+        let (cola, entrantes) = mpsc::unbounded_channel();
+        let emparejamiento = Emparejamiento {
+            en_espera: Vec::new(),
+            retorno: cola.clone(),
+            configuracion,
+            lobby,
+        };
+        tokio::spawn(emparejamiento.run(entrantes));
+        cola
+    }
+
+    /// Función que atiende la cola hasta que se cierra su canal
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que buscan partida
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    async fn run(mut self, mut entrantes: UnboundedReceiver<JugadorEntrante>) {
+        loop {
+            match time::timeout(INTERVALO_COLA, entrantes.recv()).await {
+                Ok(Some(jugador)) => self.encolar(jugador),
+                Ok(None) => return,
+                Err(_) => {}
+            }
+            self.atender_en_espera();
+            self.armar_partidas();
+        }
+    }
+
+    /// Función que suma un jugador a la cola y avisa a todos cuántos esperan
+    ///
+    /// # Args
+    ///
+    /// `jugador` - Jugador que busca partida
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn encolar(&mut self, jugador: JugadorEntrante) {
+        println!("{} busca partida", jugador.nombre);
+        let puntos = if self.configuracion.emparejar_por_ranking {
+            puntos_en_ranking(&jugador.nombre)
+        } else {
+            0
+        };
+        self.en_espera.push(EnEspera { jugador, puntos });
+        let mensaje = Mensaje::EnCola(
+            self.en_espera.len(),
+            self.configuracion.jugadores_por_partida,
+        );
+        for en_espera in &self.en_espera {
+            let _ = en_espera.jugador.enlace.enviar(&mensaje);
+        }
+    }
+
+    /// Función que atiende los mensajes de los jugadores que esperan y saca de la cola a los desconectados
+    ///
+    /// En la cola no hay con quién chatear, por lo que los mensajes de chat
+    /// se rechazan, si el cliente admite el chat; el resto se descarta.
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn atender_en_espera(&mut self) {
+        let lobby = Arc::clone(&self.lobby);
+        self.en_espera.retain_mut(|en_espera| {
+            let enlace = &mut en_espera.jugador.enlace;
+            while let Some(mensaje) = enlace.pendiente() {
+                if matches!(mensaje, Mensaje::Chat { .. } | Mensaje::Silenciar(..))
+                    && enlace.admite(CHAT)
+                {
+                    let motivo = CustomError::ErrorChatFueraDeSala.to_string();
+                    let _ = enlace.enviar(&Mensaje::ChatRechazado(motivo));
+                }
+            }
+            if enlace.cerrado() {
+                println!("{} dejó de buscar partida", en_espera.jugador.nombre);
+                lobby.liberar_nombre(&en_espera.jugador.nombre);
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Función que arma partidas mientras haya jugadores suficientes en la cola
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn armar_partidas(&mut self) {
+        let tamanio = self.configuracion.jugadores_por_partida;
+        while self.en_espera.len() >= tamanio {
+            let inicio = if self.configuracion.emparejar_por_ranking {
+                // El orden es estable, así que a igual puntaje se respeta la llegada
+                self.en_espera.sort_by_key(|en_espera| en_espera.puntos);
+                let puntos: Vec<usize> = self.en_espera.iter().map(|e| e.puntos).collect();
+                inicio_grupo_parejo(&puntos, tamanio)
+            } else {
+                0
+            };
+            let grupo: Vec<JugadorEntrante> = self
+                .en_espera
+                .drain(inicio..inicio + tamanio)
+                .map(|en_espera| en_espera.jugador)
+                .collect();
+            self.iniciar_partida(grupo);
+        }
+    }
+
+    /// Función que abre una sala para un grupo de jugadores y lanza su partida
+    ///
+    /// La sala queda en curso desde el principio, así nadie más se une por
+    /// el lobby, aunque se la puede mirar como espectador.
+    ///
+    /// # Args
+    ///
+    /// `grupo` - Jugadores de la partida
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn iniciar_partida(&mut self, grupo: Vec<JugadorEntrante>) {
+        let (codigo, entrantes) = self
+            .lobby
+            .crear_sala(NOMBRE_SALA_EMPAREJADA.to_string(), None);
+        println!("Sala {} creada para una partida emparejada", codigo);
+        for jugador in grupo {
+            if let Err((err, jugador)) = self.lobby.unirse(&codigo, None, jugador) {
+                eprintln!(
+                    "No se pudo sumar a {} a la partida: {}",
+                    jugador.nombre, err
+                );
+                self.encolar(jugador);
+            }
+        }
+        self.lobby.marcar_en_curso(&codigo);
+        let sala = Sala::emparejada(
+            codigo,
+            self.configuracion.clone(),
+            Arc::clone(&self.lobby),
+            self.retorno.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(err) = sala.run(entrantes).await {
+                eprintln!("Error en la sala: {}", err);
+            }
+        });
+    }
+}
+
+/// Función que obtiene los puntos que un jugador acumula en el ranking
+///
+/// # Args
+///
+/// `nombre` - Nombre del jugador
+///
+/// # Returns
+///
+/// `usize` - Puntos del jugador, 0 si no figura o no se pudo leer el ranking
+fn puntos_en_ranking(nombre: &str) -> usize {
+    ranking::obtener_ranking()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(otro, _)| otro == nombre)
+        .map_or(0, |(_, puntos)| puntos)
+}
+
+/// Función que elige el grupo de jugadores con los puntajes más parecidos
+///
+/// # Args
+///
+/// `puntos` - Puntos de los jugadores en espera, ordenados de menor a mayor
+///
+/// `tamanio` - Cantidad de jugadores por partida
+///
+/// # Returns
+///
+/// `usize` - Posición del primer jugador del grupo con menor diferencia de puntos
+fn inicio_grupo_parejo(puntos: &[usize], tamanio: usize) -> usize {
+    (0..=puntos.len().saturating_sub(tamanio))
+        .min_by_key(|inicio| puntos[inicio + tamanio - 1] - puntos[*inicio])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enlace::Enlace, lobby::Rol};
+
+    #[test]
+    fn test_grupo_con_puntajes_parecidos() {
+        assert_eq!(inicio_grupo_parejo(&[0, 300, 500, 520, 900], 2), 2);
+        assert_eq!(inicio_grupo_parejo(&[0, 10, 20, 500], 3), 0);
+        assert_eq!(inicio_grupo_parejo(&[7, 7], 2), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cola_arma_partidas_del_tamanio_configurado() {
+        let lobby = Arc::new(Lobby::new(4));
+        let configuracion = ConfiguracionServidor {
+            jugadores_por_partida: 2,
+            ..Default::default()
+        };
+        let cola = Emparejamiento::lanzar(configuracion, Arc::clone(&lobby));
+
+        let mut clientes: Vec<Enlace> = Vec::new();
+        for nombre in ["ana", "beto", "carla"] {
+            let (enlace, cliente) = Enlace::par();
+            cola.send(JugadorEntrante {
+                nombre: nombre.to_string(),
+                enlace,
+                rol: Rol::Jugador,
+            })
+            .unwrap();
+            clientes.push(cliente);
+        }
+        for cliente in clientes.iter_mut().take(2) {
+            loop {
+                if matches!(cliente.recibir().await.unwrap(), Mensaje::SalaUnida(_)) {
+                    break;
+                }
+            }
+        }
+        assert!(matches!(
+            clientes[2].recibir().await.unwrap(),
+            Mensaje::EnCola(1, 2)
+        ));
+        let salas = lobby.listar_salas();
+        assert_eq!(salas.len(), 1);
+        assert_eq!(salas[0].jugadores, 2);
+        assert!(salas[0].en_curso);
+
+        lobby.registrar_nombre("carla");
+        drop(clientes.remove(2));
+        time::sleep(INTERVALO_COLA * 2).await;
+        assert!(lobby.registrar_nombre("carla"));
+    }
+}
@@ -0,0 +1,383 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use libreria::custom_error::CustomError;
+use tokio::{
+    net::TcpStream,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        watch,
+    },
+    time,
+};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+use crate::{
+    codec::Codec,
+    mensaje::Mensaje,
+    protocolo::Protocolo,
+    trama::{escribir_trama_async, leer_trama_async},
+};
+
+/// Destino de los mensajes que se envían por un enlace
+#[derive(Debug)]
+enum Salida {
+    /// Otro enlace en memoria, que recibe los mensajes sin codificar
+    Mensajes(UnboundedSender<Mensaje>),
+    /// Tarea que escribe en el socket las tramas ya codificadas
+    Tramas(UnboundedSender<Vec<u8>>),
+}
+
+/// Estructura con la que el servidor intercambia mensajes con un jugador
+///
+/// No toca el socket: cada conexión tiene una tarea que lee y otra que
+/// escribe, y el enlace solo se comunica con ellas por canales. Así quien
+/// atiende al jugador (el lobby o la sala) nunca bloquea un hilo ni toma un
+/// lock mientras espera la red.
+///
+/// Cada enlace recuerda el protocolo acordado con su cliente; hasta que se
+/// acuerda uno se asumen todas las capacidades salvo el codec binario.
+#[derive(Debug)]
+pub struct Enlace {
+    salida: Salida,
+    entrada: UnboundedReceiver<Mensaje>,
+    protocolo: Protocolo,
+    codec_entrada: watch::Sender<Codec>,
+    solo_texto: bool,
+}
+
+impl Enlace {
+    /// Función que crea dos enlaces conectados entre sí, sin socket
+    ///
+    /// Lo que se envía por uno de los extremos se recibe por el otro.
+    ///
+    /// # Returns
+    ///
+    /// `(Enlace, Enlace)` - Extremos del enlace
+    pub fn par() -> (Enlace, Enlace) {
+        let (salida_a, entrada_b) = mpsc::unbounded_channel();
+        let (salida_b, entrada_a) = mpsc::unbounded_channel();
+        (
+            Enlace {
+                salida: Salida::Mensajes(salida_a),
+                entrada: entrada_a,
+                protocolo: Protocolo::actual(),
+                codec_entrada: watch::channel(Codec::Json).0,
+                solo_texto: false,
+            },
+            Enlace {
+                salida: Salida::Mensajes(salida_b),
+                entrada: entrada_b,
+                protocolo: Protocolo::actual(),
+                codec_entrada: watch::channel(Codec::Json).0,
+                solo_texto: false,
+            },
+        )
+    }
+
+    /// Función que lanza las tareas que atienden un socket y devuelve su enlace
+    ///
+    /// Cuando se corta la conexión las tareas terminan, por lo que el enlace
+    /// empieza a devolver errores.
+    ///
+    /// # Args
+    ///
+    /// `stream` - Socket conectado con el jugador
+    ///
+    /// # Returns
+    ///
+    /// `Enlace` - Enlace con el jugador
+    pub fn desde_socket(stream: TcpStream) -> Enlace {
+        let (mut lector, mut escritor) = stream.into_split();
+        let (salida, mut pendientes) = mpsc::unbounded_channel::<Vec<u8>>();
+        let (recibidos, entrada) = mpsc::unbounded_channel();
+        let (codec_entrada, codec_lector) = watch::channel(Codec::Json);
+
+        tokio::spawn(async move {
+            while let Ok(trama) = leer_trama_async(&mut lector).await {
+                let codec = *codec_lector.borrow();
+                let Ok(mensaje) = codec.decodificar(&trama) else {
+                    break;
+                };
+                if recibidos.send(mensaje).is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(datos) = pendientes.recv().await {
+                if escribir_trama_async(&mut escritor, &datos).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Enlace {
+            salida: Salida::Tramas(salida),
+            entrada,
+            protocolo: Protocolo::con_codec(Codec::Json),
+            codec_entrada,
+            solo_texto: false,
+        }
+    }
+
+    /// Función que lanza las tareas que atienden un WebSocket y devuelve su enlace
+    ///
+    /// Cada mensaje viaja en JSON dentro de un frame de texto, por lo que con
+    /// estos clientes no se acuerda el codec binario.
+    ///
+    /// # Args
+    ///
+    /// `websocket` - WebSocket ya aceptado
+    ///
+    /// # Returns
+    ///
+    /// `Enlace` - Enlace con el jugador
+    pub fn desde_websocket(websocket: WebSocketStream<TcpStream>) -> Enlace {
+        let (mut escritor, mut lector) = websocket.split();
+        let (salida, mut pendientes) = mpsc::unbounded_channel::<Vec<u8>>();
+        let (recibidos, entrada) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Some(Ok(frame)) = lector.next().await {
+                let mensaje = match frame {
+                    Message::Text(texto) => Codec::Json.decodificar(texto.as_bytes()),
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                let Ok(mensaje) = mensaje else {
+                    break;
+                };
+                if recibidos.send(mensaje).is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(datos) = pendientes.recv().await {
+                let Ok(texto) = String::from_utf8(datos) else {
+                    continue;
+                };
+                if escritor.send(Message::Text(texto)).await.is_err() {
+                    break;
+                }
+            }
+            let _ = escritor.close().await;
+        });
+        Enlace {
+            salida: Salida::Tramas(salida),
+            entrada,
+            protocolo: Protocolo::con_codec(Codec::Json),
+            codec_entrada: watch::channel(Codec::Json).0,
+            solo_texto: true,
+        }
+    }
+
+    /// Función que indica si el enlace solo puede transportar mensajes en JSON
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si el cliente se conectó por WebSocket
+    pub fn solo_texto(&self) -> bool {
+        self.solo_texto
+    }
+
+    /// Función que fija el protocolo acordado con el cliente
+    ///
+    /// Desde ese momento los mensajes se leen y se escriben con el codec
+    /// acordado, salvo los del saludo, que siempre viajan en JSON.
+    ///
+    /// # Args
+    ///
+    /// `protocolo` - Protocolo acordado
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn acordar_protocolo(&mut self, protocolo: Protocolo) {
+        self.codec_entrada.send_replace(protocolo.codec());
+        self.protocolo = protocolo;
+    }
+
+    /// Función que indica si el cliente admite una capacidad del protocolo
+    ///
+    /// # Args
+    ///
+    /// `capacidad` - Nombre de la capacidad
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si la capacidad fue acordada
+    pub fn admite(&self, capacidad: &str) -> bool {
+        self.protocolo.admite(capacidad)
+    }
+
+    /// Función que envía un mensaje al jugador
+    ///
+    /// No espera a que el mensaje se escriba en el socket.
+    ///
+    /// # Args
+    ///
+    /// `mensaje` - Mensaje a enviar
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado del envío
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSerializacion` - Si no se pudo codificar el mensaje
+    ///
+    /// `CustomError::ErrorEnviarMensaje` - Si la conexión ya se cerró
+    pub fn enviar(&self, mensaje: &Mensaje) -> Result<(), CustomError> {
+        match &self.salida {
+            Salida::Mensajes(salida) => salida
+                .send(mensaje.clone())
+                .map_err(|_| CustomError::ErrorEnviarMensaje),
+            Salida::Tramas(salida) => {
+                let codec = if mensaje.es_saludo() {
+                    Codec::Json
+                } else {
+                    self.protocolo.codec()
+                };
+                salida
+                    .send(codec.codificar(mensaje)?)
+                    .map_err(|_| CustomError::ErrorEnviarMensaje)
+            }
+        }
+    }
+
+    /// Función que espera el siguiente mensaje del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<Mensaje, CustomError>` - Mensaje recibido
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoMensaje` - Si la conexión se cerró
+    pub async fn recibir(&mut self) -> Result<Mensaje, CustomError> {
+        self.entrada
+            .recv()
+            .await
+            .ok_or(CustomError::ErrorRecibiendoMensaje)
+    }
+
+    /// Función que espera el siguiente mensaje del jugador durante un tiempo limitado
+    ///
+    /// # Args
+    ///
+    /// `plazo` - Tiempo máximo de espera
+    ///
+    /// # Returns
+    ///
+    /// `Result<Option<Mensaje>, CustomError>` - Mensaje recibido, o `None` si venció el plazo
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoMensaje` - Si la conexión se cerró
+    pub async fn recibir_con_plazo(
+        &mut self,
+        plazo: Duration,
+    ) -> Result<Option<Mensaje>, CustomError> {
+        match time::timeout(plazo, self.entrada.recv()).await {
+            Ok(Some(mensaje)) => Ok(Some(mensaje)),
+            Ok(None) => Err(CustomError::ErrorRecibiendoMensaje),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Función que obtiene el siguiente mensaje ya recibido, sin esperar
+    ///
+    /// # Returns
+    ///
+    /// `Option<Mensaje>` - Mensaje pendiente, si lo hay
+    pub fn pendiente(&mut self) -> Option<Mensaje> {
+        self.entrada.try_recv().ok()
+    }
+
+    /// Función que indica si el jugador cerró la conexión y ya se leyó todo lo que envió
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si no van a llegar más mensajes por el enlace
+    pub fn cerrado(&self) -> bool {
+        self.entrada.is_closed() && self.entrada.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conexion::{Conexion, ConexionTcp};
+    use crate::protocolo::BINARIO;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_enlace_par() {
+        let (servidor, mut cliente) = Enlace::par();
+        servidor.enviar(&Mensaje::Puntos(3)).unwrap();
+        assert!(matches!(
+            cliente.recibir().await.unwrap(),
+            Mensaje::Puntos(3)
+        ));
+        assert!(cliente
+            .recibir_con_plazo(Duration::from_millis(10))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!cliente.cerrado());
+        drop(servidor);
+        assert!(cliente.cerrado());
+        assert!(cliente.recibir().await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enlace_con_cliente_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let direccion = listener.local_addr().unwrap();
+        let cliente = tokio::task::spawn_blocking(move || {
+            let cliente =
+                ConexionTcp::new(std::net::TcpStream::connect(direccion).unwrap()).unwrap();
+            let mensaje = cliente.recibir().unwrap();
+            cliente.enviar(&Mensaje::Pong).unwrap();
+            mensaje
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut enlace = Enlace::desde_socket(stream);
+
+        enlace.enviar(&Mensaje::Ping).unwrap();
+        assert!(matches!(enlace.recibir().await.unwrap(), Mensaje::Pong));
+        assert!(matches!(cliente.await.unwrap(), Mensaje::Ping));
+        assert!(enlace.recibir().await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enlace_cambia_a_codec_binario() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let direccion = listener.local_addr().unwrap();
+        let cliente = tokio::task::spawn_blocking(move || {
+            let cliente =
+                ConexionTcp::new(std::net::TcpStream::connect(direccion).unwrap()).unwrap();
+            let bienvenida = cliente.recibir().unwrap();
+            cliente.usar_codec(Codec::Binario);
+            let puntos = cliente.recibir().unwrap();
+            cliente.enviar(&Mensaje::Pong).unwrap();
+            (bienvenida, puntos)
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut enlace = Enlace::desde_socket(stream);
+        enlace.acordar_protocolo(Protocolo::actual());
+
+        enlace
+            .enviar(&Mensaje::Bienvenida {
+                version: 2,
+                capacidades: vec![BINARIO.to_string()],
+            })
+            .unwrap();
+        enlace.enviar(&Mensaje::Puntos(7)).unwrap();
+        assert_eq!(enlace.recibir().await.unwrap(), Mensaje::Pong);
+        let (bienvenida, puntos) = cliente.await.unwrap();
+        assert!(matches!(bienvenida, Mensaje::Bienvenida { .. }));
+        assert_eq!(puntos, Mensaje::Puntos(7));
+    }
+}
@@ -0,0 +1,162 @@
+use barcos::tipo_barco::TipoBarco;
+
+use crate::arma::TipoArma;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Enumeración que representa los hechos del juego que resultan de aplicar una instrucción
+pub enum Evento {
+    BarcoGolpeado {
+        jugador: usize,
+        coordenadas: (i32, i32),
+    },
+    BarcoHundido {
+        jugador: usize,
+        coordenadas: (i32, i32),
+    },
+    ResultadoAtaque {
+        jugador: usize,
+        coordenadas: (i32, i32),
+        puntos: usize,
+        monedas: usize,
+    },
+    ResultadoBomba {
+        jugador: usize,
+        centro: (i32, i32),
+        impactos: Vec<(i32, i32)>,
+        puntos: usize,
+        monedas: usize,
+    },
+    ResultadoTorpedo {
+        jugador: usize,
+        impacto: Option<(i32, i32)>,
+        puntos: usize,
+        monedas: usize,
+    },
+    ResultadoSonar {
+        jugador: usize,
+        centro: (i32, i32),
+        detectado: bool,
+    },
+    JugadorEliminado {
+        jugador: usize,
+        nombre: String,
+        puntos: usize,
+    },
+    TiendaSolicitada {
+        jugador: usize,
+    },
+    CompraRealizada {
+        jugador: usize,
+        tipo: TipoBarco,
+        monedas: usize,
+    },
+    CompraRechazada {
+        jugador: usize,
+        motivo: String,
+    },
+    ArmaComprada {
+        jugador: usize,
+        arma: TipoArma,
+        cantidad: usize,
+        monedas: usize,
+    },
+    BarcoReparado {
+        jugador: usize,
+        barco: usize,
+        coordenadas: (i32, i32),
+        monedas: usize,
+    },
+    BarcoMovido {
+        jugador: usize,
+        barco: usize,
+        posiciones: Vec<(i32, i32)>,
+    },
+    TurnoSaltado {
+        jugador: usize,
+    },
+    RankingSolicitado {
+        jugador: usize,
+    },
+    AccionInvalida {
+        jugador: usize,
+        motivo: String,
+    },
+    PremioObtenido {
+        jugador: usize,
+        monedas: usize,
+    },
+    Ganador {
+        jugador: usize,
+        nombre: String,
+        puntos: usize,
+    },
+}
+
+impl Evento {
+    /// Función que indica si el evento da por terminado el turno del jugador
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Falso si el jugador debe volver a elegir una acción
+    pub fn finaliza_turno(&self) -> bool {
+        !matches!(
+            self,
+            Evento::AccionInvalida { .. }
+                | Evento::TiendaSolicitada { .. }
+                | Evento::CompraRechazada { .. }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Enumeración que representa los hechos de la partida que se transmiten a los espectadores
+///
+/// A diferencia de `Evento`, identifica a los jugadores por su nombre y no
+/// incluye nada que solo deba ver el jugador involucrado, como sus monedas o
+/// la posición de sus barcos.
+pub enum EventoPublico {
+    Ataque {
+        atacante: String,
+        coordenadas: (i32, i32),
+        puntos: usize,
+    },
+    BarcoGolpeado {
+        jugador: String,
+        coordenadas: (i32, i32),
+    },
+    BarcoHundido {
+        jugador: String,
+        coordenadas: (i32, i32),
+    },
+    JugadorEliminado {
+        jugador: String,
+    },
+    Compra {
+        jugador: String,
+        tipo: TipoBarco,
+    },
+    EventoSorpresa,
+    PremioObtenido {
+        jugador: String,
+        monedas: usize,
+    },
+    Ganador {
+        jugador: String,
+        puntos: usize,
+    },
+    Reparacion {
+        jugador: String,
+        coordenadas: (i32, i32),
+    },
+    Bombardeo {
+        atacante: String,
+        centro: (i32, i32),
+        puntos: usize,
+    },
+    Torpedo {
+        atacante: String,
+        impacto: Option<(i32, i32)>,
+        puntos: usize,
+    },
+}
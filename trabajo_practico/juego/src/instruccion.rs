@@ -1,11 +1,24 @@
-use serde::{Deserialize, Serialize};
+use barcos::{orientacion::Orientacion, tipo_barco::TipoBarco};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::arma::{Direccion, TipoArma};
+use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Instruccion {
     Ataque(usize, (i32, i32)),
+    /// Mueve el barco sin cambiar su orientación
     Movimiento(usize, (i32, i32)),
-    Compra(usize),
+    Tienda,
+    /// Compra un barco y lo ubica con una orientación al azar
+    Compra(TipoBarco),
     Saltar,
     Ranking,
+    Rotacion(usize),
+    MovimientoOrientado(usize, (i32, i32), Orientacion),
+    CompraOrientada(TipoBarco, Orientacion),
+    Reparacion(usize),
+    CompraArma(TipoArma),
+    Bomba((i32, i32)),
+    Torpedo(usize, Direccion),
+    Sonar((i32, i32)),
 }
@@ -1,15 +1,15 @@
-use std::collections::HashMap;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufReader, BufWriter};
-use std::sync::Once;
-use std::{io::Write, net::TcpStream, sync::MutexGuard};
-
-use crate::juego::CustomError::AccionInvalida;
 use crate::{
-    instruccion::Instruccion, jugador::Jugador, mapa::Mapa, mensaje::Mensaje, server::Server,
+    arma::{Direccion, TipoArma},
+    configuracion::ConfiguracionServidor,
+    evento::Evento,
+    instruccion::Instruccion,
+    jugador::Jugador,
+    mapa::Mapa,
+    reglas::Reglas,
+    tienda,
+    tienda::Producto,
 };
-use barcos::estado_barco::EstadoBarco;
-use libreria::constantes::EVENTO_SORPRESA;
+use barcos::{estado_barco::EstadoBarco, orientacion::Orientacion, tipo_barco::TipoBarco};
 use libreria::custom_error::CustomError;
 
 #[derive(Clone)]
@@ -17,6 +17,7 @@ pub struct Juego {
     pub mapa: Mapa,
     pub jugadores: Vec<Jugador>,
     pub turno: usize,
+    pub reglas: Reglas,
 }
 
 impl Juego {
@@ -31,293 +32,213 @@ impl Juego {
     /// `Juego` - Juego creado
     ///
     pub fn new(numero_jugadores: usize) -> Juego {
-        let mut mapa = Mapa::new();
+        Self::con_configuracion(numero_jugadores, &ConfiguracionServidor::default())
+    }
+
+    /// Función que crea un nuevo juego según la configuración del servidor
+    ///
+    /// # Args
+    ///
+    /// `numero_jugadores` - Número de jugadores que participarán en el juego
+    ///
+    /// `configuracion` - Configuración con las dimensiones del mapa y las reglas de la partida
+    ///
+    /// # Returns
+    ///
+    /// `Juego` - Juego creado
+    pub fn con_configuracion(
+        numero_jugadores: usize,
+        configuracion: &ConfiguracionServidor,
+    ) -> Juego {
+        let mut mapa = Mapa::con_dimensiones(configuracion.filas, configuracion.columnas);
+        let reglas = configuracion.reglas.clone();
         let mut jugadores = Vec::new();
         for _ in 0..numero_jugadores {
-            jugadores.push(Jugador::new(jugadores.len(), "".to_string(), &mut mapa));
+            jugadores.push(Jugador::new(
+                jugadores.len(),
+                "".to_string(),
+                &reglas,
+                &mut mapa,
+            ));
         }
         let turno = 0;
         Juego {
             mapa,
             jugadores,
             turno,
+            reglas,
         }
     }
 
-    /// Función que inicia el juego
+    /// Función que aplica la instrucción de un jugador sobre el estado del juego
     ///
-    /// # Returns
+    /// Si la instrucción es válida se pasa el turno al siguiente jugador con
+    /// barcos. Si no lo es, se devuelve `Evento::AccionInvalida` y el turno
+    /// no cambia.
     ///
-    /// `Result<(), CustomError>` - Resultado de la ejecución
+    /// # Args
     ///
-    /// # Errors
+    /// `jugador` - ID del jugador que realiza la instrucción
+    ///
+    /// `instruccion` - Instrucción a aplicar
+    ///
+    /// # Returns
     ///
-    /// `CustomError` - Error personalizado
-    pub fn iniciar_juego(&mut self, server: &mut Server) -> Result<(), CustomError> {
-        let mut rondas = 0;
-        let mut server_clone = server.clone();
+    /// `Vec<Evento>` - Eventos producidos por la instrucción
+    pub fn aplicar(&mut self, jugador: usize, instruccion: Instruccion) -> Vec<Evento> {
+        if self.finalizo() {
+            return Self::accion_invalida(jugador, "La partida ya finalizó.");
+        }
+        if self.jugador_en_turno() != Some(jugador) {
+            return Self::accion_invalida(jugador, "No es tu turno.");
+        }
+        let indice = self.turno;
 
-        while self.finalizo().is_ok() {
-            let jugadores_con_barcos: Vec<_> = self
-                .jugadores
-                .iter()
-                .filter(|j| !j.barcos.is_empty())
-                .collect();
-            if jugadores_con_barcos.len() == 1 {
-                println!("El ganador es: {}", jugadores_con_barcos[0].nombre_usuario);
-                let mensaje = Mensaje::Ganaste(jugadores_con_barcos[0].puntos);
-                let mensaje_serializado = serde_json::to_string(&mensaje).unwrap();
-                for (id, conexion) in &server_clone.conexiones_jugadores {
-                    if *id == jugadores_con_barcos[0].id {
-                        let conexion = conexion.lock().unwrap();
-                        let _ = Self::enviar_mensaje(
-                            &conexion,
-                            mensaje_serializado.as_bytes().to_vec(),
-                        );
-                    }
-                }
-                return Ok(());
+        let eventos = match instruccion {
+            Instruccion::Movimiento(barco_id, cordenadas) => {
+                self.procesar_movimiento(indice, barco_id, cordenadas, None)
             }
-
-            if rondas == EVENTO_SORPRESA {
-                let _ = server_clone.crear_evento_sorpresa(&mut self.jugadores);
+            Instruccion::MovimientoOrientado(barco_id, cordenadas, orientacion) => {
+                self.procesar_movimiento(indice, barco_id, cordenadas, Some(orientacion))
             }
-
-            while self.jugadores[self.turno].barcos.is_empty() {
-                self.turno = (self.turno + 1) % self.jugadores.len();
+            Instruccion::Rotacion(barco_id) => self.procesar_rotacion(indice, barco_id),
+            Instruccion::Ataque(_barco_id, coordenadas_ataque) => {
+                self.procesar_ataque(indice, coordenadas_ataque)
             }
-
-            println!(
-                "Turno del jugador {}",
-                self.jugadores[self.turno].nombre_usuario
-            );
-            println!(
-                "Cantidad de jugadores con barcos: {:?}",
-                self.jugadores
-                    .iter()
-                    .filter(|j| !j.barcos.is_empty())
-                    .count()
-            );
-
-            let mut server_clone = server_clone.clone();
-            if let Some(conexion) = server_clone
-                .conexiones_jugadores
-                .get(&self.jugadores[self.turno].id)
-            {
-                let conexion = conexion.lock().unwrap();
-                let mensaje_serializado = serde_json::to_string(&Mensaje::RealiceAccion).unwrap();
-                Self::enviar_mensaje(&conexion, mensaje_serializado.as_bytes().to_vec())?;
+            Instruccion::Saltar => vec![Evento::TurnoSaltado { jugador }],
+            Instruccion::Tienda => vec![Evento::TiendaSolicitada { jugador }],
+            Instruccion::Compra(tipo) => self.procesar_compra(indice, tipo, None),
+            Instruccion::CompraOrientada(tipo, orientacion) => {
+                self.procesar_compra(indice, tipo, Some(orientacion))
             }
-
-            self.jugadores[self.turno].manejar_turno(&server_clone);
-
-            loop {
-                match server_clone.recibir_mensaje(self.jugadores[self.turno].id) {
-                    Ok(mensaje_serializado) => {
-                        match serde_json::from_str::<Mensaje>(&mensaje_serializado) {
-                            Ok(mensaje) => {
-                                if let Mensaje::Accion(instruccion, monedas) = mensaje {
-                                    if let Some(conexion) = server_clone
-                                        .conexiones_jugadores
-                                        .get(&self.jugadores[self.turno].id)
-                                    {
-                                        let mut conexion = conexion.lock().unwrap();
-                                        let mut server_mut = server_clone.clone();
-                                        let mut self_clone = self.clone();
-                                        match self_clone.manejar_instruccion(
-                                            instruccion,
-                                            self.turno,
-                                            &mut conexion,
-                                            &mut self.jugadores,
-                                            &mut server_mut,
-                                            monedas,
-                                        ) {
-                                            Ok(_) => break,
-                                            Err(_) => {
-                                                return Err(
-                                                    CustomError::ErrorRecibiendoInstruccion,
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                return Err(CustomError::ErrorSerializacion);
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        return Err(CustomError::ErrorAceptandoConexion);
-                    }
-                }
+            Instruccion::Reparacion(barco_id) => self.procesar_reparacion(indice, barco_id),
+            Instruccion::CompraArma(arma) => self.procesar_compra_arma(indice, arma),
+            Instruccion::Bomba(centro) => self.procesar_bomba(indice, centro),
+            Instruccion::Torpedo(barco_id, direccion) => {
+                self.procesar_torpedo(indice, barco_id, direccion)
             }
+            Instruccion::Sonar(centro) => self.procesar_sonar(indice, centro),
+            Instruccion::Ranking => vec![Evento::RankingSolicitado { jugador }],
+        };
 
-            self.jugadores[self.turno].enviar_instrucciones(&server_clone);
-            self.turno = (self.turno + 1) % self.jugadores.len();
-            rondas += 1;
-        }
-
-        match self.finalizo() {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e),
+        if eventos.iter().all(Evento::finaliza_turno) {
+            self.avanzar_turno();
         }
+        eventos
     }
 
-    fn mostrar_ranking(conexion: &mut MutexGuard<'_, TcpStream>) -> Result<(), CustomError> {
-        let file_path = "../archivos/ranking.json";
-        let file = File::open(file_path).map_err(|_| CustomError::ErrorMostrandoRanking)?;
-        let reader = BufReader::new(file);
-
-        let rankings: HashMap<String, usize> = serde_json::from_reader(reader).unwrap_or_default();
-
-        let mut ranking_sorted: Vec<_> = rankings.iter().collect();
-        ranking_sorted.sort_by_key(|&(_, puntos)| std::cmp::Reverse(*puntos));
-
-        let ranking_vec: Vec<(String, usize)> = ranking_sorted
-            .into_iter()
-            .map(|(nombre, puntos)| (nombre.clone(), *puntos))
-            .collect();
-
-        let mensaje = Mensaje::Ranking(ranking_vec);
-
-        let mensaje_serializado =
-            serde_json::to_string(&mensaje).map_err(|_| CustomError::ErrorSerializacion)?;
-
-        Self::enviar_mensaje(conexion, mensaje_serializado.into_bytes())
-    }
-    /// Función que maneja una instrucción
+    /// Función que resuelve el evento sorpresa
     ///
     /// # Args
     ///
-    /// `instruccion` - Instrucción a manejar
+    /// `ganador` - ID del jugador que reclamó primero el premio, si lo hubo
     ///
-    /// `jugador_actual` - Jugador que realiza la instrucción
+    /// `perdedores` - IDs de los jugadores que no lo reclamaron a tiempo
     ///
-    /// `conexion` - Conexión del jugador
+    /// # Returns
     ///
-    /// `jugadores` - Vector de jugadores
+    /// `Vec<Evento>` - Eventos producidos por el evento sorpresa
+    pub fn resolver_evento_sorpresa(
+        &mut self,
+        ganador: Option<usize>,
+        perdedores: &[usize],
+    ) -> Vec<Evento> {
+        let mut eventos = Vec::new();
+        let premio = self.reglas.premio_evento_sorpresa;
+        if let Some(jugador) = ganador.and_then(|id| self.obtener_jugador_mut(id)) {
+            jugador.monedas += premio;
+            eventos.push(Evento::PremioObtenido {
+                jugador: jugador.id,
+                monedas: premio,
+            });
+        }
+
+        for jugador in self
+            .jugadores
+            .iter_mut()
+            .filter(|j| perdedores.contains(&j.id) && !j.ha_perdido)
+        {
+            jugador.procesar_ataque(
+                self.reglas.coordenadas_bomba,
+                &self.reglas,
+                &mut self.mapa,
+                &mut eventos,
+            );
+            Self::verificar_eliminacion(jugador, &mut eventos);
+        }
+
+        if !self.jugadores.is_empty() && !self.esta_en_juego(self.turno) {
+            self.avanzar_turno();
+        }
+        self.verificar_ganador(&mut eventos);
+        eventos
+    }
+
+    /// Función que indica si el juego finalizó
     ///
-    /// `server` - Servidor en el que se encuentra el juego
+    /// # Returns
     ///
-    /// `monedas` - Monedas del jugador
+    /// `bool` - Verdadero si queda a lo sumo un jugador con barcos
+    pub fn finalizo(&self) -> bool {
+        self.jugadores.iter().filter(|j| j.esta_vivo()).count() <= 1
+    }
+
+    /// Función que obtiene el jugador que debe realizar la próxima acción
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Resultado de la ejecución
-    fn manejar_instruccion(
-        &mut self,
-        instruccion: Instruccion,
-        jugador_actual: usize,
-        conexion: &mut MutexGuard<'_, TcpStream>,
-        jugadores: &mut [Jugador],
-        server: &mut Server,
-        monedas: usize,
-    ) -> Result<(), CustomError> {
-        match instruccion {
-            Instruccion::Movimiento(barco_id, cordenadas) => {
-                Self::procesar_movimiento(
-                    barco_id,
-                    cordenadas,
-                    jugador_actual,
-                    jugadores,
-                    conexion,
-                )?;
-            }
-            Instruccion::Ataque(_barco_id, coordenadas_ataque) => {
-                let pierde = Self::procesar_ataque(
-                    coordenadas_ataque,
-                    jugador_actual,
-                    jugadores,
-                    server,
-                    conexion,
-                );
-                if pierde {
-                    self.eliminar_jugador(jugadores[jugador_actual].id);
-                    server
-                        .conexiones_jugadores
-                        .remove(&jugadores[jugador_actual].id);
-                }
-            }
-
-            Instruccion::Saltar => {
-                println!("Jugador salta su turno.");
-            }
-            Instruccion::Compra(barco_elegido) => {
-                Self::abrir_tienda(jugadores, jugador_actual, barco_elegido);
-                jugadores[jugador_actual].monedas -= monedas;
-                let mensaje =
-                    Mensaje::CompraExitosa(jugadores[jugador_actual].monedas, barco_elegido);
-                let mensaje_serializado = serde_json::to_string(&mensaje).unwrap();
-                Self::enviar_mensaje(conexion, mensaje_serializado.as_bytes().to_vec()).unwrap();
-                match barco_elegido {
-                    0 => {
-                        println!(
-                            "El jugador {} ha comprado una fragata",
-                            jugadores[jugador_actual].nombre_usuario
-                        );
-                    }
-                    1 => {
-                        println!(
-                            "El jugador {} ha comprado un buque",
-                            jugadores[jugador_actual].nombre_usuario
-                        );
-                    }
-                    2 => {
-                        println!(
-                            "El jugador {} ha comprado un acorazado",
-                            jugadores[jugador_actual].nombre_usuario
-                        );
-                    }
-                    _ => {}
-                }
-            }
-            Instruccion::Ranking => {
-                if Self::mostrar_ranking(conexion).is_err() {
-                    return Err(CustomError::ErrorMostrandoRanking);
-                }
-            }
+    /// `Option<usize>` - ID del jugador en turno, si queda alguno con barcos
+    pub fn jugador_en_turno(&self) -> Option<usize> {
+        if self.esta_en_juego(self.turno) {
+            Some(self.jugadores[self.turno].id)
+        } else {
+            None
         }
-        Ok(())
     }
 
-    /// Función que finaliza el juego
+    /// Función que obtiene un jugador a partir de su ID
     ///
     /// # Args
     ///
-    /// `server` - Servidor en el que se encuentra el juego
+    /// `id_jugador` - ID del jugador
     ///
     /// # Returns
     ///
-    /// `Result<bool, CustomError>` - Result con booleano que indica si el juego finalizó o error
-    fn finalizo(&self) -> Result<bool, CustomError> {
-        static ONCE_FLAG: Once = Once::new();
+    /// `Option<&Jugador>` - Jugador encontrado
+    pub fn obtener_jugador(&self, id_jugador: usize) -> Option<&Jugador> {
+        self.jugadores.iter().find(|j| j.id == id_jugador)
+    }
 
-        let jugadores_con_barcos = self
-            .jugadores
-            .iter()
-            .filter(|j| !j.barcos.is_empty())
-            .count();
-
-        if jugadores_con_barcos <= 1 {
-            if let Some(jugador) = self.jugadores.iter().find(|j| !j.barcos.is_empty()) {
-                self.actualizar_ranking()
-                    .map_err(|_| CustomError::ErrorMostrandoRanking)?;
-
-                let _ganador = &jugador.nombre_usuario;
-
-                ONCE_FLAG.call_once(|| {
-                    println!("Juego terminado");
-                });
-            } else {
-                ONCE_FLAG.call_once(|| {
-                    println!("No hay ganadores.");
-                });
-            }
-            return Ok(true);
-        }
+    fn obtener_jugador_mut(&mut self, id_jugador: usize) -> Option<&mut Jugador> {
+        self.jugadores.iter_mut().find(|j| j.id == id_jugador)
+    }
 
-        Ok(false)
+    /// Función que ubica la flota de un jugador donde él la eligió
+    ///
+    /// # Args
+    ///
+    /// `id_jugador` - ID del jugador
+    ///
+    /// `ubicaciones` - ID de cada barco con su origen y su orientación
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si se ubicaron todos los barcos
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorUbicacionInvalida` - Si alguna ubicación no es válida o el jugador no está en el juego
+    pub fn desplegar_flota(
+        &mut self,
+        id_jugador: usize,
+        ubicaciones: &[(usize, (i32, i32), Orientacion)],
+    ) -> Result<(), CustomError> {
+        let jugador = self
+            .jugadores
+            .iter_mut()
+            .find(|j| j.id == id_jugador)
+            .ok_or(CustomError::ErrorUbicacionInvalida)?;
+        jugador.desplegar_flota(ubicaciones, &mut self.mapa)
     }
 
     /// Función que agrega un jugador al juego
@@ -330,8 +251,12 @@ impl Juego {
     ///
     /// `Jugador` - Jugador agregado
     pub fn agregar_jugador(&mut self, id_jugador: usize, nombre: String) {
-        self.jugadores
-            .push(Jugador::new(id_jugador, nombre, &mut self.mapa));
+        self.jugadores.push(Jugador::new(
+            id_jugador,
+            nombre,
+            &self.reglas,
+            &mut self.mapa,
+        ));
     }
     /// Función que elimina un jugador del juego
     ///
@@ -345,209 +270,613 @@ impl Juego {
     ///
     /// `()` - No retorna nada
     pub fn eliminar_jugador(&mut self, id_jugador: usize) {
+        if let Some(jugador) = self.obtener_jugador(id_jugador) {
+            let posiciones: Vec<(i32, i32)> = jugador
+                .barcos
+                .iter()
+                .flat_map(|barco| barco.posiciones.clone())
+                .collect();
+            self.mapa.liberar_posiciones(&posiciones);
+        }
         self.jugadores.retain(|j| j.id != id_jugador);
     }
-    /// Función que abre la tienda para un jugador
+
+    /// Función que saca de la partida a un jugador que la abandonó
+    ///
+    /// A diferencia de `eliminar_jugador`, el jugador sigue en la lista para
+    /// que sus puntos lleguen al ranking; solo pierde sus barcos.
     ///
     /// # Args
     ///
-    /// `jugadores` - Vector de jugadores
+    /// `id_jugador` - ID del jugador que abandonó
     ///
-    /// `jugador_actual` - Jugador que abre la tienda
+    /// # Returns
     ///
-    /// `barco` - Barco a comprar
+    /// `Vec<Evento>` - Eventos producidos por el abandono
+    pub fn abandonar(&mut self, id_jugador: usize) -> Vec<Evento> {
+        let mut eventos = Vec::new();
+        let Some(indice) = self.jugadores.iter().position(|j| j.id == id_jugador) else {
+            return eventos;
+        };
+        let posiciones: Vec<(i32, i32)> = self.jugadores[indice]
+            .barcos
+            .iter()
+            .flat_map(|barco| barco.posiciones.clone())
+            .collect();
+        self.mapa.liberar_posiciones(&posiciones);
+        let jugador = &mut self.jugadores[indice];
+        jugador.barcos.clear();
+        Self::verificar_eliminacion(jugador, &mut eventos);
+
+        if !self.esta_en_juego(self.turno) {
+            self.avanzar_turno();
+        }
+        self.verificar_ganador(&mut eventos);
+        eventos
+    }
+
+    fn esta_en_juego(&self, indice: usize) -> bool {
+        self.jugadores
+            .get(indice)
+            .is_some_and(|j| j.esta_vivo() && !j.ha_perdido)
+    }
+
+    /// Función que pasa el turno al siguiente jugador con barcos
     ///
     /// # Returns
     ///
     /// `()` - No retorna nada
-    fn abrir_tienda(jugadores: &mut [Jugador], jugador_actual: usize, barco: usize) {
-        jugadores[jugador_actual].agregar_barco(barco);
+    fn avanzar_turno(&mut self) {
+        for _ in 0..self.jugadores.len() {
+            self.turno = (self.turno + 1) % self.jugadores.len();
+            if self.esta_en_juego(self.turno) {
+                return;
+            }
+        }
+    }
+
+    fn accion_invalida(jugador: usize, motivo: &str) -> Vec<Evento> {
+        vec![Evento::AccionInvalida {
+            jugador,
+            motivo: motivo.to_owned(),
+        }]
+    }
+
+    /// Función que procesa la compra de un barco
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que compra
+    ///
+    /// `tipo` - Clase de barco a comprar
+    ///
+    /// `orientacion` - Orientación con la que se ubica el barco comprado; si no se indica, se elige al azar
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por la compra
+    fn procesar_compra(
+        &mut self,
+        jugador_actual: usize,
+        tipo: TipoBarco,
+        orientacion: Option<Orientacion>,
+    ) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        let Some(articulo) = tienda::obtener_articulo(tipo) else {
+            return Self::compra_rechazada(jugador.id, "El barco seleccionado no está a la venta.");
+        };
+        let Some(monedas) = jugador.monedas.checked_sub(articulo.precio) else {
+            return Self::compra_rechazada(
+                jugador.id,
+                "No cuenta con el dinero suficiente para comprar ese barco",
+            );
+        };
+        if !jugador.agregar_barco(tipo, orientacion, &mut self.mapa) {
+            return Self::compra_rechazada(jugador.id, "No hay lugar en el mapa para ese barco.");
+        }
+        jugador.monedas = monedas;
+        vec![Evento::CompraRealizada {
+            jugador: jugador.id,
+            tipo,
+            monedas,
+        }]
+    }
+
+    /// Función que procesa la compra de una reparación para un barco
+    ///
+    /// Se repara el casillero dañado más antiguo del barco; si era el único,
+    /// el barco vuelve a estar sano.
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que compra la reparación
+    ///
+    /// `barco_id` - ID del barco a reparar
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por la reparación
+    fn procesar_reparacion(&mut self, jugador_actual: usize, barco_id: usize) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        let Some(articulo) = tienda::obtener_producto(Producto::Reparacion, &self.reglas) else {
+            return Self::compra_rechazada(jugador.id, "Las reparaciones no están a la venta.");
+        };
+        let Some(barco) = jugador.obtener_barco(barco_id) else {
+            return Self::compra_rechazada(jugador.id, "El barco seleccionado no existe.");
+        };
+        if barco.dañadas.is_empty() {
+            return Self::compra_rechazada(
+                jugador.id,
+                "El barco seleccionado no tiene daños para reparar.",
+            );
+        }
+        let Some(monedas) = jugador.monedas.checked_sub(articulo.precio) else {
+            return Self::compra_rechazada(
+                jugador.id,
+                "No cuenta con el dinero suficiente para reparar ese barco",
+            );
+        };
+        let Some(coordenadas) = jugador.reparar_barco(barco_id, &mut self.mapa) else {
+            return Self::compra_rechazada(
+                jugador.id,
+                "El barco seleccionado no tiene daños para reparar.",
+            );
+        };
+        jugador.monedas = monedas;
+        vec![Evento::BarcoReparado {
+            jugador: jugador.id,
+            barco: barco_id,
+            coordenadas,
+            monedas,
+        }]
+    }
+
+    /// Función que procesa la compra de un arma especial
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que compra el arma
+    ///
+    /// `arma` - Arma a comprar
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por la compra
+    fn procesar_compra_arma(&mut self, jugador_actual: usize, arma: TipoArma) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        let Some(articulo) = tienda::obtener_producto(Producto::Arma(arma), &self.reglas) else {
+            return Self::compra_rechazada(jugador.id, "El arma seleccionada no está a la venta.");
+        };
+        let Some(monedas) = jugador.monedas.checked_sub(articulo.precio) else {
+            return Self::compra_rechazada(
+                jugador.id,
+                "No cuenta con el dinero suficiente para comprar esa arma",
+            );
+        };
+        jugador.monedas = monedas;
+        jugador.armas.push(arma);
+        vec![Evento::ArmaComprada {
+            jugador: jugador.id,
+            arma,
+            cantidad: jugador
+                .armas
+                .iter()
+                .filter(|&&propia| propia == arma)
+                .count(),
+            monedas,
+        }]
+    }
+
+    fn compra_rechazada(jugador: usize, motivo: &str) -> Vec<Evento> {
+        vec![Evento::CompraRechazada {
+            jugador,
+            motivo: motivo.to_owned(),
+        }]
     }
 
     /// Función que procesa un movimiento en el mapa
     ///
     /// # Args
     ///
-    /// `movimiento` - Movimiento a procesar
+    /// `jugador_actual` - Índice del jugador que mueve el barco
     ///
-    /// `jugadores` - Vector de jugadores
+    /// `barco_id` - ID del barco a mover
+    ///
+    /// `cordenadas` - Coordenadas de destino
+    ///
+    /// `orientacion` - Orientación del barco en el destino; si no se indica, conserva la actual
     ///
     /// # Returns
     ///
-    /// `Jugador` - Jugador con el movimiento procesado
+    /// `Vec<Evento>` - Eventos producidos por el movimiento
     fn procesar_movimiento(
+        &mut self,
+        jugador_actual: usize,
         barco_id: usize,
         cordenadas: (i32, i32),
-        jugador_actual: usize,
-        jugadores: &mut [Jugador],
-        conexion: &mut MutexGuard<'_, TcpStream>,
-    ) -> Result<(), CustomError> {
-        let barco = jugadores[jugador_actual].obtener_barco(barco_id);
+        orientacion: Option<Orientacion>,
+    ) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        let Some(barco) = jugador.obtener_barco(barco_id) else {
+            return Self::accion_invalida(jugador.id, "El barco seleccionado no existe.");
+        };
         if barco.estado == EstadoBarco::Golpeado || barco.estado == EstadoBarco::Hundido {
-            let mensaje = "El barco seleccionado esta golpeado, no se puede mover, elija otra accion u otro barco.";
-            let mensaje_serializado = serde_json::to_string(&Mensaje::RepetirAccion(
-                mensaje.to_owned(),
-                jugadores[jugador_actual]
-                    .mapa
-                    .serializar_barcos(&jugadores[jugador_actual].barcos),
-                jugadores[jugador_actual].monedas,
-            ))
-            .unwrap();
-            Self::enviar_mensaje(conexion, mensaje_serializado.as_bytes().to_vec())?;
-            return Err(AccionInvalida);
+            return Self::accion_invalida(
+                jugador.id,
+                "El barco seleccionado esta golpeado, no se puede mover, elija otra accion u otro barco.",
+            );
         }
-        let coordenadas_contiguas = jugadores[jugador_actual]
-            .mapa
-            .obtener_coordenadas_contiguas(cordenadas, barco.tamaño);
+        let Some(origen) = barco.origen() else {
+            return Self::accion_invalida(jugador.id, "El barco seleccionado no existe.");
+        };
+        let orientacion = orientacion.unwrap_or(barco.orientacion);
+        let velocidad = self.reglas.velocidad(barco.tipo);
+        let distancia = (cordenadas.0 - origen.0).unsigned_abs() as usize
+            + (cordenadas.1 - origen.1).unsigned_abs() as usize;
+        if distancia > velocidad {
+            return Self::accion_invalida(
+                jugador.id,
+                &format!(
+                    "El destino está fuera de alcance: un {} puede moverse hasta {} casilleros.",
+                    barco.tipo.nombre(),
+                    velocidad
+                ),
+            );
+        }
+
+        // El barco puede ocupar casilleros que hoy ocupa él mismo. Si cambia
+        // de orientación, primero gira sobre su origen y después avanza
+        let posiciones_actuales = barco.posiciones.clone();
+        self.mapa.liberar_posiciones(&posiciones_actuales);
+        let coordenadas_contiguas =
+            self.mapa
+                .obtener_coordenadas_contiguas(cordenadas, barco.tamaño(), orientacion);
+        let hay_camino =
+            self.mapa
+                .hay_camino(origen, cordenadas, barco.tamaño(), orientacion, velocidad);
+        self.mapa
+            .ocupar_posiciones(jugador.id.to_string(), &posiciones_actuales);
         if coordenadas_contiguas.is_empty() {
-            let mensaje = "No hay suficientes espacios contiguos disponibles para mover el barco.";
-            let mensaje_serializado = serde_json::to_string(&Mensaje::RepetirAccion(
-                mensaje.to_owned(),
-                jugadores[jugador_actual]
-                    .mapa
-                    .serializar_barcos(&jugadores[jugador_actual].barcos),
-                jugadores[jugador_actual].monedas,
-            ))
-            .unwrap();
-            Self::enviar_mensaje(conexion, mensaje_serializado.as_bytes().to_vec())?;
-            return Err(AccionInvalida);
+            return Self::accion_invalida(
+                jugador.id,
+                "No hay suficientes espacios contiguos disponibles para mover el barco.",
+            );
+        }
+        if !hay_camino {
+            return Self::accion_invalida(
+                jugador.id,
+                "No hay un camino libre hasta el destino: el barco no puede atravesar otros barcos.",
+            );
         }
 
-        jugadores[jugador_actual].actualizar_posicion_barco(coordenadas_contiguas, barco_id);
+        jugador.actualizar_posicion_barco(
+            coordenadas_contiguas,
+            barco_id,
+            orientacion,
+            &mut self.mapa,
+        );
 
-        Ok(())
+        let posiciones = jugador
+            .obtener_barco(barco_id)
+            .map(|barco| barco.posiciones.clone())
+            .unwrap_or_default();
+        vec![Evento::BarcoMovido {
+            jugador: jugador.id,
+            barco: barco_id,
+            posiciones,
+        }]
+    }
+
+    /// Función que procesa el giro de un barco sobre su primer casillero
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que gira el barco
+    ///
+    /// `barco_id` - ID del barco a girar
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por el giro
+    fn procesar_rotacion(&mut self, jugador_actual: usize, barco_id: usize) -> Vec<Evento> {
+        let jugador = &self.jugadores[jugador_actual];
+        let Some((origen, orientacion)) = jugador
+            .obtener_barco(barco_id)
+            .and_then(|barco| Some((barco.origen()?, barco.orientacion.rotada())))
+        else {
+            return Self::accion_invalida(jugador.id, "El barco seleccionado no existe.");
+        };
+        self.procesar_movimiento(jugador_actual, barco_id, origen, Some(orientacion))
     }
+
     /// Función que procesa un ataque en el mapa
     ///
     /// # Args
     ///
+    /// `jugador_actual` - Índice del jugador que realiza el ataque
+    ///
     /// `coordenadas_ataque` - Coordenadas del ataque
     ///
-    /// `jugador_id` - ID del jugador que realiza el ataque
+    /// # Returns
     ///
-    /// `jugadores` - Vector de jugadores
+    /// `Vec<Evento>` - Eventos producidos por el ataque
+    fn procesar_ataque(
+        &mut self,
+        jugador_actual: usize,
+        coordenadas_ataque: (i32, i32),
+    ) -> Vec<Evento> {
+        let mut eventos = Vec::new();
+        let (puntos, monedas) =
+            self.atacar_casilleros(jugador_actual, &[coordenadas_ataque], &mut eventos);
+        eventos.push(Evento::ResultadoAtaque {
+            jugador: self.jugadores[jugador_actual].id,
+            coordenadas: coordenadas_ataque,
+            puntos,
+            monedas,
+        });
+
+        self.verificar_ganador(&mut eventos);
+        eventos
+    }
+
+    /// Función que ataca varios casilleros a la vez y acredita las recompensas al atacante
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que ataca
+    ///
+    /// `casilleros` - Coordenadas atacadas
     ///
-    /// `mapa` - Mapa en el que se realiza el ataque
+    /// `eventos` - Eventos a los que se agregan los impactos y las eliminaciones
     ///
     /// # Returns
     ///
-    /// `Jugador` - Jugador con el ataque procesado
-    pub fn procesar_ataque(
-        coordenadas_ataque: (i32, i32),
+    /// `(usize, usize)` - Puntos y monedas ganados por el atacante
+    fn atacar_casilleros(
+        &mut self,
         jugador_actual: usize,
-        jugadores: &mut [Jugador],
-        server: &mut Server,
-        conexion: &mut MutexGuard<'_, TcpStream>,
-    ) -> bool {
-        let mut pierde = false;
+        casilleros: &[(i32, i32)],
+        eventos: &mut Vec<Evento>,
+    ) -> (usize, usize) {
         let mut puntos_ganados = 0;
         let mut monedas_ganadas = 0;
+        let atacante = self.jugadores[jugador_actual].id;
 
-        for jugador in jugadores.iter_mut() {
-            if jugador.id != jugador_actual {
-                let (puntos, monedas) = jugador.procesar_ataque(coordenadas_ataque, server);
-                if jugador.barcos.is_empty() && !jugador.ha_perdido {
-                    jugador.ha_perdido = true;
-
-                    let mensaje = Mensaje::Perdiste(jugador.puntos);
-                    let mensaje_serializado = serde_json::to_string(&mensaje).unwrap();
-                    for (id, conexion) in &server.conexiones_jugadores {
-                        if *id == jugador.id {
-                            let conexion = conexion.lock().unwrap();
-                            let _ = Self::enviar_mensaje(
-                                &conexion,
-                                mensaje_serializado.as_bytes().to_vec(),
-                            );
-                        }
-                    }
-                    println!("El jugador {} ha sido eliminado", jugador.nombre_usuario);
-                    pierde = true;
-                    server.conexiones_jugadores.remove(&jugador.id);
-                }
+        for jugador in self
+            .jugadores
+            .iter_mut()
+            .filter(|j| j.id != atacante && !j.ha_perdido)
+        {
+            for &casillero in casilleros {
+                let (puntos, monedas) =
+                    jugador.procesar_ataque(casillero, &self.reglas, &mut self.mapa, eventos);
                 puntos_ganados += puntos;
                 monedas_ganadas += monedas;
-                if puntos > 0 {
-                    jugador.mapa.marcar_hundido(coordenadas_ataque);
-                }
-            }
-            if server.conexiones_jugadores.len() == 1 {
-                println!("El juego ha terminado");
-                break;
             }
+            Self::verificar_eliminacion(jugador, eventos);
         }
 
-        let mensaje = Mensaje::MensajeInfoAtaque(puntos_ganados, monedas_ganadas);
-        let mensaje_serializado = serde_json::to_string(&mensaje).unwrap();
-        Self::enviar_mensaje(conexion, mensaje_serializado.as_bytes().to_vec()).unwrap();
-        jugadores[jugador_actual].puntos += puntos_ganados;
-        jugadores[jugador_actual].monedas += monedas_ganadas;
-
-        pierde
+        let jugador = &mut self.jugadores[jugador_actual];
+        jugador.puntos += puntos_ganados;
+        jugador.monedas += monedas_ganadas;
+        (puntos_ganados, monedas_ganadas)
     }
 
-    /// Función que envía un mensaje a un cliente
+    /// Función que procesa el lanzamiento de una bomba sobre los 3x3 casilleros alrededor de un centro
     ///
     /// # Args
     ///
-    /// `stream` - Stream de conexión   
+    /// `jugador_actual` - Índice del jugador que lanza la bomba
     ///
-    /// `msg` - Mensaje a enviar
+    /// `centro` - Coordenadas del centro de la explosión
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Resultado de la ejecución
+    /// `Vec<Evento>` - Eventos producidos por la bomba
+    fn procesar_bomba(&mut self, jugador_actual: usize, centro: (i32, i32)) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        if !jugador.usar_arma(TipoArma::Bomba) {
+            return Self::accion_invalida(
+                jugador.id,
+                "No tenés bombas, podés comprarlas en la tienda.",
+            );
+        }
+        let casilleros: Vec<(i32, i32)> = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (centro.0 + dx, centro.1 + dy)))
+            .collect();
+
+        let mut eventos = Vec::new();
+        let (puntos, monedas) = self.atacar_casilleros(jugador_actual, &casilleros, &mut eventos);
+        let impactos = eventos
+            .iter()
+            .filter_map(|evento| match evento {
+                Evento::BarcoGolpeado { coordenadas, .. }
+                | Evento::BarcoHundido { coordenadas, .. } => Some(*coordenadas),
+                _ => None,
+            })
+            .collect();
+        eventos.push(Evento::ResultadoBomba {
+            jugador: self.jugadores[jugador_actual].id,
+            centro,
+            impactos,
+            puntos,
+            monedas,
+        });
+
+        self.verificar_ganador(&mut eventos);
+        eventos
+    }
+
+    /// Función que procesa el lanzamiento de un torpedo desde uno de los barcos del jugador
     ///
-    /// # Errors
+    /// El torpedo sale del primer casillero del barco y avanza por su fila o
+    /// columna, pasando por debajo de los barcos propios y de los casilleros
+    /// ya dañados, hasta golpear el primer barco enemigo o salir del tablero.
     ///
-    /// `CustomError` - Error personalizado
-    fn enviar_mensaje(mut stream: &TcpStream, msg: Vec<u8>) -> Result<(), CustomError> {
-        let result_stream = stream.write_all(&msg);
-        result_stream.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        let result_flush = stream.flush();
-        result_flush.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        Ok(())
-    }
-    /// Función que actualiza el ranking de jugadores
+    /// # Args
     ///
-    /// # Returns
+    /// `jugador_actual` - Índice del jugador que lanza el torpedo
     ///
-    /// `Result<(), CustomError>` - Resultado de la ejecución
+    /// `barco_id` - ID del barco desde el que se lanza
     ///
-    /// # Errors
+    /// `direccion` - Dirección en la que avanza el torpedo
     ///
-    /// `CustomError` - Error personalizado
-    pub fn actualizar_ranking(&self) -> Result<(), CustomError> {
-        let nombre_archivo = "../archivos/ranking.json";
-
-        fs::create_dir_all("../archivos").map_err(|_| CustomError::ErrorMostrandoRanking)?;
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por el torpedo
+    fn procesar_torpedo(
+        &mut self,
+        jugador_actual: usize,
+        barco_id: usize,
+        direccion: Direccion,
+    ) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        let Some(origen) = jugador
+            .obtener_barco(barco_id)
+            .and_then(|barco| barco.origen())
+        else {
+            return Self::accion_invalida(jugador.id, "El barco seleccionado no existe.");
+        };
+        if !jugador.usar_arma(TipoArma::Torpedo) {
+            return Self::accion_invalida(
+                jugador.id,
+                "No tenés torpedos, podés comprarlos en la tienda.",
+            );
+        }
+        let atacante = jugador.id;
+        let (dx, dy) = direccion.desplazamiento();
+        let mut casillero = (origen.0 + dx, origen.1 + dy);
+        let mut impacto = None;
+        while self.mapa.contiene(casillero) {
+            if self
+                .jugadores
+                .iter()
+                .any(|j| j.id != atacante && !j.ha_perdido && j.tiene_barco_intacto_en(casillero))
+            {
+                impacto = Some(casillero);
+                break;
+            }
+            casillero = (casillero.0 + dx, casillero.1 + dy);
+        }
 
-        let mut rankings: HashMap<String, usize> = if let Ok(archivo) = File::open(nombre_archivo) {
-            let reader = BufReader::new(archivo);
-            serde_json::from_reader(reader).unwrap_or_default()
-        } else {
-            HashMap::new()
+        let mut eventos = Vec::new();
+        let (puntos, monedas) = match impacto {
+            Some(casillero) => self.atacar_casilleros(jugador_actual, &[casillero], &mut eventos),
+            None => (0, 0),
         };
+        eventos.push(Evento::ResultadoTorpedo {
+            jugador: atacante,
+            impacto,
+            puntos,
+            monedas,
+        });
+
+        self.verificar_ganador(&mut eventos);
+        eventos
+    }
 
-        for jugador in &self.jugadores {
-            *rankings.entry(jugador.nombre_usuario.clone()).or_insert(0) += jugador.puntos;
+    /// Función que procesa el uso del sonar alrededor de un centro
+    ///
+    /// # Args
+    ///
+    /// `jugador_actual` - Índice del jugador que usa el sonar
+    ///
+    /// `centro` - Coordenadas desde las que se rastrea
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Evento>` - Eventos producidos por el sonar
+    fn procesar_sonar(&mut self, jugador_actual: usize, centro: (i32, i32)) -> Vec<Evento> {
+        let jugador = &mut self.jugadores[jugador_actual];
+        if !jugador.usar_arma(TipoArma::Sonar) {
+            return Self::accion_invalida(
+                jugador.id,
+                "No tenés sonares, podés comprarlos en la tienda.",
+            );
         }
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(nombre_archivo)
-            .map_err(|_| CustomError::ErrorMostrandoRanking)?;
+        let atacante = jugador.id;
+        let radio = self.reglas.radio_sonar;
+        let detectado = self
+            .jugadores
+            .iter()
+            .filter(|j| j.id != atacante && !j.ha_perdido)
+            .flat_map(|j| j.barcos.iter().flat_map(|barco| barco.posiciones.iter()))
+            .any(|&(x, y)| (x - centro.0).abs() <= radio && (y - centro.1).abs() <= radio);
+        vec![Evento::ResultadoSonar {
+            jugador: atacante,
+            centro,
+            detectado,
+        }]
+    }
 
-        let writer = BufWriter::new(file);
+    /// Función que marca como eliminado a un jugador que se quedó sin barcos
+    ///
+    /// # Args
+    ///
+    /// `jugador` - Jugador a verificar
+    ///
+    /// `eventos` - Eventos a los que se agrega la eliminación
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn verificar_eliminacion(jugador: &mut Jugador, eventos: &mut Vec<Evento>) {
+        if !jugador.esta_vivo() && !jugador.ha_perdido {
+            jugador.ha_perdido = true;
+            eventos.push(Evento::JugadorEliminado {
+                jugador: jugador.id,
+                nombre: jugador.nombre_usuario.clone(),
+                puntos: jugador.puntos,
+            });
+        }
+    }
 
-        serde_json::to_writer(writer, &rankings).map_err(|_| CustomError::ErrorSerializacion)?;
-        Ok(())
+    /// Función que agrega el evento de ganador si queda un único jugador con barcos
+    ///
+    /// # Args
+    ///
+    /// `eventos` - Eventos a los que se agrega el ganador
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn verificar_ganador(&self, eventos: &mut Vec<Evento>) {
+        let mut vivos = self.jugadores.iter().filter(|j| j.esta_vivo());
+        if let (Some(ganador), None) = (vivos.next(), vivos.next()) {
+            eventos.push(Evento::Ganador {
+                jugador: ganador.id,
+                nombre: ganador.nombre_usuario.clone(),
+                puntos: ganador.puntos,
+            });
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use barcos::barco::Barco;
+    use libreria::constantes::PREMIO;
+
+    fn juego_con_barcos(posiciones: Vec<Vec<(i32, i32)>>) -> Juego {
+        let mut juego = Juego::new(0);
+        for (id, posiciones) in posiciones.into_iter().enumerate() {
+            let mut jugador =
+                Jugador::new(id, "".to_string(), &Reglas::default(), &mut Mapa::new());
+            let mut barco = Barco::new(
+                0,
+                TipoBarco::con_tamaño(posiciones.len()).unwrap(),
+                posiciones.clone(),
+                Orientacion::Horizontal,
+            );
+            juego
+                .mapa
+                .actualizar_posicion_barco(&mut barco, posiciones, id);
+            jugador.barcos = vec![barco];
+            juego.jugadores.push(jugador);
+        }
+        juego
+    }
 
     #[test]
     fn test_nuevo_juego() {
@@ -555,6 +884,23 @@ mod tests {
         assert_eq!(juego.jugadores.len(), 2);
     }
 
+    #[test]
+    fn test_juego_con_configuracion() {
+        let configuracion = ConfiguracionServidor {
+            filas: 6,
+            columnas: 8,
+            reglas: Reglas {
+                monedas_iniciales: 1000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut juego = Juego::con_configuracion(1, &configuracion);
+        juego.agregar_jugador(1, "pedro".to_string());
+        assert_eq!(juego.mapa.tablero.dim(), (6, 8));
+        assert!(juego.jugadores.iter().all(|j| j.monedas == 1000));
+    }
+
     #[test]
     fn test_agregar_jugador() {
         let mut juego = Juego::new(2);
@@ -568,4 +914,502 @@ mod tests {
         juego.eliminar_jugador(1);
         assert_eq!(juego.jugadores.len(), 1);
     }
+
+    #[test]
+    fn test_accion_fuera_de_turno() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(1, Instruccion::Saltar);
+        assert!(matches!(
+            eventos[0],
+            Evento::AccionInvalida { jugador: 1, .. }
+        ));
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+    }
+
+    #[test]
+    fn test_saltar_pasa_el_turno() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        assert_eq!(
+            juego.aplicar(0, Instruccion::Saltar),
+            vec![Evento::TurnoSaltado { jugador: 0 }]
+        );
+        assert_eq!(juego.jugador_en_turno(), Some(1));
+    }
+
+    #[test]
+    fn test_ataque_golpea_barco() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5), (6, 5)], vec![(9, 9)]]);
+        let eventos = juego.aplicar(0, Instruccion::Ataque(0, (5, 5)));
+        assert_eq!(
+            eventos,
+            vec![
+                Evento::BarcoGolpeado {
+                    jugador: 1,
+                    coordenadas: (5, 5)
+                },
+                Evento::ResultadoAtaque {
+                    jugador: 0,
+                    coordenadas: (5, 5),
+                    puntos: 5,
+                    monedas: 50
+                },
+            ]
+        );
+        assert_eq!(juego.jugadores[0].puntos, 5);
+        assert_eq!(juego.jugadores[1].barcos[0].estado, EstadoBarco::Golpeado);
+    }
+
+    #[test]
+    fn test_ataque_hunde_elimina_y_define_ganador() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(0, Instruccion::Ataque(0, (5, 5)));
+        assert_eq!(
+            eventos,
+            vec![
+                Evento::BarcoHundido {
+                    jugador: 1,
+                    coordenadas: (5, 5)
+                },
+                Evento::JugadorEliminado {
+                    jugador: 1,
+                    nombre: "".to_string(),
+                    puntos: 0
+                },
+                Evento::ResultadoAtaque {
+                    jugador: 0,
+                    coordenadas: (5, 5),
+                    puntos: 15,
+                    monedas: 100
+                },
+                Evento::Ganador {
+                    jugador: 0,
+                    nombre: "".to_string(),
+                    puntos: 15
+                },
+            ]
+        );
+        assert!(juego.finalizo());
+        assert!(matches!(
+            juego.aplicar(0, Instruccion::Saltar)[0],
+            Evento::AccionInvalida { .. }
+        ));
+    }
+
+    #[test]
+    fn test_turno_saltea_jugadores_eliminados() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)], vec![(9, 9)]]);
+        juego.aplicar(0, Instruccion::Ataque(0, (5, 5)));
+        assert_eq!(juego.jugador_en_turno(), Some(2));
+    }
+
+    #[test]
+    fn test_compra_descuenta_monedas() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::CompraOrientada(TipoBarco::Acorazado, Orientacion::Vertical),
+        );
+        assert_eq!(
+            eventos,
+            vec![Evento::CompraRealizada {
+                jugador: 0,
+                tipo: TipoBarco::Acorazado,
+                monedas: 200
+            }]
+        );
+        assert_eq!(juego.jugadores[0].barcos.len(), 2);
+        assert_eq!(juego.jugadores[0].barcos[1].tipo, TipoBarco::Acorazado);
+    }
+
+    #[test]
+    fn test_compra_sin_monedas_suficientes() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        juego.jugadores[0].monedas = 50;
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::CompraOrientada(TipoBarco::Fragata, Orientacion::Horizontal),
+        );
+        assert!(matches!(
+            eventos[0],
+            Evento::CompraRechazada { jugador: 0, .. }
+        ));
+        assert_eq!(juego.jugadores[0].monedas, 50);
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+    }
+
+    #[test]
+    fn test_reparacion_devuelve_el_barco_a_sano() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0), (1, 0)], vec![(5, 5), (6, 5)]]);
+        juego.aplicar(0, Instruccion::Ataque(0, (5, 5)));
+        let barco = &juego.jugadores[1].barcos[0];
+        assert_eq!(barco.estado, EstadoBarco::Golpeado);
+        assert_eq!(barco.posiciones, vec![(5, 5), (6, 5)]);
+        assert_eq!(juego.mapa.tablero[[5, 5]], 'X');
+
+        let monedas = juego.jugadores[1].monedas;
+        let eventos = juego.aplicar(1, Instruccion::Reparacion(0));
+        assert_eq!(
+            eventos,
+            vec![Evento::BarcoReparado {
+                jugador: 1,
+                barco: 0,
+                coordenadas: (5, 5),
+                monedas: monedas - juego.reglas.precio_reparacion,
+            }]
+        );
+        assert_eq!(juego.jugadores[1].barcos[0].estado, EstadoBarco::Sano);
+        assert_eq!(juego.mapa.tablero[[5, 5]], '1');
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+
+        let eventos = juego.aplicar(0, Instruccion::Reparacion(0));
+        assert!(matches!(
+            eventos[0],
+            Evento::CompraRechazada { jugador: 0, .. }
+        ));
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+    }
+
+    #[test]
+    fn test_bomba_ataca_los_casilleros_alrededor_del_centro() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5), (6, 5)], vec![(9, 9)]]);
+        assert!(matches!(
+            juego.aplicar(0, Instruccion::Bomba((5, 4)))[0],
+            Evento::AccionInvalida { jugador: 0, .. }
+        ));
+
+        juego.jugadores[0].armas = vec![TipoArma::Bomba];
+        let eventos = juego.aplicar(0, Instruccion::Bomba((5, 4)));
+        assert!(eventos.contains(&Evento::ResultadoBomba {
+            jugador: 0,
+            centro: (5, 4),
+            impactos: vec![(5, 5), (6, 5)],
+            puntos: juego.reglas.puntos_por_impacto + juego.reglas.puntos_por_hundimiento,
+            monedas: juego.reglas.monedas_por_impacto + juego.reglas.monedas_por_hundimiento,
+        }));
+        assert_eq!(
+            juego.jugadores[0].puntos,
+            juego.reglas.puntos_por_impacto + juego.reglas.puntos_por_hundimiento
+        );
+        assert!(juego.jugadores[0].armas.is_empty());
+        assert!(juego.jugadores[1].ha_perdido);
+        assert!(juego.jugadores[2].esta_vivo());
+        assert_eq!(juego.jugador_en_turno(), Some(2));
+    }
+
+    #[test]
+    fn test_torpedo_avanza_hasta_el_primer_barco_intacto() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 5)], vec![(6, 5)], vec![(3, 5), (4, 5)]]);
+        juego.jugadores[2].barcos[0].recibir_impacto((3, 5));
+        juego.jugadores[0].armas = vec![TipoArma::Torpedo];
+        let eventos = juego.aplicar(0, Instruccion::Torpedo(0, Direccion::Derecha));
+        assert!(matches!(
+            eventos.last(),
+            Some(Evento::ResultadoTorpedo {
+                jugador: 0,
+                impacto: Some((4, 5)),
+                ..
+            })
+        ));
+        assert!(juego.jugadores[2].ha_perdido);
+        assert!(juego.jugadores[1].esta_vivo());
+
+        juego.jugadores[1].armas = vec![TipoArma::Torpedo];
+        let eventos = juego.aplicar(1, Instruccion::Torpedo(0, Direccion::Abajo));
+        assert_eq!(
+            eventos,
+            vec![Evento::ResultadoTorpedo {
+                jugador: 1,
+                impacto: None,
+                puntos: 0,
+                monedas: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sonar_detecta_barcos_enemigos_en_su_radio() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        juego.jugadores[0].armas = vec![TipoArma::Sonar];
+        juego.jugadores[1].armas = vec![TipoArma::Sonar];
+        assert_eq!(
+            juego.aplicar(0, Instruccion::Sonar((3, 3))),
+            vec![Evento::ResultadoSonar {
+                jugador: 0,
+                centro: (3, 3),
+                detectado: true,
+            }]
+        );
+        assert_eq!(
+            juego.aplicar(1, Instruccion::Sonar((5, 2))),
+            vec![Evento::ResultadoSonar {
+                jugador: 1,
+                centro: (5, 2),
+                detectado: false,
+            }]
+        );
+
+        juego.reglas.radio_sonar = 3;
+        juego.jugadores[0].armas = vec![TipoArma::Sonar];
+        assert_eq!(
+            juego.aplicar(0, Instruccion::Sonar((5, 2))),
+            vec![Evento::ResultadoSonar {
+                jugador: 0,
+                centro: (5, 2),
+                detectado: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compra_de_arma() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let monedas = juego.jugadores[0].monedas;
+        let eventos = juego.aplicar(0, Instruccion::CompraArma(TipoArma::Sonar));
+        assert_eq!(
+            eventos,
+            vec![Evento::ArmaComprada {
+                jugador: 0,
+                arma: TipoArma::Sonar,
+                cantidad: 1,
+                monedas: monedas - juego.reglas.precio_sonar,
+            }]
+        );
+        assert_eq!(juego.jugadores[0].armas, vec![TipoArma::Sonar]);
+    }
+
+    #[test]
+    fn test_tienda_no_consume_turno() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(0, Instruccion::Tienda);
+        assert_eq!(eventos, vec![Evento::TiendaSolicitada { jugador: 0 }]);
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+    }
+
+    #[test]
+    fn test_movimiento_de_barco_golpeado() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0), (1, 0)], vec![(5, 5)]]);
+        juego.jugadores[0].barcos[0].estado = EstadoBarco::Golpeado;
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (3, 3), Orientacion::Horizontal),
+        );
+        assert!(matches!(
+            eventos[0],
+            Evento::AccionInvalida { jugador: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_movimiento_se_aplica_en_el_mapa_compartido() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (1, 2), Orientacion::Horizontal),
+        );
+        assert_eq!(
+            eventos,
+            vec![Evento::BarcoMovido {
+                jugador: 0,
+                barco: 0,
+                posiciones: vec![(1, 2)]
+            }]
+        );
+        assert_eq!(juego.mapa.tablero[[0, 0]], '.');
+        assert_eq!(juego.mapa.tablero[[2, 1]], '0');
+    }
+
+    #[test]
+    fn test_movimiento_limitado_por_la_velocidad_y_el_camino() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(1, 0), (1, 1)]]);
+        let motivo = |eventos: Vec<Evento>| match &eventos[0] {
+            Evento::AccionInvalida { motivo, .. } => motivo.clone(),
+            otro => panic!("Evento inesperado: {:?}", otro),
+        };
+
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (3, 1), Orientacion::Horizontal),
+        );
+        assert!(motivo(eventos).contains("hasta 3 casilleros"));
+
+        juego.mapa.tablero[[2, 0]] = 'X';
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (0, 3), Orientacion::Horizontal),
+        );
+        assert!(motivo(eventos).contains("camino libre"));
+        assert_eq!(juego.jugadores[0].barcos[0].posiciones, vec![(0, 0)]);
+        assert_eq!(juego.jugador_en_turno(), Some(0));
+
+        juego.mapa.tablero[[2, 0]] = '.';
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (0, 3), Orientacion::Horizontal),
+        );
+        assert!(matches!(eventos[0], Evento::BarcoMovido { .. }));
+    }
+
+    #[test]
+    fn test_movimiento_usa_la_velocidad_de_las_reglas() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(9, 9)]]);
+        juego.reglas.velocidad_fragata = 1;
+
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (2, 0), Orientacion::Horizontal),
+        );
+        assert!(matches!(
+            &eventos[0],
+            Evento::AccionInvalida { motivo, .. } if motivo.contains("hasta 1 casilleros")
+        ));
+
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (1, 0), Orientacion::Horizontal),
+        );
+        assert!(matches!(eventos[0], Evento::BarcoMovido { .. }));
+    }
+
+    #[test]
+    fn test_movimiento_verifica_el_camino_con_la_nueva_orientacion() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0), (1, 0)], vec![(9, 9)]]);
+        juego.mapa.tablero[[1, 0]] = 'X';
+
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (1, 1), Orientacion::Vertical),
+        );
+        assert!(matches!(
+            &eventos[0],
+            Evento::AccionInvalida { motivo, .. } if motivo.contains("camino libre")
+        ));
+        assert_eq!(
+            juego.jugadores[0].barcos[0].posiciones,
+            vec![(0, 0), (1, 0)]
+        );
+
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (1, 1), Orientacion::Horizontal),
+        );
+        assert!(matches!(eventos[0], Evento::BarcoMovido { .. }));
+    }
+
+    #[test]
+    fn test_los_barcos_se_eligen_por_id_despues_de_un_hundimiento() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(9, 9)]]);
+        let jugador = &mut juego.jugadores[0];
+        jugador.agregar_barco(TipoBarco::Fragata, None, &mut juego.mapa);
+        let id_barco = jugador.barcos[1].id;
+        jugador.procesar_ataque((0, 0), &Reglas::default(), &mut juego.mapa, &mut Vec::new());
+
+        let eventos = juego.aplicar(0, Instruccion::Rotacion(id_barco));
+        assert!(matches!(
+            eventos[0],
+            Evento::BarcoMovido { barco, .. } if barco == id_barco
+        ));
+    }
+
+    #[test]
+    fn test_rotacion_gira_el_barco_sobre_su_origen() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0), (1, 0), (2, 0)], vec![(5, 5)]]);
+        let eventos = juego.aplicar(0, Instruccion::Rotacion(0));
+        assert_eq!(
+            eventos,
+            vec![Evento::BarcoMovido {
+                jugador: 0,
+                barco: 0,
+                posiciones: vec![(0, 0), (0, 1), (0, 2)]
+            }]
+        );
+        assert_eq!(
+            juego.jugadores[0].barcos[0].orientacion,
+            Orientacion::Vertical
+        );
+        assert_eq!(juego.mapa.tablero[[2, 0]], '0');
+        assert_eq!(juego.mapa.tablero[[0, 2]], '.');
+
+        juego.aplicar(1, Instruccion::Saltar);
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (5, 3), Orientacion::Vertical),
+        );
+        assert!(matches!(
+            eventos[0],
+            Evento::AccionInvalida { jugador: 0, .. }
+        ));
+        assert_eq!(juego.mapa.tablero[[0, 0]], '0');
+        // Sin orientación, el barco conserva la que tiene
+        let eventos = juego.aplicar(0, Instruccion::Movimiento(0, (0, 1)));
+        assert!(matches!(
+            &eventos[0],
+            Evento::BarcoMovido { posiciones, .. } if *posiciones == vec![(0, 1), (0, 2), (0, 3)]
+        ));
+    }
+
+    #[test]
+    fn test_movimiento_sobre_barco_ajeno() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(1, 1)]]);
+        let eventos = juego.aplicar(
+            0,
+            Instruccion::MovimientoOrientado(0, (1, 1), Orientacion::Horizontal),
+        );
+        assert!(matches!(
+            eventos[0],
+            Evento::AccionInvalida { jugador: 0, .. }
+        ));
+        assert_eq!(juego.mapa.tablero[[1, 1]], '1');
+        assert_eq!(juego.jugadores[0].barcos[0].posiciones, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_eliminar_jugador_libera_sus_posiciones() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        juego.eliminar_jugador(1);
+        assert_eq!(juego.mapa.tablero[[5, 5]], '.');
+    }
+
+    #[test]
+    fn test_abandonar_elimina_y_define_ganador() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(5, 5)]]);
+        let eventos = juego.abandonar(0);
+        assert!(matches!(
+            eventos.as_slice(),
+            [
+                Evento::JugadorEliminado { jugador: 0, .. },
+                Evento::Ganador { jugador: 1, .. }
+            ]
+        ));
+        assert_eq!(juego.mapa.tablero[[0, 0]], '.');
+        assert_eq!(juego.jugadores.len(), 2);
+        assert!(juego.finalizo());
+    }
+
+    #[test]
+    fn test_evento_sorpresa_premia_al_ganador() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(1, 1)]]);
+        let eventos = juego.resolver_evento_sorpresa(Some(1), &[0]);
+        assert_eq!(
+            eventos,
+            vec![Evento::PremioObtenido {
+                jugador: 1,
+                monedas: PREMIO
+            }]
+        );
+        assert_eq!(juego.jugadores[1].monedas, 500 + PREMIO);
+    }
+
+    #[test]
+    fn test_evento_sorpresa_usa_las_reglas() {
+        let mut juego = juego_con_barcos(vec![vec![(0, 0)], vec![(2, 3)], vec![(9, 9)]]);
+        juego.reglas.premio_evento_sorpresa = 10;
+        juego.reglas.coordenadas_bomba = (2, 3);
+        let eventos = juego.resolver_evento_sorpresa(Some(0), &[1]);
+        assert_eq!(juego.jugadores[0].monedas, 510);
+        assert!(eventos.contains(&Evento::BarcoHundido {
+            jugador: 1,
+            coordenadas: (2, 3)
+        }));
+        assert!(juego.jugadores[1].ha_perdido);
+    }
 }
@@ -1,18 +1,20 @@
-use barcos::{barco::Barco, estado_barco::EstadoBarco};
+use barcos::{
+    barco::Barco, estado_barco::EstadoBarco, orientacion::Orientacion, tipo_barco::TipoBarco,
+};
 use libreria::custom_error::CustomError;
 
-use crate::{mapa::Mapa, mensaje::Mensaje, server::Server};
-use std::{io::Write, net::TcpStream, vec};
+use crate::{arma::TipoArma, evento::Evento, mapa::Mapa, reglas::Reglas};
 
 #[derive(Clone)]
 pub struct Jugador {
     pub id: usize,
     pub nombre_usuario: String,
-    pub mapa: Mapa,
     pub barcos: Vec<Barco>,
     pub puntos: usize,
     pub monedas: usize,
+    pub armas: Vec<TipoArma>,
     pub ha_perdido: bool,
+    siguiente_barco: usize,
 }
 
 impl Jugador {
@@ -22,127 +24,179 @@ impl Jugador {
     ///
     /// `id` - Identificador del jugador
     ///
-    /// `mapa` - Mapa en el que se encuentra el jugador
+    /// `nombre` - Nombre de usuario del jugador
+    ///
+    /// `reglas` - Reglas de la partida, de las que se toman las monedas y la flota iniciales
+    ///
+    /// `mapa` - Mapa compartido en el que se ubican los barcos del jugador
     ///
     /// # Returns
     ///
     /// `Jugador` - Jugador creado
-    pub fn new(id: usize, nombre: String, mapa: &mut Mapa) -> Jugador {
-        let mut barcos = Vec::new();
-
-        let tamaños_barcos: Vec<usize> = vec![1];
-        for (id_actual, &tamaño) in tamaños_barcos.iter().enumerate() {
-            let vec_posiciones = mapa.obtener_posiciones_libres_contiguas(id.to_string(), tamaño);
-            barcos.push(Barco::new(id_actual, tamaño, vec_posiciones));
-        }
-
-        Jugador {
+    pub fn new(id: usize, nombre: String, reglas: &Reglas, mapa: &mut Mapa) -> Jugador {
+        let mut jugador = Jugador {
             id,
             nombre_usuario: nombre,
-            barcos,
+            barcos: Vec::new(),
             puntos: 0,
-            monedas: 500,
-            mapa: mapa.clone(),
+            monedas: reglas.monedas_iniciales,
+            armas: Vec::new(),
             ha_perdido: false,
+            siguiente_barco: 0,
+        };
+        for &tipo in &reglas.flota_inicial {
+            jugador.agregar_barco(tipo, None, mapa);
         }
+        jugador
     }
 
-    /// Función que envía las instrucciones al jugador
+    /// Función que permite al jugador agregar un barco al tablero
+    ///
+    /// Cada barco recibe un ID nuevo, que no se reutiliza aunque se hundan
+    /// barcos anteriores.
     ///
     /// # Args
     ///
-    /// `server` - Servidor en el que se encuentra el jugador
+    /// `tipo` - Clase del barco a agregar
+    ///
+    /// `orientacion` - Orientación elegida para el barco; si no se indica, se elige al azar
+    ///
+    /// `mapa` - Mapa compartido en el que se ubicará el barco
     ///
     /// # Returns
     ///
-    /// `()` - No retorna nada
-    pub fn enviar_instrucciones(&self, server: &Server) {
-        if let Some(conexion) = server.conexiones_jugadores.get(&self.id) {
-            let conexion = conexion
-                .lock()
-                .map_err(|_| CustomError::ErrorAceptandoConexion)
-                .unwrap();
-            let mensaje_serializado = serde_json::to_string(&Mensaje::Puntos(self.puntos)).unwrap();
-            let _ = Self::enviar_mensaje(&conexion, mensaje_serializado.as_bytes().to_vec());
-        }
+    /// `bool` - Verdadero si había lugar en el mapa para el barco
+    pub fn agregar_barco(
+        &mut self,
+        tipo: TipoBarco,
+        orientacion: Option<Orientacion>,
+        mapa: &mut Mapa,
+    ) -> bool {
+        let Some((posiciones, orientacion)) = mapa.obtener_posiciones_libres_contiguas(
+            self.id.to_string(),
+            tipo.tamaño(),
+            orientacion,
+        ) else {
+            return false;
+        };
+        self.barcos.push(Barco::new(
+            self.siguiente_barco,
+            tipo,
+            posiciones,
+            orientacion,
+        ));
+        self.siguiente_barco += 1;
+        true
     }
-    /// Función que maneja el turno del jugador
+
+    /// Función que vuelve a ubicar los barcos que el jugador eligió antes de comenzar
+    ///
+    /// Los barcos que no se indican quedan donde estaban. Si alguna ubicación
+    /// no es válida no se mueve ningún barco.
     ///
     /// # Args
     ///
-    /// `server` - Servidor en el que se encuentra el jugador
+    /// `ubicaciones` - ID de cada barco con su origen y su orientación
+    ///
+    /// `mapa` - Mapa compartido en el que se ubican los barcos
     ///
     /// # Returns
     ///
+    /// `Result<(), CustomError>` - Ok si se ubicaron todos los barcos
     ///
-    pub fn manejar_turno(&mut self, server: &Server) {
-        if self.barcos.is_empty() {
-            return;
+    /// # Errors
+    ///
+    /// `CustomError::ErrorUbicacionInvalida` - Si un barco no existe, se repite, queda fuera del tablero o se superpone con otro
+    pub fn desplegar_flota(
+        &mut self,
+        ubicaciones: &[(usize, (i32, i32), Orientacion)],
+        mapa: &mut Mapa,
+    ) -> Result<(), CustomError> {
+        let mut indices = Vec::new();
+        for (id_barco, _, _) in ubicaciones {
+            let indice = self
+                .barcos
+                .iter()
+                .position(|barco| barco.id == *id_barco)
+                .filter(|indice| !indices.contains(indice))
+                .ok_or(CustomError::ErrorUbicacionInvalida)?;
+            indices.push(indice);
+        }
+        for &indice in &indices {
+            mapa.liberar_posiciones(&self.barcos[indice].posiciones);
         }
-        let _ = self
-            .mapa
-            .enviar_tablero(self.id.to_string(), server, &self.barcos, self.monedas);
+
+        let mut ocupadas: Vec<Vec<(i32, i32)>> = Vec::new();
+        for (&indice, &(_, origen, orientacion)) in indices.iter().zip(ubicaciones) {
+            let posiciones = orientacion.posiciones(origen, self.barcos[indice].tamaño());
+            if !mapa.ocupar_posiciones(self.id.to_string(), &posiciones) {
+                for posiciones in &ocupadas {
+                    mapa.liberar_posiciones(posiciones);
+                }
+                for &indice in &indices {
+                    mapa.ocupar_posiciones(self.id.to_string(), &self.barcos[indice].posiciones);
+                }
+                return Err(CustomError::ErrorUbicacionInvalida);
+            }
+            ocupadas.push(posiciones);
+        }
+        for ((indice, posiciones), &(_, _, orientacion)) in
+            indices.into_iter().zip(ocupadas).zip(ubicaciones)
+        {
+            self.barcos[indice].actualizar_posicion(posiciones, orientacion);
+        }
+        Ok(())
     }
-    /// Función que permite al jugador agregar un barco al tablero
+
+    /// Función que permite al jugador moverse en el tablero
     ///
     /// # Args
     ///
-    /// `tamanio_barco` - Tamaño del barco a agregar
+    /// `coordenadas_destino` - Coordenadas libres que ocupará el barco, una por casillero
     ///
-    /// # Returns
-    ///
-    /// `()` - No retorna nada
-    pub fn agregar_barco(&mut self, tamanio_barco: usize) {
-        let vec_posiciones = self
-            .mapa
-            .obtener_posiciones_libres_contiguas(self.id.to_string(), tamanio_barco);
-        let id_barco = self.barcos.len();
-        self.barcos
-            .push(Barco::new(id_barco, tamanio_barco, vec_posiciones));
-    }
-
-    /// Función que permite al jugador moverse en el tablero
+    /// `barco` - ID del barco a mover
     ///
-    /// # Args
+    /// `orientacion` - Orientación del barco en su nueva posición
     ///
-    /// `mapa` - Mapa en el que se moverá el jugador
+    /// `mapa` - Mapa compartido en el que se moverá el barco
     ///
     /// # Returns
     ///
-    /// `Accion` - Acción de movimiento realizada por el jugador
-
+    /// `()` - No retorna nada
     pub fn actualizar_posicion_barco(
         &mut self,
-        coordenadas_contiguas: Vec<(i32, i32)>,
+        coordenadas_destino: Vec<(i32, i32)>,
         barco: usize,
+        orientacion: Orientacion,
+        mapa: &mut Mapa,
     ) {
-        let mut coordenadas_destino = vec![];
-        for (i, &coordenada) in coordenadas_contiguas.iter().enumerate() {
-            coordenadas_destino.push(coordenada);
-            if i == self.barcos[barco].tamaño - 1 {
-                break;
-            }
-        }
-
-        if self.mapa.actualizar_posicion_barco(
-            &mut self.barcos[barco],
-            coordenadas_destino.clone(),
-            self.id,
-        ) {
-            self.barcos[barco].actualizar_posicion(coordenadas_destino);
+        let id_jugador = self.id;
+        let Some(barco) = self.obtener_barco_mut(barco) else {
+            return;
+        };
+        if mapa.actualizar_posicion_barco(barco, coordenadas_destino.clone(), id_jugador) {
+            barco.actualizar_posicion(coordenadas_destino, orientacion);
         }
     }
     /// Función que permite al jugador obtener un barco
     ///
     /// # Args
     ///
-    /// `barco_seleccionado` - Indice del barco seleccionado
+    /// `barco_seleccionado` - ID del barco seleccionado
     ///
     /// # Returns
     ///
-    /// `Barco` - Barco seleccionado
-    pub fn obtener_barco(&self, barco_seleccionado: usize) -> Barco {
-        self.barcos[barco_seleccionado].clone()
+    /// `Option<&Barco>` - Barco seleccionado, `None` si el jugador no tiene un barco con ese ID
+    pub fn obtener_barco(&self, barco_seleccionado: usize) -> Option<&Barco> {
+        self.barcos
+            .iter()
+            .find(|barco| barco.id == barco_seleccionado)
+    }
+
+    fn obtener_barco_mut(&mut self, barco_seleccionado: usize) -> Option<&mut Barco> {
+        self.barcos
+            .iter_mut()
+            .find(|barco| barco.id == barco_seleccionado)
     }
 
     /// Función que procesa un ataque realizado por un jugador
@@ -151,104 +205,103 @@ impl Jugador {
     ///
     /// `coordenadas_ataque` - Coordenadas del ataque realizado por el jugador
     ///
-    /// `mapa` - Mapa en el que se encuentra el jugador
+    /// `reglas` - Reglas de la partida, de las que se toman las recompensas
+    ///
+    /// `mapa` - Mapa compartido en el que se marcan los barcos hundidos
+    ///
+    /// `eventos` - Eventos a los que se agregan los barcos golpeados o hundidos
     ///
     /// # Returns
     ///
-    /// `usize` - Puntos ganados por el jugador
-
+    /// `(usize, usize)` - Puntos y monedas ganados por el atacante
     pub fn procesar_ataque(
         &mut self,
         coordenadas_ataque: (i32, i32),
-        server: &Server,
+        reglas: &Reglas,
+        mapa: &mut Mapa,
+        eventos: &mut Vec<Evento>,
     ) -> (usize, usize) {
         let mut puntos = 0;
         let mut monedas = 0;
-        let mut barcos_hundidos = Vec::new();
         for barco in &mut self.barcos {
-            if barco.posiciones.contains(&coordenadas_ataque) {
-                barco.posiciones.retain(|&pos| pos != coordenadas_ataque);
-
-                if barco.posiciones.is_empty() {
-                    barco.estado = EstadoBarco::Hundido;
-                    puntos += 15;
-                    monedas += 100;
-
-                    let conexion = server
-                        .conexiones_jugadores
-                        .get(&self.id)
-                        .unwrap()
-                        .lock()
-                        .unwrap();
-                    let mensaje_serializado =
-                        serde_json::to_string(&Mensaje::BarcoHundido).unwrap();
-                    let _ =
-                        Self::enviar_mensaje(&conexion, mensaje_serializado.as_bytes().to_vec());
-                    barcos_hundidos.push(coordenadas_ataque);
-                } else if barco.estado == EstadoBarco::Sano {
-                    barco.estado = EstadoBarco::Golpeado;
-                    let conexion = server
-                        .conexiones_jugadores
-                        .get(&self.id)
-                        .unwrap()
-                        .lock()
-                        .unwrap();
-                    let mensaje_serializado =
-                        serde_json::to_string(&Mensaje::BarcoGolpead(coordenadas_ataque));
-                    let _ = Self::enviar_mensaje(
-                        &conexion,
-                        mensaje_serializado.unwrap().as_bytes().to_vec(),
-                    );
-                    puntos += 5;
-                    monedas += 50;
-                } else if barco.estado == EstadoBarco::Golpeado {
-                    let conexion = server
-                        .conexiones_jugadores
-                        .get(&self.id)
-                        .unwrap()
-                        .lock()
-                        .unwrap();
-                    let mensaje_serializado =
-                        serde_json::to_string(&Mensaje::BarcoGolpead(coordenadas_ataque));
-                    let _ = Self::enviar_mensaje(
-                        &conexion,
-                        mensaje_serializado.unwrap().as_bytes().to_vec(),
-                    );
-                    puntos += 5;
-                    monedas += 50;
-                }
+            if !barco.recibir_impacto(coordenadas_ataque) {
+                continue;
+            }
+            mapa.marcar_hundido(coordenadas_ataque);
+
+            if barco.estado == EstadoBarco::Hundido {
+                puntos += reglas.puntos_por_hundimiento;
+                monedas += reglas.monedas_por_hundimiento;
+
+                eventos.push(Evento::BarcoHundido {
+                    jugador: self.id,
+                    coordenadas: coordenadas_ataque,
+                });
+            } else {
+                eventos.push(Evento::BarcoGolpeado {
+                    jugador: self.id,
+                    coordenadas: coordenadas_ataque,
+                });
+                puntos += reglas.puntos_por_impacto;
+                monedas += reglas.monedas_por_impacto;
             }
         }
 
         self.barcos
             .retain(|barco| barco.estado != EstadoBarco::Hundido);
 
-        for coordenadas in barcos_hundidos {
-            self.mapa.marcar_hundido(coordenadas);
-        }
-
         (puntos, monedas)
     }
 
-    /// Función que envía un mensaje al servidor
+    /// Función que repara un casillero dañado de uno de los barcos del jugador
     ///
     /// # Args
     ///
-    /// `stream` - Stream por el cual se enviará el mensaje
+    /// `barco` - ID del barco a reparar
     ///
-    /// `msg` - Mensaje a enviar
+    /// `mapa` - Mapa compartido en el que se vuelve a mostrar el casillero
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Resultado de la operación
+    /// `Option<(i32, i32)>` - Casillero reparado, `None` si el barco no existe o no tiene daños
+    pub fn reparar_barco(&mut self, barco: usize, mapa: &mut Mapa) -> Option<(i32, i32)> {
+        let reparada = self.obtener_barco_mut(barco)?.reparar()?;
+        mapa.marcar_reparado(self.id.to_string(), reparada);
+        Some(reparada)
+    }
+
+    /// Función que gasta una de las armas compradas por el jugador
+    ///
+    /// # Args
     ///
-    /// # Errors
-    fn enviar_mensaje(mut stream: &TcpStream, msg: Vec<u8>) -> Result<(), CustomError> {
-        let result_stream = stream.write_all(&msg);
-        result_stream.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        let result_flush = stream.flush();
-        result_flush.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        Ok(())
+    /// `arma` - Arma a usar
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si el jugador tenía el arma
+    pub fn usar_arma(&mut self, arma: TipoArma) -> bool {
+        match self.armas.iter().position(|&propia| propia == arma) {
+            Some(indice) => {
+                self.armas.remove(indice);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Función que indica si algún barco del jugador ocupa una posición sin daños
+    ///
+    /// # Args
+    ///
+    /// `coordenadas` - Coordenadas a revisar
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si un torpedo se detendría en esa posición
+    pub fn tiene_barco_intacto_en(&self, coordenadas: (i32, i32)) -> bool {
+        self.barcos.iter().any(|barco| {
+            barco.posiciones.contains(&coordenadas) && !barco.dañadas.contains(&coordenadas)
+        })
     }
 
     pub fn esta_vivo(&self) -> bool {
@@ -263,7 +316,12 @@ mod tests {
 
     #[test]
     fn test_new_jugador() {
-        let jugador = Jugador::new(1, "Jugador 1".to_string(), &mut Mapa::new());
+        let jugador = Jugador::new(
+            1,
+            "Jugador 1".to_string(),
+            &Reglas::default(),
+            &mut Mapa::new(),
+        );
         assert_eq!(jugador.id, 1);
         assert_eq!(jugador.nombre_usuario, "Jugador 1");
         assert_eq!(jugador.barcos.len(), 1);
@@ -273,24 +331,181 @@ mod tests {
 
     #[test]
     fn test_agregar_barco() {
-        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &mut Mapa::new());
-        jugador.agregar_barco(2);
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &Reglas::default(), &mut mapa);
+        assert!(jugador.agregar_barco(TipoBarco::Buque, Some(Orientacion::Vertical), &mut mapa));
         assert_eq!(jugador.barcos.len(), 2);
+        let (x, y) = jugador.barcos[1].posiciones[0];
+        assert_eq!(jugador.barcos[1].posiciones, vec![(x, y), (x, y + 1)]);
+        assert_eq!(jugador.barcos[1].orientacion, Orientacion::Vertical);
     }
 
     #[test]
     fn test_obtener_barco() {
-        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &mut Mapa::new());
-        jugador.agregar_barco(2);
-        let barco = jugador.obtener_barco(1);
-        assert_eq!(barco.tamaño, 2);
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &Reglas::default(), &mut mapa);
+        jugador.agregar_barco(TipoBarco::Buque, None, &mut mapa);
+        let barco = jugador.obtener_barco(1).unwrap();
+        assert_eq!(barco.tipo, TipoBarco::Buque);
+        assert!(jugador.obtener_barco(2).is_none());
+    }
+
+    #[test]
+    fn test_los_ids_de_barcos_no_se_reutilizan() {
+        let reglas = Reglas::default();
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &reglas, &mut mapa);
+        jugador.agregar_barco(TipoBarco::Buque, None, &mut mapa);
+        let fragata = jugador.barcos[0].posiciones[0];
+        jugador.procesar_ataque(fragata, &reglas, &mut mapa, &mut Vec::new());
+        assert!(jugador.obtener_barco(0).is_none());
+
+        jugador.agregar_barco(TipoBarco::Fragata, None, &mut mapa);
+        let ids: Vec<usize> = jugador.barcos.iter().map(|barco| barco.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(jugador.obtener_barco(1).unwrap().tipo, TipoBarco::Buque);
     }
 
     #[test]
     fn test_actualizar_posicion_barco() {
-        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &mut Mapa::new());
-        jugador.agregar_barco(2);
-        jugador.actualizar_posicion_barco(vec![(0, 0), (0, 1)], 0);
-        assert_eq!(jugador.barcos[0].posiciones, vec![(0, 0)]);
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &Reglas::default(), &mut mapa);
+        jugador.agregar_barco(TipoBarco::Buque, None, &mut mapa);
+        jugador.actualizar_posicion_barco(
+            vec![(0, 0), (0, 1)],
+            1,
+            Orientacion::Vertical,
+            &mut mapa,
+        );
+        assert_eq!(jugador.barcos[1].posiciones, vec![(0, 0), (0, 1)]);
+        assert_eq!(jugador.barcos[1].orientacion, Orientacion::Vertical);
+        assert_eq!(mapa.tablero[[1, 0]], '1');
+    }
+
+    #[test]
+    fn test_barcos_de_distintos_jugadores_no_se_superponen() {
+        let mut mapa = Mapa::new();
+        let mut jugadores: Vec<Jugador> = (0..5)
+            .map(|id| Jugador::new(id, id.to_string(), &Reglas::default(), &mut mapa))
+            .collect();
+        for jugador in jugadores.iter_mut() {
+            for _ in 0..4 {
+                jugador.agregar_barco(TipoBarco::Acorazado, None, &mut mapa);
+            }
+        }
+
+        let mut ocupadas = std::collections::HashSet::new();
+        for jugador in &jugadores {
+            for barco in &jugador.barcos {
+                for &(x, y) in &barco.posiciones {
+                    assert!(ocupadas.insert((x, y)));
+                    assert_eq!(
+                        mapa.tablero[[y as usize, x as usize]],
+                        jugador.id.to_string().chars().next().unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_desplegar_flota() {
+        let reglas = Reglas {
+            flota_inicial: vec![TipoBarco::Acorazado, TipoBarco::Buque],
+            ..Default::default()
+        };
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &reglas, &mut mapa);
+        jugador
+            .desplegar_flota(
+                &[
+                    (0, (0, 0), Orientacion::Vertical),
+                    (1, (1, 0), Orientacion::Horizontal),
+                ],
+                &mut mapa,
+            )
+            .unwrap();
+        assert_eq!(jugador.barcos[0].posiciones, vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(jugador.barcos[1].posiciones, vec![(1, 0), (2, 0)]);
+        assert_eq!(mapa.tablero[[2, 0]], '1');
+
+        for ubicaciones in [
+            vec![(1, (9, 9), Orientacion::Horizontal)],
+            vec![(1, (0, 1), Orientacion::Horizontal)],
+            vec![
+                (0, (5, 5), Orientacion::Vertical),
+                (0, (6, 6), Orientacion::Vertical),
+            ],
+            vec![(7, (5, 5), Orientacion::Vertical)],
+        ] {
+            assert!(matches!(
+                jugador.desplegar_flota(&ubicaciones, &mut mapa),
+                Err(CustomError::ErrorUbicacionInvalida)
+            ));
+        }
+        assert_eq!(jugador.barcos[1].posiciones, vec![(1, 0), (2, 0)]);
+        assert_eq!(mapa.tablero[[0, 2]], '1');
+        assert_eq!(mapa.tablero.iter().filter(|c| **c == '1').count(), 5);
+    }
+
+    #[test]
+    fn test_ataque_marca_el_mapa_compartido() {
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &Reglas::default(), &mut mapa);
+        let objetivo = jugador.barcos[0].posiciones[0];
+        let mut eventos = Vec::new();
+        assert_eq!(
+            jugador.procesar_ataque(objetivo, &Reglas::default(), &mut mapa, &mut eventos),
+            (15, 100)
+        );
+        assert!(!jugador.esta_vivo());
+        assert_eq!(
+            mapa.tablero[[objetivo.1 as usize, objetivo.0 as usize]],
+            'X'
+        );
+    }
+
+    #[test]
+    fn test_ataque_conserva_las_posiciones_del_barco() {
+        let reglas = Reglas {
+            flota_inicial: vec![TipoBarco::Buque],
+            ..Default::default()
+        };
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &reglas, &mut mapa);
+        let posiciones = jugador.barcos[0].posiciones.clone();
+        let mut eventos = Vec::new();
+        jugador.procesar_ataque(posiciones[0], &reglas, &mut mapa, &mut eventos);
+        jugador.procesar_ataque(posiciones[0], &reglas, &mut mapa, &mut eventos);
+        assert_eq!(eventos.len(), 1);
+        assert_eq!(jugador.barcos[0].posiciones, posiciones);
+        assert_eq!(jugador.barcos[0].dañadas, vec![posiciones[0]]);
+
+        assert_eq!(jugador.reparar_barco(0, &mut mapa), Some(posiciones[0]));
+        assert_eq!(jugador.barcos[0].estado, EstadoBarco::Sano);
+        assert_eq!(
+            mapa.tablero[[posiciones[0].1 as usize, posiciones[0].0 as usize]],
+            '1'
+        );
+        assert_eq!(jugador.reparar_barco(0, &mut mapa), None);
+    }
+
+    #[test]
+    fn test_ataque_usa_recompensas_de_las_reglas() {
+        let reglas = Reglas {
+            puntos_por_impacto: 1,
+            monedas_por_impacto: 2,
+            flota_inicial: vec![TipoBarco::Buque],
+            monedas_iniciales: 0,
+            ..Default::default()
+        };
+        let mut mapa = Mapa::new();
+        let mut jugador = Jugador::new(1, "Jugador 1".to_string(), &reglas, &mut mapa);
+        assert_eq!(jugador.monedas, 0);
+        let objetivo = jugador.barcos[0].posiciones[0];
+        assert_eq!(
+            jugador.procesar_ataque(objetivo, &reglas, &mut mapa, &mut Vec::new()),
+            (1, 2)
+        );
     }
 }
@@ -1,7 +1,24 @@
+pub mod arma;
+pub mod chat;
 pub mod cliente;
+pub mod codec;
+pub mod conexion;
+pub mod configuracion;
+pub mod consola;
+pub mod emparejamiento;
+pub mod enlace;
+pub mod evento;
 pub mod instruccion;
 pub mod juego;
 pub mod jugador;
+pub mod lobby;
 pub mod mapa;
 pub mod mensaje;
+pub mod protocolo;
+pub mod ranking;
+pub mod reglas;
+pub mod sala;
 pub mod server;
+pub mod tienda;
+pub mod trama;
+pub mod vista;
@@ -0,0 +1,581 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+};
+
+use libreria::custom_error::CustomError;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::enlace::Enlace;
+
+/// Caracteres con los que se arman los códigos de sala
+const CARACTERES_CODIGO: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const LONGITUD_CODIGO: usize = 4;
+
+/// Papel con el que alguien entra a una sala
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rol {
+    Jugador,
+    /// Solo mira la partida; si es administrador ve el tablero sin ocultar
+    Espectador {
+        administrador: bool,
+    },
+}
+
+/// Jugador que se une a una sala y cuyo enlace pasa a manejar la sala
+#[derive(Debug)]
+pub struct JugadorEntrante {
+    pub nombre: String,
+    pub enlace: Enlace,
+    pub rol: Rol,
+}
+
+/// Pedido rechazado por el lobby, junto con el jugador para poder avisarle
+pub type Rechazo = (CustomError, JugadorEntrante);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Estructura que resume el estado de una sala para mostrarla en el lobby
+pub struct ResumenSala {
+    pub codigo: String,
+    pub nombre: String,
+    pub jugadores: usize,
+    pub maximo_jugadores: usize,
+    pub privada: bool,
+    pub en_curso: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Estructura que resume quiénes están listos en una sala que todavía no comenzó
+pub struct EstadoPreparacion {
+    /// Nombre de cada jugador y si está listo, en orden de llegada
+    pub jugadores: Vec<(String, bool)>,
+    pub anfitrion: String,
+    pub minimo_jugadores: usize,
+    /// Segundos que faltan para comenzar, si corre la cuenta regresiva
+    pub segundos_para_comenzar: Option<u64>,
+}
+
+struct EntradaSala {
+    nombre: String,
+    contraseña: Option<String>,
+    jugadores: usize,
+    en_curso: bool,
+    entrantes: UnboundedSender<JugadorEntrante>,
+}
+
+/// Sesión de un jugador, con la que puede volver a su sala si pierde la conexión
+struct Sesion {
+    nombre: String,
+    sala: Option<String>,
+}
+
+/// Estructura que lleva el registro de las salas abiertas, de los nombres en uso
+/// y de las sesiones de los jugadores
+///
+/// Es compartida entre las tareas que atienden conexiones nuevas y las tareas
+/// de cada sala. Sus locks nunca se mantienen mientras se espera la red.
+pub struct Lobby {
+    salas: Mutex<HashMap<String, EntradaSala>>,
+    nombres: Mutex<HashSet<String>>,
+    sesiones: Mutex<HashMap<String, Sesion>>,
+    maximo_jugadores: usize,
+}
+
+impl Lobby {
+    /// Función que crea un lobby vacío
+    ///
+    /// # Args
+    ///
+    /// `maximo_jugadores` - Cantidad máxima de jugadores por sala
+    ///
+    /// # Returns
+    ///
+    /// `Lobby` - Lobby creado
+    pub fn new(maximo_jugadores: usize) -> Lobby {
+        Lobby {
+            salas: Mutex::new(HashMap::new()),
+            nombres: Mutex::new(HashSet::new()),
+            sesiones: Mutex::new(HashMap::new()),
+            maximo_jugadores,
+        }
+    }
+
+    /// Función que reserva un nombre de usuario
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre a reservar
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Falso si el nombre ya estaba en uso
+    pub fn registrar_nombre(&self, nombre: &str) -> bool {
+        bloquear(&self.nombres).insert(nombre.to_string())
+    }
+
+    /// Función que libera un nombre de usuario para que pueda volver a usarse
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre a liberar
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn liberar_nombre(&self, nombre: &str) {
+        bloquear(&self.nombres).remove(nombre);
+        bloquear(&self.sesiones).retain(|_, sesion| sesion.nombre != nombre);
+    }
+
+    /// Función que abre una sesión para un jugador ya registrado
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre del jugador
+    ///
+    /// # Returns
+    ///
+    /// `String` - Token con el que el jugador puede reconectarse
+    pub fn crear_sesion(&self, nombre: &str) -> String {
+        let token = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        bloquear(&self.sesiones).insert(
+            token.clone(),
+            Sesion {
+                nombre: nombre.to_string(),
+                sala: None,
+            },
+        );
+        token
+    }
+
+    /// Función que devuelve a un jugador a la sala en la que estaba
+    ///
+    /// # Args
+    ///
+    /// `token` - Token de la sesión del jugador
+    ///
+    /// `enlace` - Enlace con la nueva conexión del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), (CustomError, Enlace)>` - Ok si la sala recibió el nuevo enlace; si no, se devuelve el enlace
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSesionInvalida` - Si el token no corresponde a un jugador dentro de una sala abierta
+    pub fn reconectar(&self, token: &str, enlace: Enlace) -> Result<(), (CustomError, Enlace)> {
+        let (nombre, codigo) = match bloquear(&self.sesiones).get(token) {
+            Some(Sesion {
+                nombre,
+                sala: Some(codigo),
+            }) => (nombre.clone(), codigo.clone()),
+            _ => return Err((CustomError::ErrorSesionInvalida, enlace)),
+        };
+        let salas = bloquear(&self.salas);
+        let Some(sala) = salas.get(&codigo) else {
+            return Err((CustomError::ErrorSesionInvalida, enlace));
+        };
+        sala.entrantes
+            .send(JugadorEntrante {
+                nombre,
+                enlace,
+                rol: Rol::Jugador,
+            })
+            .map_err(|rechazado| (CustomError::ErrorSesionInvalida, rechazado.0.enlace))
+    }
+
+    /// Función que obtiene el resumen de todas las salas abiertas
+    ///
+    /// # Returns
+    ///
+    /// `Vec<ResumenSala>` - Salas ordenadas por código
+    pub fn listar_salas(&self) -> Vec<ResumenSala> {
+        let mut salas: Vec<ResumenSala> = bloquear(&self.salas)
+            .iter()
+            .map(|(codigo, sala)| ResumenSala {
+                codigo: codigo.clone(),
+                nombre: sala.nombre.clone(),
+                jugadores: sala.jugadores,
+                maximo_jugadores: self.maximo_jugadores,
+                privada: sala.contraseña.is_some(),
+                en_curso: sala.en_curso,
+            })
+            .collect();
+        salas.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+        salas
+    }
+
+    /// Función que abre una sala nueva
+    ///
+    /// La sala queda vacía; quien la crea debe unirse con `unirse`.
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre visible de la sala
+    ///
+    /// `contraseña` - Contraseña para unirse, si la sala es privada
+    ///
+    /// # Returns
+    ///
+    /// `(String, UnboundedReceiver<JugadorEntrante>)` - Código de la sala y canal por el que llegan sus jugadores
+    pub fn crear_sala(
+        &self,
+        nombre: String,
+        contraseña: Option<String>,
+    ) -> (String, UnboundedReceiver<JugadorEntrante>) {
+        let mut salas = bloquear(&self.salas);
+        let codigo = loop {
+            let codigo = generar_codigo();
+            if !salas.contains_key(&codigo) {
+                break codigo;
+            }
+        };
+        let (entrantes, receptor) = mpsc::unbounded_channel();
+        salas.insert(
+            codigo.clone(),
+            EntradaSala {
+                nombre,
+                contraseña,
+                jugadores: 0,
+                en_curso: false,
+                entrantes,
+            },
+        );
+        (codigo, receptor)
+    }
+
+    /// Función que une a un jugador a una sala
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código de la sala
+    ///
+    /// `contraseña` - Contraseña ingresada por el jugador
+    ///
+    /// `jugador` - Jugador que se une
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), Rechazo>` - Ok si el jugador quedó en la sala; si no, se devuelve el jugador
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSalaInexistente` - Si no hay una sala con ese código
+    ///
+    /// `CustomError::ErrorSalaEnCurso` - Si la partida de la sala ya comenzó
+    ///
+    /// `CustomError::ErrorSalaLlena` - Si la sala alcanzó el máximo de jugadores
+    ///
+    /// `CustomError::ErrorContraseniaIncorrecta` - Si la contraseña no coincide
+    pub fn unirse(
+        &self,
+        codigo: &str,
+        contraseña: Option<String>,
+        jugador: JugadorEntrante,
+    ) -> Result<(), Rechazo> {
+        let mut salas = bloquear(&self.salas);
+        let Some(sala) = salas.get_mut(&codigo.to_uppercase()) else {
+            return Err((CustomError::ErrorSalaInexistente, jugador));
+        };
+        if sala.en_curso {
+            return Err((CustomError::ErrorSalaEnCurso, jugador));
+        }
+        if sala.jugadores >= self.maximo_jugadores {
+            return Err((CustomError::ErrorSalaLlena, jugador));
+        }
+        if sala.contraseña.is_some() && sala.contraseña != contraseña {
+            return Err((CustomError::ErrorContraseniaIncorrecta, jugador));
+        }
+        let nombre = jugador.nombre.clone();
+        sala.entrantes
+            .send(jugador)
+            .map_err(|rechazado| (CustomError::ErrorSalaInexistente, rechazado.0))?;
+        sala.jugadores += 1;
+        for sesion in bloquear(&self.sesiones)
+            .values_mut()
+            .filter(|sesion| sesion.nombre == nombre)
+        {
+            sesion.sala = Some(codigo.to_uppercase());
+        }
+        Ok(())
+    }
+
+    /// Función que suma un espectador a una sala
+    ///
+    /// Se puede observar una partida en curso o una sala llena, y el
+    /// espectador no cuenta como jugador de la sala.
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código de la sala
+    ///
+    /// `contraseña` - Contraseña ingresada por el espectador
+    ///
+    /// `espectador` - Espectador que entra a la sala
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), Rechazo>` - Ok si el espectador quedó en la sala; si no, se devuelve el espectador
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSalaInexistente` - Si no hay una sala con ese código
+    ///
+    /// `CustomError::ErrorContraseniaIncorrecta` - Si la contraseña no coincide
+    pub fn observar(
+        &self,
+        codigo: &str,
+        contraseña: Option<String>,
+        espectador: JugadorEntrante,
+    ) -> Result<(), Rechazo> {
+        let salas = bloquear(&self.salas);
+        let Some(sala) = salas.get(&codigo.to_uppercase()) else {
+            return Err((CustomError::ErrorSalaInexistente, espectador));
+        };
+        if sala.contraseña.is_some() && sala.contraseña != contraseña {
+            return Err((CustomError::ErrorContraseniaIncorrecta, espectador));
+        }
+        sala.entrantes
+            .send(espectador)
+            .map_err(|rechazado| (CustomError::ErrorSalaInexistente, rechazado.0))
+    }
+
+    /// Función que saca de una sala a un jugador que se fue antes de que comience la partida
+    ///
+    /// Se libera su lugar en la sala y su nombre.
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código de la sala
+    ///
+    /// `nombre` - Nombre del jugador
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn salir(&self, codigo: &str, nombre: &str) {
+        if let Some(sala) = bloquear(&self.salas).get_mut(codigo) {
+            sala.jugadores = sala.jugadores.saturating_sub(1);
+        }
+        self.liberar_nombre(nombre);
+    }
+
+    /// Función que marca que la partida de una sala comenzó, por lo que no acepta más jugadores
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código de la sala
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn marcar_en_curso(&self, codigo: &str) {
+        if let Some(sala) = bloquear(&self.salas).get_mut(codigo) {
+            sala.en_curso = true;
+        }
+    }
+
+    /// Función que cierra una sala y libera los nombres y las sesiones de sus jugadores
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código de la sala
+    ///
+    /// `nombres` - Nombres de los jugadores que estaban en la sala
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn cerrar_sala(&self, codigo: &str, nombres: &[String]) {
+        bloquear(&self.salas).remove(codigo);
+        let mut nombres_en_uso = bloquear(&self.nombres);
+        for nombre in nombres {
+            nombres_en_uso.remove(nombre);
+        }
+        bloquear(&self.sesiones).retain(|_, sesion| !nombres.contains(&sesion.nombre));
+    }
+}
+
+fn bloquear<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|envenenado| envenenado.into_inner())
+}
+
+fn generar_codigo() -> String {
+    let mut rng = rand::thread_rng();
+    (0..LONGITUD_CODIGO)
+        .map(|_| CARACTERES_CODIGO[rng.gen_range(0..CARACTERES_CODIGO.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jugador(nombre: &str) -> JugadorEntrante {
+        let (enlace, _) = Enlace::par();
+        JugadorEntrante {
+            nombre: nombre.to_string(),
+            enlace,
+            rol: Rol::Jugador,
+        }
+    }
+
+    #[test]
+    fn test_registrar_nombre() {
+        let lobby = Lobby::new(4);
+        assert!(lobby.registrar_nombre("ana"));
+        assert!(!lobby.registrar_nombre("ana"));
+        lobby.liberar_nombre("ana");
+        assert!(lobby.registrar_nombre("ana"));
+    }
+
+    #[test]
+    fn test_crear_sala_y_unirse() {
+        let lobby = Lobby::new(4);
+        let (codigo, mut entrantes) = lobby.crear_sala("sala de ana".to_string(), None);
+        assert_eq!(codigo.len(), LONGITUD_CODIGO);
+
+        lobby
+            .unirse(&codigo.to_lowercase(), None, jugador("ana"))
+            .unwrap();
+        assert_eq!(entrantes.try_recv().unwrap().nombre, "ana");
+        assert_eq!(
+            lobby.listar_salas(),
+            vec![ResumenSala {
+                codigo,
+                nombre: "sala de ana".to_string(),
+                jugadores: 1,
+                maximo_jugadores: 4,
+                privada: false,
+                en_curso: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unirse_a_sala_privada() {
+        let lobby = Lobby::new(4);
+        let (codigo, _entrantes) = lobby.crear_sala("privada".to_string(), Some("1234".into()));
+        assert!(matches!(
+            lobby.unirse(&codigo, None, jugador("ana")),
+            Err((CustomError::ErrorContraseniaIncorrecta, _))
+        ));
+        assert!(lobby
+            .unirse(&codigo, Some("1234".into()), jugador("ana"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unirse_rechazado() {
+        let lobby = Lobby::new(1);
+        let (codigo, _entrantes) = lobby.crear_sala("chica".to_string(), None);
+        assert!(matches!(
+            lobby.unirse("ZZZZZ", None, jugador("ana")),
+            Err((CustomError::ErrorSalaInexistente, _))
+        ));
+        lobby.unirse(&codigo, None, jugador("ana")).unwrap();
+        assert!(matches!(
+            lobby.unirse(&codigo, None, jugador("beto")),
+            Err((CustomError::ErrorSalaLlena, _))
+        ));
+
+        let (codigo, _entrantes) = lobby.crear_sala("en curso".to_string(), None);
+        lobby.marcar_en_curso(&codigo);
+        assert!(matches!(
+            lobby.unirse(&codigo, None, jugador("beto")),
+            Err((CustomError::ErrorSalaEnCurso, _))
+        ));
+    }
+
+    #[test]
+    fn test_observar_sala_en_curso() {
+        let lobby = Lobby::new(1);
+        let (codigo, mut entrantes) = lobby.crear_sala("sala".to_string(), Some("1234".into()));
+        lobby
+            .unirse(&codigo, Some("1234".into()), jugador("ana"))
+            .unwrap();
+        lobby.marcar_en_curso(&codigo);
+        let espectador = |nombre: &str| JugadorEntrante {
+            rol: Rol::Espectador {
+                administrador: false,
+            },
+            ..jugador(nombre)
+        };
+
+        assert!(matches!(
+            lobby.observar(&codigo, None, espectador("beto")),
+            Err((CustomError::ErrorContraseniaIncorrecta, _))
+        ));
+        lobby
+            .observar(
+                &codigo.to_lowercase(),
+                Some("1234".into()),
+                espectador("beto"),
+            )
+            .unwrap();
+        entrantes.try_recv().unwrap();
+        assert_eq!(
+            entrantes.try_recv().unwrap().rol,
+            Rol::Espectador {
+                administrador: false
+            }
+        );
+        assert_eq!(lobby.listar_salas()[0].jugadores, 1);
+    }
+
+    #[test]
+    fn test_salir_libera_el_lugar() {
+        let lobby = Lobby::new(1);
+        lobby.registrar_nombre("ana");
+        let (codigo, _entrantes) = lobby.crear_sala("sala".to_string(), None);
+        lobby.unirse(&codigo, None, jugador("ana")).unwrap();
+
+        lobby.salir(&codigo, "ana");
+        assert_eq!(lobby.listar_salas()[0].jugadores, 0);
+        assert!(lobby.registrar_nombre("ana"));
+        assert!(lobby.unirse(&codigo, None, jugador("beto")).is_ok());
+    }
+
+    #[test]
+    fn test_cerrar_sala_libera_nombres() {
+        let lobby = Lobby::new(4);
+        lobby.registrar_nombre("ana");
+        let (codigo, _entrantes) = lobby.crear_sala("sala".to_string(), None);
+        lobby.cerrar_sala(&codigo, &["ana".to_string()]);
+        assert!(lobby.listar_salas().is_empty());
+        assert!(lobby.registrar_nombre("ana"));
+    }
+
+    #[test]
+    fn test_reconectar_con_token() {
+        let lobby = Lobby::new(4);
+        let token = lobby.crear_sesion("ana");
+        let enlace = || Enlace::par().0;
+        assert!(matches!(
+            lobby.reconectar(&token, enlace()),
+            Err((CustomError::ErrorSesionInvalida, _))
+        ));
+
+        let (codigo, mut entrantes) = lobby.crear_sala("sala".to_string(), None);
+        assert!(lobby.unirse(&codigo, None, jugador("ana")).is_ok());
+        lobby.marcar_en_curso(&codigo);
+        assert!(lobby.reconectar(&token, enlace()).is_ok());
+        assert_eq!(entrantes.try_recv().unwrap().nombre, "ana");
+        assert_eq!(entrantes.try_recv().unwrap().nombre, "ana");
+        assert_eq!(lobby.listar_salas()[0].jugadores, 1);
+        assert!(matches!(
+            lobby.reconectar("otro", enlace()),
+            Err((CustomError::ErrorSesionInvalida, _))
+        ));
+
+        lobby.cerrar_sala(&codigo, &["ana".to_string()]);
+        assert!(matches!(
+            lobby.reconectar(&token, enlace()),
+            Err((CustomError::ErrorSesionInvalida, _))
+        ));
+    }
+}
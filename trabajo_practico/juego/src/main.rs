@@ -1,7 +1,16 @@
-use juego::server::Server;
+use juego::{configuracion::ConfiguracionServidor, server::Server};
 
-fn main() {
-    let mut server = match Server::new() {
+#[tokio::main]
+async fn main() {
+    let configuracion = match ConfiguracionServidor::desde_argumentos(std::env::args().skip(1)) {
+        Ok(configuracion) => configuracion,
+        Err(err) => {
+            eprintln!("Error al leer la configuración del servidor: {}", err);
+            return;
+        }
+    };
+
+    let server = match Server::new(configuracion).await {
         Ok(server) => server,
         Err(err) => {
             eprintln!("Error al iniciar el servidor: {:?}", err);
@@ -9,7 +18,7 @@ fn main() {
         }
     };
 
-    if let Err(err) = server.run() {
+    if let Err(err) = server.run().await {
         eprintln!("Error al ejecutar el servidor: {:?}", err);
     }
 }
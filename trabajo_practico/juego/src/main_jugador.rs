@@ -1,14 +1,18 @@
-use juego::cliente::Cliente;
+use juego::{cliente::Cliente, configuracion::ConfiguracionCliente};
 use libreria::custom_error::CustomError;
 
 fn main() -> Result<(), CustomError> {
-    let addr = "127.0.0.1:8080".to_owned();
-    let mut cliente = match Cliente::new(addr, 1.to_owned(), "".to_string()) {
+    let configuracion = ConfiguracionCliente::desde_argumentos(std::env::args().skip(1))?;
+    let mut cliente = match Cliente::new(configuracion.direccion(), 1.to_owned(), "".to_string()) {
         Ok(cliente) => cliente,
         Err(_) => {
             return Err(CustomError::ErrorCreatingSocket);
         }
     };
+    if let Some(token) = configuracion.token {
+        cliente.usar_token(token);
+    }
+    cliente.usar_codec(configuracion.codec);
     println!("Nueva sesión iniciada");
     cliente.run()?;
 
@@ -1,11 +1,8 @@
-use std::{io::Write, net::TcpStream};
-
-use barcos::barco::Barco;
-use libreria::custom_error::CustomError;
+use barcos::{barco::Barco, orientacion::Orientacion};
+use libreria::constantes::{COLUMNAS_MAPA, FILAS_MAPA};
 use ndarray::Array2;
-use rand::Rng;
-
-use crate::{mensaje::Mensaje, server::Server};
+use rand::{seq::SliceRandom, Rng};
+use std::collections::{HashSet, VecDeque};
 
 #[derive(Clone)]
 
@@ -26,7 +23,21 @@ impl Mapa {
     ///
     /// `Mapa` - Mapa creado
     pub fn new() -> Mapa {
-        let tablero = Array2::from_elem((10, 10), '.');
+        Self::con_dimensiones(FILAS_MAPA, COLUMNAS_MAPA)
+    }
+    /// Función que crea un nuevo mapa con las dimensiones indicadas
+    ///
+    /// # Args
+    ///
+    /// `filas` - Cantidad de filas del tablero
+    ///
+    /// `columnas` - Cantidad de columnas del tablero
+    ///
+    /// # Returns
+    ///
+    /// `Mapa` - Mapa creado
+    pub fn con_dimensiones(filas: usize, columnas: usize) -> Mapa {
+        let tablero = Array2::from_elem((filas, columnas), '.');
         Mapa { tablero }
     }
     /// Función que establece un valor en una posición del tablero
@@ -76,7 +87,7 @@ impl Mapa {
         let fil_i32 = i32::try_from(fil).expect("Error");
         let col_i32 = i32::try_from(col).expect("Error");
 
-        (fil_i32, col_i32)
+        (col_i32, fil_i32)
     }
     /// Función que imprime el tablero
     ///
@@ -100,45 +111,42 @@ impl Mapa {
             println!();
         }
     }
-    pub fn enviar_tablero(
-        &self,
-        id: String,
-        server: &Server,
-        barcos: &[Barco],
-        monedas: usize,
-    ) -> Result<(), CustomError> {
-        let jugador: char = id
-            .chars()
-            .next()
-            .ok_or(CustomError::ErrorAceptandoConexion)?;
+    /// Función que obtiene el tablero tal como lo ve un jugador
+    ///
+    /// Las posiciones de los barcos de los demás jugadores se ocultan; las
+    /// casillas golpeadas quedan visibles para todos.
+    ///
+    /// # Args
+    ///
+    /// `id` - Identificador del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Vec<char>>` - Filas del tablero ocultado
+    pub fn tablero_para(&self, id: String) -> Vec<Vec<char>> {
+        let jugador: char = id.chars().next().unwrap_or('.');
 
         let mut tablero_ocultado = self.tablero.clone();
         for ((_, _), cell) in tablero_ocultado.indexed_iter_mut() {
-            if *cell != '.' && *cell != jugador {
+            if *cell != '.' && *cell != 'X' && *cell != jugador {
                 *cell = '.';
             }
         }
-        let tablero_vec: Vec<Vec<char>> = tablero_ocultado
+        tablero_ocultado
             .outer_iter()
             .map(|row| row.to_vec())
-            .collect();
-
-        let barcos_serializados = self.serializar_barcos(barcos);
-
-        if let Some(conexion) = server
-            .conexiones_jugadores
-            .get(&id.parse().unwrap_or_default())
-        {
-            let conexion = conexion
-                .lock()
-                .map_err(|_| CustomError::ErrorAceptandoConexion)?;
-            let mensaje_serializado =
-                serde_json::to_string(&Mensaje::Tablero(tablero_vec, barcos_serializados, monedas))
-                    .unwrap();
-            Self::enviar_mensaje(&conexion, mensaje_serializado.as_bytes().to_vec())?;
-        }
+            .collect()
+    }
 
-        Ok(())
+    /// Función que obtiene el tablero sin ocultar ningún barco
+    ///
+    /// Solo se envía a los espectadores con permisos de administrador.
+    ///
+    /// # Returns
+    ///
+    /// `Vec<Vec<char>>` - Filas del tablero completo
+    pub fn tablero_completo(&self) -> Vec<Vec<char>> {
+        self.tablero.outer_iter().map(|row| row.to_vec()).collect()
     }
 
     pub fn serializar_barcos(&self, barcos: &[Barco]) -> Vec<(usize, Vec<(i32, i32)>)> {
@@ -221,6 +229,25 @@ impl Mapa {
         }
     }
 
+    /// Función que vuelve a mostrar como parte del barco una posición reparada
+    ///
+    /// # Args
+    ///
+    /// `id` - Identificador del jugador dueño del barco
+    ///
+    /// `coordenadas` - Coordenadas de la posición reparada
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn marcar_reparado(&mut self, id: String, coordenadas: (i32, i32)) {
+        let (x, y) = coordenadas;
+        let jugador: char = id.chars().next().unwrap_or('.');
+        if x >= 0 && x < self.tablero.ncols() as i32 && y >= 0 && y < self.tablero.nrows() as i32 {
+            self.tablero[[y as usize, x as usize]] = jugador;
+        }
+    }
+
     /// Función que obtiene las coordenadas contiguas a una posicion dada
     ///
     /// # Args
@@ -229,88 +256,135 @@ impl Mapa {
     ///
     /// `tamaño_barco` - Tamaño del barco
     ///
+    /// `orientacion` - Hacia dónde se extiende el barco desde la posición
+    ///
     /// # Returns
     ///
-    /// `Vec<(i32, i32)>` - Coordenadas contiguas
+    /// `Vec<(i32, i32)>` - Coordenadas contiguas, vacío si alguna está ocupada o fuera del tablero
     pub fn obtener_coordenadas_contiguas(
         &self,
         coordenada_destino: (i32, i32),
         tamano_barco: usize,
+        orientacion: Orientacion,
     ) -> Vec<(i32, i32)> {
-        let mut coordenadas_contiguas = Vec::new();
-        let (x, y) = coordenada_destino;
-
-        if self.es_coordenada_vacia(coordenada_destino) {
-            coordenadas_contiguas.push((x, y));
-
-            for i in 1..tamano_barco {
-                let coordenada_horizontal = (x + i as i32, y);
-                if self.es_coordenada_vacia(coordenada_horizontal) {
-                    coordenadas_contiguas.push(coordenada_horizontal);
-                } else {
-                    coordenadas_contiguas.clear();
-                    break;
-                }
-            }
-
-            if coordenadas_contiguas.len() == tamano_barco {
-                for i in 1..tamano_barco {
-                    let coordenada_vertical = (x, y + i as i32);
-                    if self.es_coordenada_vacia(coordenada_vertical) {
-                        coordenadas_contiguas.push(coordenada_vertical);
-                    } else {
-                        coordenadas_contiguas.clear();
-                        break;
-                    }
-                }
-            }
+        let coordenadas_contiguas = orientacion.posiciones(coordenada_destino, tamano_barco);
+        if coordenadas_contiguas
+            .iter()
+            .all(|&coordenada| self.es_coordenada_vacia(coordenada))
+        {
+            coordenadas_contiguas
+        } else {
+            Vec::new()
         }
-
-        coordenadas_contiguas
     }
     /// Función que obtiene posiciones libres contiguas en el tablero
     ///
+    /// Las posiciones se eligen al azar entre las que no se superponen con
+    /// ningún otro barco del mapa y quedan ocupadas por el jugador. Si no se
+    /// indica una orientación, también se elige al azar.
+    ///
     /// # Args
     ///
     /// `id` - Identificador del jugador
     ///
     /// `tamaño` - Tamaño del barco
     ///
+    /// `orientacion` - Orientación pedida para el barco, si se eligió una
+    ///
     /// # Returns
     ///
-    /// `Vec<(i32, i32)>` - Posiciones libres contiguas
+    /// `Option<(Vec<(i32, i32)>, Orientacion)>` - Posiciones libres contiguas y su orientación, `None` si no hay lugar en el mapa
     pub fn obtener_posiciones_libres_contiguas(
         &mut self,
         id: String,
         tamaño: usize,
-    ) -> Vec<(i32, i32)> {
+        orientacion: Option<Orientacion>,
+    ) -> Option<(Vec<(i32, i32)>, Orientacion)> {
         let mut rng = rand::thread_rng();
         let (nrows, ncols) = (self.tablero.nrows(), self.tablero.ncols());
         let jugador: char = id.chars().next().unwrap();
 
-        loop {
-            let fil = rng.gen_range(0..nrows) as i32;
-            let col = rng.gen_range(0..ncols) as i32;
+        let mut origenes: Vec<(i32, i32)> = (0..nrows)
+            .flat_map(|fil| (0..ncols).map(move |col| (col as i32, fil as i32)))
+            .collect();
+        origenes.shuffle(&mut rng);
+        let mut orientaciones = match orientacion {
+            Some(orientacion) => vec![orientacion],
+            None => vec![Orientacion::Horizontal, Orientacion::Vertical],
+        };
 
-            let mut posiciones = Vec::new();
-            for i in 0..tamaño {
-                let coord = (col + i as i32, fil);
-                if self.es_coordenada_vacia(coord) {
-                    posiciones.push(coord);
-                } else {
-                    break;
+        for origen in origenes {
+            orientaciones.shuffle(&mut rng);
+            for &orientacion in &orientaciones {
+                let posiciones = self.obtener_coordenadas_contiguas(origen, tamaño, orientacion);
+                if !posiciones.is_empty() {
+                    for &(x, y) in &posiciones {
+                        self.tablero[[y as usize, x as usize]] = jugador;
+                    }
+                    return Some((posiciones, orientacion));
                 }
             }
+        }
+        None
+    }
 
-            if posiciones.len() == tamaño {
-                for &(x, y) in &posiciones {
-                    self.tablero[[y as usize, x as usize]] = jugador;
-                }
-                return posiciones;
+    /// Función que ocupa posiciones del tablero con un barco del jugador
+    ///
+    /// # Args
+    ///
+    /// `id` - Identificador del jugador
+    ///
+    /// `posiciones` - Posiciones que ocupará el barco
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si todas las posiciones estaban libres; si no, el tablero no cambia
+    pub fn ocupar_posiciones(&mut self, id: String, posiciones: &[(i32, i32)]) -> bool {
+        let jugador: char = id.chars().next().unwrap_or('.');
+        if posiciones.is_empty() || !posiciones.iter().all(|&p| self.es_coordenada_vacia(p)) {
+            return false;
+        }
+        for &(x, y) in posiciones {
+            self.tablero[[y as usize, x as usize]] = jugador;
+        }
+        true
+    }
+
+    /// Función que deja libres posiciones del tablero
+    ///
+    /// # Args
+    ///
+    /// `posiciones` - Posiciones a liberar
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn liberar_posiciones(&mut self, posiciones: &[(i32, i32)]) {
+        for &(x, y) in posiciones {
+            if x >= 0
+                && y >= 0
+                && x < self.tablero.ncols() as i32
+                && y < self.tablero.nrows() as i32
+            {
+                self.tablero[[y as usize, x as usize]] = '.';
             }
         }
     }
 
+    /// Función que verifica si una coordenada está dentro del tablero
+    ///
+    /// # Args
+    ///
+    /// `coordenada` - Coordenada a verificar
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si la coordenada está dentro del tablero
+    pub fn contiene(&self, coordenada: (i32, i32)) -> bool {
+        let (x, y) = coordenada;
+        x >= 0 && y >= 0 && x < self.tablero.ncols() as i32 && y < self.tablero.nrows() as i32
+    }
+
     /// Función que verifica si una coordenada está vacía
     ///
     /// # Args
@@ -322,23 +396,72 @@ impl Mapa {
     /// `bool` - Verdadero si la coordenada está vacía, falso en caso contrario
     pub fn es_coordenada_vacia(&self, coordenada: (i32, i32)) -> bool {
         let (x, y) = coordenada;
-        if x >= 0 && y >= 0 && x < self.tablero.ncols() as i32 && y < self.tablero.nrows() as i32 {
-            return self.tablero[[y as usize, x as usize]] == '.';
+        self.contiene(coordenada) && self.tablero[[y as usize, x as usize]] == '.'
+    }
+
+    /// Función que verifica si un barco puede llegar a un destino sin atravesar otros barcos
+    ///
+    /// El barco avanza de a un casillero, hacia arriba, abajo o los costados,
+    /// y conserva su orientación durante el recorrido. Si tiene que moverse,
+    /// todos sus casilleros tienen que estar libres al partir y en cada paso
+    /// intermedio; el destino se verifica aparte. Los casilleros que ocupa el
+    /// propio barco tienen que estar liberados.
+    ///
+    /// # Args
+    ///
+    /// `desde` - Primer casillero del barco antes de moverse
+    ///
+    /// `hasta` - Primer casillero del barco en el destino
+    ///
+    /// `tamaño` - Tamaño del barco
+    ///
+    /// `orientacion` - Orientación del barco durante el recorrido
+    ///
+    /// `alcance` - Cantidad máxima de pasos
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si hay un camino libre de a lo sumo `alcance` pasos
+    pub fn hay_camino(
+        &self,
+        desde: (i32, i32),
+        hasta: (i32, i32),
+        tamaño: usize,
+        orientacion: Orientacion,
+        alcance: usize,
+    ) -> bool {
+        let libre = |origen: (i32, i32)| {
+            orientacion
+                .posiciones(origen, tamaño)
+                .into_iter()
+                .all(|coordenada| self.es_coordenada_vacia(coordenada))
+        };
+        if desde != hasta && !libre(desde) {
+            return false;
+        }
+        let mut visitados = HashSet::from([desde]);
+        let mut pendientes = VecDeque::from([(desde, 0)]);
+        while let Some(((x, y), pasos)) = pendientes.pop_front() {
+            if (x, y) == hasta {
+                return true;
+            }
+            if pasos == alcance {
+                continue;
+            }
+            for siguiente in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if (siguiente == hasta || libre(siguiente)) && visitados.insert(siguiente) {
+                    pendientes.push_back((siguiente, pasos + 1));
+                }
+            }
         }
         false
     }
-    fn enviar_mensaje(mut stream: &TcpStream, msg: Vec<u8>) -> Result<(), CustomError> {
-        let result_stream = stream.write_all(&msg);
-        result_stream.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        let result_flush = stream.flush();
-        result_flush.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        Ok(())
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
     #[test]
     fn test_mapa_new() {
         let mapa = Mapa::new();
@@ -346,6 +469,24 @@ mod tests {
         assert_eq!(mapa.tablero.ncols(), 10);
     }
 
+    #[test]
+    fn test_mapa_con_dimensiones() {
+        let mapa = Mapa::con_dimensiones(4, 7);
+        assert_eq!(mapa.tablero.nrows(), 4);
+        assert_eq!(mapa.tablero.ncols(), 7);
+        assert_eq!(mapa.tablero_para("0".to_string())[0].len(), 7);
+    }
+
+    #[test]
+    fn test_tablero_completo_no_oculta_barcos() {
+        let mut mapa = Mapa::con_dimensiones(1, 3);
+        mapa.set(0, 0, '0');
+        mapa.set(0, 1, 'X');
+        mapa.set(0, 2, '1');
+        assert_eq!(mapa.tablero_completo(), vec![vec!['0', 'X', '1']]);
+        assert_eq!(mapa.tablero_para(String::new()), vec![vec!['.', 'X', '.']]);
+    }
+
     #[test]
     fn test_mapa_set() {
         let mut mapa = Mapa::new();
@@ -357,7 +498,76 @@ mod tests {
     fn test_mapa_obtener_posicion_libre() {
         let mut mapa = Mapa::new();
         let (x, y) = mapa.obtener_posicion_libre("a".to_string());
-        assert_eq!(mapa.tablero[[y as usize, x as usize]], '.');
+        assert_eq!(mapa.tablero[[y as usize, x as usize]], 'a');
+        assert_eq!(mapa.tablero.iter().filter(|&&cell| cell == 'a').count(), 1);
+    }
+
+    #[test]
+    fn test_posiciones_libres_no_se_superponen() {
+        let mut mapa = Mapa::new();
+        for _ in 0..100 {
+            let (posiciones, _) = mapa
+                .obtener_posiciones_libres_contiguas("1".to_string(), 1, None)
+                .unwrap();
+            assert_eq!(posiciones.len(), 1);
+        }
+        assert!(mapa.tablero.iter().all(|&cell| cell == '1'));
+        assert!(mapa
+            .obtener_posiciones_libres_contiguas("2".to_string(), 1, None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_posiciones_libres_respetan_la_orientacion() {
+        let mut mapa = Mapa::con_dimensiones(1, 3);
+        assert!(mapa
+            .obtener_posiciones_libres_contiguas("1".to_string(), 3, Some(Orientacion::Vertical))
+            .is_none());
+        let (posiciones, orientacion) = mapa
+            .obtener_posiciones_libres_contiguas("1".to_string(), 3, None)
+            .unwrap();
+        assert_eq!(posiciones, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(orientacion, Orientacion::Horizontal);
+    }
+
+    #[test]
+    fn test_coordenadas_contiguas_segun_orientacion() {
+        let mut mapa = Mapa::con_dimensiones(3, 3);
+        mapa.tablero[[0, 2]] = '1';
+        assert_eq!(
+            mapa.obtener_coordenadas_contiguas((1, 0), 2, Orientacion::Vertical),
+            vec![(1, 0), (1, 1)]
+        );
+        assert!(mapa
+            .obtener_coordenadas_contiguas((1, 0), 2, Orientacion::Horizontal)
+            .is_empty());
+        assert!(mapa
+            .obtener_coordenadas_contiguas((1, 2), 2, Orientacion::Vertical)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_hay_camino_rodea_barcos() {
+        let mut mapa = Mapa::con_dimensiones(3, 3);
+        mapa.tablero[[0, 1]] = '1';
+        mapa.tablero[[1, 1]] = '1';
+        assert!(mapa.hay_camino((0, 0), (2, 0), 1, Orientacion::Horizontal, 6));
+        assert!(!mapa.hay_camino((0, 0), (2, 0), 1, Orientacion::Horizontal, 5));
+        mapa.tablero[[2, 1]] = 'X';
+        assert!(!mapa.hay_camino((0, 0), (2, 0), 1, Orientacion::Horizontal, 10));
+        assert!(mapa.hay_camino((0, 0), (0, 1), 2, Orientacion::Vertical, 1));
+        assert!(!mapa.hay_camino((1, 0), (2, 0), 2, Orientacion::Vertical, 1));
+    }
+
+    #[test]
+    fn test_ocupar_posiciones() {
+        let mut mapa = Mapa::con_dimensiones(3, 3);
+        assert!(mapa.ocupar_posiciones("1".to_string(), &[(0, 0), (0, 1)]));
+        assert_eq!(mapa.tablero[[1, 0]], '1');
+        assert!(!mapa.ocupar_posiciones("2".to_string(), &[(1, 1), (0, 1)]));
+        assert!(!mapa.ocupar_posiciones("2".to_string(), &[(2, 2), (3, 2)]));
+        assert!(mapa.es_coordenada_vacia((1, 1)));
+        assert!(mapa.es_coordenada_vacia((2, 2)));
     }
 
     #[test]
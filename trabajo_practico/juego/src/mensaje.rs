@@ -1,29 +1,123 @@
-use crate::instruccion::Instruccion;
+use crate::{
+    arma::TipoArma,
+    evento::EventoPublico,
+    instruccion::Instruccion,
+    lobby::{EstadoPreparacion, ResumenSala},
+    tienda::{ArticuloCatalogo, ArticuloTienda},
+    vista::{BarcosSerializados, CambioTablero},
+};
+use barcos::{orientacion::Orientacion, tipo_barco::TipoBarco};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Mensaje {
     PreguntaComienzo,
     RealiceAccion,
     Esperando,
     ComenzoJuego,
     NombreEnUso,
+    Lobby,
+    ListarSalas,
+    Salas(Vec<ResumenSala>),
+    CrearSala(String, Option<String>),
+    UnirseSala(String, Option<String>),
+    SalaUnida(String),
+    SalaRechazada(String),
     Puntos(usize),
     Perdiste(usize),
     Ganaste(usize),
     NotificacionEliminacion(String),
     Tablero(Vec<Vec<char>>, Vec<(usize, Vec<(i32, i32)>)>, usize),
-    Accion(Instruccion, usize),
-    AbrirTienda(usize),
+    Accion(Instruccion),
+    AbrirTienda(Vec<ArticuloTienda>, usize),
     RepetirAccion(String, Vec<(usize, Vec<(i32, i32)>)>, usize),
-    BarcoGolpead((i32, i32)),
+    /// Conserva el nombre con el que viaja desde la primera versión del protocolo
+    #[serde(rename = "BarcoGolpead")]
+    BarcoGolpeado((i32, i32)),
     BarcoHundido,
     MensajeInfoAtaque(usize, usize),
     EventoSorpresa,
     EventoSorpresaResultado(bool),
+    Hola {
+        version: u32,
+        capacidades: Vec<String>,
+    },
+    Bienvenida {
+        version: u32,
+        capacidades: Vec<String>,
+    },
+    VersionRechazada(String),
     Registro,
+    NombreUsuario(String),
+    Sesion(String),
+    Reconectar(String),
+    SesionRechazada(String),
+    Reanudar(Vec<Vec<char>>, Vec<(usize, Vec<(i32, i32)>)>, usize),
+    NotificacionDesconexion(String),
+    NotificacionReconexion(String),
+    Ping,
+    Pong,
+    TurnoAgotado,
+    RespuestaComienzo(bool),
+    RespuestaEventoSorpresa(String),
     Ranking(Vec<(String, usize)>),
-    CompraExitosa(usize, usize),
+    CompraExitosa(TipoBarco, usize),
+    CompraRechazada(String, usize),
     NotificacionCompra(String, usize),
     FinPartida(String, usize),
+    Instantanea(u64, Vec<Vec<char>>, BarcosSerializados, usize),
+    Delta(u64, Vec<CambioTablero>, usize),
+    PedirResincronizacion,
+    Observar {
+        codigo: String,
+        contraseña: Option<String>,
+        clave_administrador: Option<String>,
+    },
+    Observando(String, bool),
+    Transmision(EventoPublico),
+    TableroEspectador(Vec<Vec<char>>),
+    Chat {
+        para: Option<String>,
+        texto: String,
+    },
+    ChatRecibido {
+        de: String,
+        texto: String,
+        privado: bool,
+    },
+    ChatRechazado(String),
+    Silenciar(String, bool),
+    EstadoSala(EstadoPreparacion),
+    ForzarComienzo,
+    ComienzoRechazado(String),
+    BuscarPartida,
+    EnCola(usize, usize),
+    Despliegue(Vec<Vec<char>>, BarcosSerializados, u64),
+    DesplegarFlota(Vec<(usize, (i32, i32), Orientacion)>),
+    AutoDespliegue,
+    DespliegueRechazado(String),
+    FlotaDesplegada(BarcosSerializados),
+    ReparacionExitosa((i32, i32), usize),
+    Catalogo(Vec<ArticuloCatalogo>, usize),
+    ArmaComprada(TipoArma, usize, usize),
+    ResultadoBomba(Vec<(i32, i32)>, usize, usize),
+    ResultadoTorpedo(Option<(i32, i32)>, usize, usize),
+    ResultadoSonar((i32, i32), bool),
+}
+
+impl Mensaje {
+    /// Función que indica si el mensaje es parte del saludo inicial
+    ///
+    /// Estos mensajes viajan siempre en JSON, ya que se envían antes de
+    /// acordar el codec.
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si es `Hola`, `Bienvenida` o `VersionRechazada`
+    pub fn es_saludo(&self) -> bool {
+        matches!(
+            self,
+            Mensaje::Hola { .. } | Mensaje::Bienvenida { .. } | Mensaje::VersionRechazada(_)
+        )
+    }
 }
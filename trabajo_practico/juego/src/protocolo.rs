@@ -0,0 +1,226 @@
+use libreria::custom_error::CustomError;
+
+use crate::codec::Codec;
+
+/// Versión del protocolo que implementan este servidor y este cliente
+pub const VERSION_PROTOCOLO: u32 = 3;
+/// Versión más antigua que el servidor sigue atendiendo
+///
+/// La versión 1 corresponde a los clientes que no envían `Hola`.
+pub const VERSION_MINIMA: u32 = 1;
+/// Versión desde la que los mensajes binarios tienen las mismas etiquetas que en este servidor
+///
+/// Hasta la versión 2 se agregaron variantes en medio de `Mensaje` e
+/// `Instruccion`, por lo que el binario de esos clientes no coincide con el
+/// actual. Con ellos se usa JSON, que identifica las variantes por nombre.
+pub const VERSION_MINIMA_BINARIO: u32 = 3;
+
+/// Capacidad de recibir el token de sesión y volver a una partida
+pub const RECONEXION: &str = "reconexion";
+/// Capacidad de contestar los `Ping` del servidor
+pub const LATIDO: &str = "latido";
+/// Capacidad de intercambiar los mensajes con el codec binario
+pub const BINARIO: &str = "binario";
+/// Capacidad de recibir el tablero como instantánea y cambios numerados
+pub const DELTAS: &str = "deltas";
+/// Capacidad de recibir el estado de preparación de la sala en lugar de la pregunta de comienzo
+pub const PREPARACION: &str = "preparacion";
+
+/// Capacidad de ubicar la flota a mano antes del primer turno
+pub const DESPLIEGUE: &str = "despliegue";
+/// Capacidad de recibir el catálogo completo de la tienda y comprar reparaciones
+pub const REPARACION: &str = "reparacion";
+/// Capacidad de comprar y usar las armas especiales
+pub const ARMAS: &str = "armas";
+/// Capacidad de enviar y recibir mensajes de chat
+pub const CHAT: &str = "chat";
+
+/// Capacidades que conoce esta versión del protocolo
+pub const CAPACIDADES: [&str; 9] = [
+    RECONEXION,
+    LATIDO,
+    BINARIO,
+    DELTAS,
+    PREPARACION,
+    DESPLIEGUE,
+    REPARACION,
+    ARMAS,
+    CHAT,
+];
+
+/// Estructura con la versión y las capacidades acordadas con un cliente
+///
+/// El servidor solo le envía a cada cliente los mensajes de las
+/// capacidades que ambos admiten.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Protocolo {
+    pub version: u32,
+    pub capacidades: Vec<String>,
+}
+
+impl Protocolo {
+    /// Función que obtiene el protocolo completo de esta versión
+    ///
+    /// # Returns
+    ///
+    /// `Protocolo` - Versión actual con todas sus capacidades
+    pub fn actual() -> Protocolo {
+        Protocolo {
+            version: VERSION_PROTOCOLO,
+            capacidades: CAPACIDADES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Función que obtiene el protocolo de un cliente que no se presentó con `Hola`
+    ///
+    /// # Returns
+    ///
+    /// `Protocolo` - Versión mínima, sin capacidades
+    pub fn sin_saludo() -> Protocolo {
+        Protocolo {
+            version: VERSION_MINIMA,
+            capacidades: Vec::new(),
+        }
+    }
+
+    /// Función que obtiene el protocolo que anuncia un cliente o admite un servidor
+    ///
+    /// Solo incluye la capacidad binaria si se eligió ese codec.
+    ///
+    /// # Args
+    ///
+    /// `codec` - Codec elegido en la configuración
+    ///
+    /// # Returns
+    ///
+    /// `Protocolo` - Versión actual con las capacidades que corresponden al codec
+    pub fn con_codec(codec: Codec) -> Protocolo {
+        let mut protocolo = Protocolo::actual();
+        if codec == Codec::Json {
+            protocolo.capacidades.retain(|c| c != BINARIO);
+        }
+        protocolo
+    }
+
+    /// Función que acuerda el protocolo con el que se atiende a un cliente
+    ///
+    /// Se usa la menor de las dos versiones y solo las capacidades que
+    /// anuncia el cliente y admite el servidor. A los clientes anteriores a
+    /// `VERSION_MINIMA_BINARIO` no se les acuerda el codec binario.
+    ///
+    /// # Args
+    ///
+    /// `version` - Versión del protocolo del cliente
+    ///
+    /// `capacidades` - Capacidades que anuncia el cliente
+    ///
+    /// `admitidas` - Capacidades que admite el servidor
+    ///
+    /// # Returns
+    ///
+    /// `Result<Protocolo, CustomError>` - Protocolo acordado
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorVersionIncompatible` - Si la versión del cliente es anterior a la mínima
+    pub fn negociar(
+        version: u32,
+        capacidades: &[String],
+        admitidas: &[String],
+    ) -> Result<Protocolo, CustomError> {
+        if version < VERSION_MINIMA {
+            return Err(CustomError::ErrorVersionIncompatible);
+        }
+        Ok(Protocolo {
+            version: version.min(VERSION_PROTOCOLO),
+            capacidades: admitidas
+                .iter()
+                .filter(|admitida| capacidades.contains(admitida))
+                .filter(|admitida| version >= VERSION_MINIMA_BINARIO || *admitida != BINARIO)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Función que indica si el cliente admite una capacidad
+    ///
+    /// # Args
+    ///
+    /// `capacidad` - Nombre de la capacidad
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si la capacidad fue acordada
+    pub fn admite(&self, capacidad: &str) -> bool {
+        self.capacidades.iter().any(|c| c == capacidad)
+    }
+
+    /// Función que obtiene el codec con el que se intercambian los mensajes después del saludo
+    ///
+    /// # Returns
+    ///
+    /// `Codec` - Binario si se acordó esa capacidad; si no, JSON
+    pub fn codec(&self) -> Codec {
+        if self.admite(BINARIO) {
+            Codec::Binario
+        } else {
+            Codec::Json
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negociar_conserva_capacidades_comunes() {
+        let capacidades = vec![LATIDO.to_string(), "telepatia".to_string()];
+        let admitidas = Protocolo::actual().capacidades;
+        let protocolo =
+            Protocolo::negociar(VERSION_PROTOCOLO + 3, &capacidades, &admitidas).unwrap();
+
+        assert_eq!(protocolo.version, VERSION_PROTOCOLO);
+        assert_eq!(protocolo.capacidades, vec![LATIDO.to_string()]);
+        assert!(protocolo.admite(LATIDO));
+        assert!(!protocolo.admite(RECONEXION));
+    }
+
+    #[test]
+    fn test_negociar_rechaza_versiones_anteriores() {
+        assert!(matches!(
+            Protocolo::negociar(VERSION_MINIMA - 1, &[], &[]),
+            Err(CustomError::ErrorVersionIncompatible)
+        ));
+    }
+
+    #[test]
+    fn test_sin_saludo_no_admite_capacidades() {
+        let protocolo = Protocolo::sin_saludo();
+        assert_eq!(protocolo.version, VERSION_MINIMA);
+        assert!(CAPACIDADES.iter().all(|c| !protocolo.admite(c)));
+        assert!(CAPACIDADES.iter().all(|c| Protocolo::actual().admite(c)));
+    }
+
+    #[test]
+    fn test_binario_solo_si_ambos_lo_admiten() {
+        let cliente = Protocolo::con_codec(Codec::Binario).capacidades;
+        let servidor_json = Protocolo::con_codec(Codec::Json).capacidades;
+
+        let acordado = Protocolo::negociar(VERSION_PROTOCOLO, &cliente, &servidor_json).unwrap();
+        assert_eq!(acordado.codec(), Codec::Json);
+        assert!(acordado.admite(LATIDO));
+        let acordado = Protocolo::negociar(VERSION_PROTOCOLO, &cliente, &cliente).unwrap();
+        assert_eq!(acordado.codec(), Codec::Binario);
+        assert_eq!(Protocolo::sin_saludo().codec(), Codec::Json);
+    }
+
+    #[test]
+    fn test_versiones_anteriores_usan_json() {
+        let cliente = Protocolo::con_codec(Codec::Binario).capacidades;
+        let acordado = Protocolo::negociar(VERSION_MINIMA_BINARIO - 1, &cliente, &cliente).unwrap();
+        assert_eq!(acordado.version, VERSION_MINIMA_BINARIO - 1);
+        assert_eq!(acordado.codec(), Codec::Json);
+        assert!(acordado.admite(DELTAS));
+    }
+}
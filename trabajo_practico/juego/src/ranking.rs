@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter};
+use std::sync::Mutex;
+
+use libreria::custom_error::CustomError;
+
+use crate::jugador::Jugador;
+
+const DIRECTORIO_RANKING: &str = "../archivos";
+const ARCHIVO_RANKING: &str = "../archivos/ranking.json";
+
+/// Evita que dos salas que terminan a la vez pisen el archivo de ranking
+static BLOQUEO_RANKING: Mutex<()> = Mutex::new(());
+
+/// Función que obtiene el ranking ordenado de mayor a menor puntaje
+///
+/// # Returns
+///
+/// `Result<Vec<(String, usize)>, CustomError>` - Nombres y puntos de los jugadores
+///
+/// # Errors
+///
+/// `CustomError::ErrorMostrandoRanking` - Si no se pudo abrir el archivo de ranking
+pub fn obtener_ranking() -> Result<Vec<(String, usize)>, CustomError> {
+    let _bloqueo = BLOQUEO_RANKING.lock().unwrap_or_else(|e| e.into_inner());
+    let file = File::open(ARCHIVO_RANKING).map_err(|_| CustomError::ErrorMostrandoRanking)?;
+    let reader = BufReader::new(file);
+
+    let rankings: HashMap<String, usize> = serde_json::from_reader(reader).unwrap_or_default();
+
+    let mut ranking_sorted: Vec<_> = rankings.into_iter().collect();
+    ranking_sorted.sort_by_key(|(_, puntos)| std::cmp::Reverse(*puntos));
+    Ok(ranking_sorted)
+}
+
+/// Función que suma los puntos de los jugadores al ranking
+///
+/// # Args
+///
+/// `jugadores` - Jugadores de la partida finalizada
+///
+/// # Returns
+///
+/// `Result<(), CustomError>` - Resultado de la ejecución
+///
+/// # Errors
+///
+/// `CustomError` - Error personalizado
+pub fn actualizar_ranking(jugadores: &[Jugador]) -> Result<(), CustomError> {
+    let _bloqueo = BLOQUEO_RANKING.lock().unwrap_or_else(|e| e.into_inner());
+    fs::create_dir_all(DIRECTORIO_RANKING).map_err(|_| CustomError::ErrorMostrandoRanking)?;
+
+    let mut rankings: HashMap<String, usize> = if let Ok(archivo) = File::open(ARCHIVO_RANKING) {
+        let reader = BufReader::new(archivo);
+        serde_json::from_reader(reader).unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+
+    for jugador in jugadores {
+        *rankings.entry(jugador.nombre_usuario.clone()).or_insert(0) += jugador.puntos;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(ARCHIVO_RANKING)
+        .map_err(|_| CustomError::ErrorMostrandoRanking)?;
+
+    let writer = BufWriter::new(file);
+
+    serde_json::to_writer(writer, &rankings).map_err(|_| CustomError::ErrorSerializacion)?;
+    Ok(())
+}
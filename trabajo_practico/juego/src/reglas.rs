@@ -0,0 +1,198 @@
+use std::{fs::File, io::BufReader};
+
+use barcos::tipo_barco::TipoBarco;
+use libreria::{
+    constantes::{
+        CORDENADAS_BOMBA, EVENTO_SORPRESA, MONEDAS_HUNDIMIENTO, MONEDAS_IMPACTO, MONEDAS_INICIALES,
+        PRECIO_BOMBA, PRECIO_REPARACION, PRECIO_SONAR, PRECIO_TORPEDO, PREMIO, PUNTOS_HUNDIMIENTO,
+        PUNTOS_IMPACTO, RADIO_SONAR, VELOCIDAD_ACORAZADO, VELOCIDAD_BUQUE, VELOCIDAD_FRAGATA,
+    },
+    custom_error::CustomError,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::arma::TipoArma;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+/// Estructura que representa los valores de balance de una partida
+pub struct Reglas {
+    pub puntos_por_hundimiento: usize,
+    pub monedas_por_hundimiento: usize,
+    pub puntos_por_impacto: usize,
+    pub monedas_por_impacto: usize,
+    pub monedas_iniciales: usize,
+    pub premio_evento_sorpresa: usize,
+    pub ronda_evento_sorpresa: usize,
+    pub coordenadas_bomba: (i32, i32),
+    pub flota_inicial: Vec<TipoBarco>,
+    pub precio_reparacion: usize,
+    pub precio_bomba: usize,
+    pub precio_torpedo: usize,
+    pub precio_sonar: usize,
+    pub radio_sonar: i32,
+    pub velocidad_fragata: usize,
+    pub velocidad_buque: usize,
+    pub velocidad_acorazado: usize,
+}
+
+impl Default for Reglas {
+    fn default() -> Self {
+        Reglas {
+            puntos_por_hundimiento: PUNTOS_HUNDIMIENTO,
+            monedas_por_hundimiento: MONEDAS_HUNDIMIENTO,
+            puntos_por_impacto: PUNTOS_IMPACTO,
+            monedas_por_impacto: MONEDAS_IMPACTO,
+            monedas_iniciales: MONEDAS_INICIALES,
+            premio_evento_sorpresa: PREMIO,
+            ronda_evento_sorpresa: EVENTO_SORPRESA,
+            coordenadas_bomba: CORDENADAS_BOMBA,
+            flota_inicial: vec![TipoBarco::Fragata],
+            precio_reparacion: PRECIO_REPARACION,
+            precio_bomba: PRECIO_BOMBA,
+            precio_torpedo: PRECIO_TORPEDO,
+            precio_sonar: PRECIO_SONAR,
+            radio_sonar: RADIO_SONAR,
+            velocidad_fragata: VELOCIDAD_FRAGATA,
+            velocidad_buque: VELOCIDAD_BUQUE,
+            velocidad_acorazado: VELOCIDAD_ACORAZADO,
+        }
+    }
+}
+
+impl Reglas {
+    /// Función que carga las reglas desde un archivo JSON
+    ///
+    /// Los valores que no estén en el archivo toman su valor por defecto.
+    ///
+    /// # Args
+    ///
+    /// `ruta` - Ruta del archivo de reglas
+    ///
+    /// # Returns
+    ///
+    /// `Result<Reglas, CustomError>` - Reglas cargadas
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorReglas` - Si no se pudo leer el archivo o sus valores son inválidos
+    pub fn desde_archivo(ruta: &str) -> Result<Reglas, CustomError> {
+        let archivo = File::open(ruta).map_err(|_| CustomError::ErrorReglas)?;
+        let reglas: Reglas = serde_json::from_reader(BufReader::new(archivo))
+            .map_err(|_| CustomError::ErrorReglas)?;
+        reglas.validar()?;
+        Ok(reglas)
+    }
+
+    /// Función que obtiene el precio de un arma en la tienda
+    ///
+    /// # Args
+    ///
+    /// `arma` - Arma a comprar
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Precio en monedas
+    pub fn precio_arma(&self, arma: TipoArma) -> usize {
+        match arma {
+            TipoArma::Bomba => self.precio_bomba,
+            TipoArma::Torpedo => self.precio_torpedo,
+            TipoArma::Sonar => self.precio_sonar,
+        }
+    }
+
+    /// Función que obtiene cuántos casilleros puede avanzar un barco en un movimiento
+    ///
+    /// # Args
+    ///
+    /// `tipo` - Clase del barco
+    ///
+    /// # Returns
+    ///
+    /// `usize` - Velocidad del barco
+    pub fn velocidad(&self, tipo: TipoBarco) -> usize {
+        match tipo {
+            TipoBarco::Fragata => self.velocidad_fragata,
+            TipoBarco::Buque => self.velocidad_buque,
+            TipoBarco::Acorazado => self.velocidad_acorazado,
+        }
+    }
+
+    /// Función que verifica que las reglas permitan jugar una partida
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si las reglas son válidas
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorReglas` - Si la flota inicial está vacía, la bomba cae fuera del tablero, el radio del sonar es negativo o algún barco no puede moverse
+    pub fn validar(&self) -> Result<(), CustomError> {
+        let (x, y) = self.coordenadas_bomba;
+        let barco_inmovil = TipoBarco::TODOS
+            .into_iter()
+            .any(|tipo| self.velocidad(tipo) == 0);
+        if self.flota_inicial.is_empty() || x < 0 || y < 0 || self.radio_sonar < 0 || barco_inmovil
+        {
+            return Err(CustomError::ErrorReglas);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reglas_por_defecto() {
+        let reglas = Reglas::default();
+        assert!(reglas.validar().is_ok());
+        assert_eq!(reglas.flota_inicial, vec![TipoBarco::Fragata]);
+    }
+
+    #[test]
+    fn test_reglas_desde_archivo() {
+        let ruta = std::env::temp_dir().join("reglas_test.json");
+        std::fs::write(
+            &ruta,
+            r#"{ "puntos_por_hundimiento": 30, "flota_inicial": ["Buque", "Fragata"] }"#,
+        )
+        .unwrap();
+
+        let reglas = Reglas::desde_archivo(ruta.to_str().unwrap()).unwrap();
+        assert_eq!(reglas.puntos_por_hundimiento, 30);
+        assert_eq!(reglas.puntos_por_impacto, PUNTOS_IMPACTO);
+        assert_eq!(reglas.precio_reparacion, PRECIO_REPARACION);
+        assert_eq!(reglas.precio_arma(TipoArma::Torpedo), PRECIO_TORPEDO);
+        assert_eq!(reglas.velocidad(TipoBarco::Fragata), VELOCIDAD_FRAGATA);
+        assert_eq!(
+            reglas.flota_inicial,
+            vec![TipoBarco::Buque, TipoBarco::Fragata]
+        );
+    }
+
+    #[test]
+    fn test_reglas_invalidas() {
+        let sin_flota = Reglas {
+            flota_inicial: Vec::new(),
+            ..Default::default()
+        };
+        let bomba_negativa = Reglas {
+            coordenadas_bomba: (-1, 3),
+            ..Default::default()
+        };
+        let sonar_negativo = Reglas {
+            radio_sonar: -1,
+            ..Default::default()
+        };
+        assert!(sin_flota.validar().is_err());
+        assert!(bomba_negativa.validar().is_err());
+        let acorazado_inmovil = Reglas {
+            velocidad_acorazado: 0,
+            ..Default::default()
+        };
+        assert!(sonar_negativo.validar().is_err());
+        assert!(acorazado_inmovil.validar().is_err());
+    }
+}
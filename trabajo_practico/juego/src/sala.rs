@@ -0,0 +1,2484 @@
+use libreria::custom_error::CustomError;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    task::{self, JoinSet},
+    time,
+};
+
+use crate::{
+    chat,
+    configuracion::ConfiguracionServidor,
+    enlace::Enlace,
+    evento::{Evento, EventoPublico},
+    instruccion::Instruccion,
+    juego::Juego,
+    lobby::{EstadoPreparacion, JugadorEntrante, Lobby, Rol},
+    mensaje::Mensaje,
+    protocolo::{ARMAS, CHAT, DELTAS, DESPLIEGUE, LATIDO, PREPARACION, RECONEXION, REPARACION},
+    ranking,
+    tienda::{self, Producto},
+    vista::{BarcosSerializados, VistaTablero},
+};
+
+/// Cada cuánto se reparten los mensajes de chat de los jugadores que no están en turno
+const INTERVALO_CHAT: Duration = Duration::from_millis(200);
+
+/// Estructura que representa a quien mira la partida de una sala sin jugarla
+struct Espectador {
+    nombre: String,
+    enlace: Enlace,
+    administrador: bool,
+}
+
+/// Estructura que representa una sala con su propia partida
+///
+/// Cada sala corre en su propia tarea y solo conoce los enlaces de sus
+/// jugadores y espectadores. Los jugadores que pierden la conexión durante la
+/// partida conservan su lugar hasta que vence el plazo de reconexión. Los
+/// espectadores nunca forman parte del juego: solo reciben los eventos
+/// públicos y el tablero. Las salas que arma la cola de emparejamiento
+/// comienzan sin esperar y devuelven a sus jugadores a la cola cuando
+/// terminan de jugar.
+pub struct Sala {
+    codigo: String,
+    pub conexiones_jugadores: HashMap<usize, Enlace>,
+    nombres_jugadores: HashMap<usize, String>,
+    espectadores: Vec<Espectador>,
+    silenciados: HashMap<usize, HashSet<usize>>,
+    listos: HashSet<usize>,
+    preguntados: HashSet<usize>,
+    desconectados: HashMap<usize, Instant>,
+    vistas: HashMap<usize, VistaTablero>,
+    en_curso: bool,
+    cola: Option<UnboundedSender<JugadorEntrante>>,
+    devueltos: HashSet<usize>,
+    siguiente_jugador: usize,
+    pub juego: Juego,
+    configuracion: ConfiguracionServidor,
+    lobby: Arc<Lobby>,
+}
+
+impl Sala {
+    /// Función que crea una sala vacía
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código con el que se identifica la sala en el lobby
+    ///
+    /// `configuracion` - Configuración del servidor
+    ///
+    /// `lobby` - Lobby en el que está registrada la sala
+    ///
+    /// # Returns
+    ///
+    /// `Sala` - Sala creada
+    pub fn new(codigo: String, configuracion: ConfiguracionServidor, lobby: Arc<Lobby>) -> Sala {
+        Sala {
+            codigo,
+            conexiones_jugadores: HashMap::new(),
+            nombres_jugadores: HashMap::new(),
+            espectadores: Vec::new(),
+            silenciados: HashMap::new(),
+            listos: HashSet::new(),
+            preguntados: HashSet::new(),
+            desconectados: HashMap::new(),
+            vistas: HashMap::new(),
+            en_curso: false,
+            cola: None,
+            devueltos: HashSet::new(),
+            siguiente_jugador: 0,
+            juego: Juego::con_configuracion(0, &configuracion),
+            configuracion,
+            lobby,
+        }
+    }
+    /// Función que crea una sala para una partida armada por la cola de emparejamiento
+    ///
+    /// # Args
+    ///
+    /// `codigo` - Código con el que se identifica la sala en el lobby
+    ///
+    /// `configuracion` - Configuración del servidor
+    ///
+    /// `lobby` - Lobby en el que está registrada la sala
+    ///
+    /// `cola` - Canal de la cola a la que vuelven los jugadores al terminar
+    ///
+    /// # Returns
+    ///
+    /// `Sala` - Sala creada
+    pub fn emparejada(
+        codigo: String,
+        configuracion: ConfiguracionServidor,
+        lobby: Arc<Lobby>,
+        cola: UnboundedSender<JugadorEntrante>,
+    ) -> Sala {
+        Sala {
+            cola: Some(cola),
+            ..Sala::new(codigo, configuracion, lobby)
+        }
+    }
+    /// Función que envía un mensaje a un jugador
+    ///
+    /// # Args
+    ///
+    /// `id` - ID del jugador
+    ///
+    /// `mensaje` - Mensaje a enviar
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorJugadorInexistente` - Si el jugador no tiene una conexión abierta
+    pub fn enviar_mensaje(&self, id: usize, mensaje: &Mensaje) -> Result<(), CustomError> {
+        self.conexiones_jugadores
+            .get(&id)
+            .ok_or(CustomError::ErrorJugadorInexistente)?
+            .enviar(mensaje)
+    }
+    /// Función que recibe un mensaje
+    ///
+    /// # Args
+    ///
+    /// `id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<Mensaje, CustomError>` - Resultado de la función
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorJugadorInexistente` - Si el jugador no tiene una conexión abierta
+    pub async fn recibir_mensaje(&mut self, id: usize) -> Result<Mensaje, CustomError> {
+        self.conexiones_jugadores
+            .get_mut(&id)
+            .ok_or(CustomError::ErrorJugadorInexistente)?
+            .recibir()
+            .await
+            .map_err(|_| CustomError::ErrorRecibiendoInstruccion)
+    }
+    /// Función que atiende a un jugador que llega por el canal de la sala
+    ///
+    /// Si el nombre ya pertenece a un jugador de la sala se trata de una
+    /// reconexión; si no, de un jugador nuevo. Los espectadores se atienden
+    /// aparte.
+    ///
+    /// # Args
+    ///
+    /// `jugador` - Jugador entrante
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn recibir_entrante(&mut self, jugador: JugadorEntrante) {
+        if let Rol::Espectador { administrador } = jugador.rol {
+            self.agregar_espectador(jugador.nombre, jugador.enlace, administrador);
+            return;
+        }
+        let existente = self
+            .nombres_jugadores
+            .iter()
+            .find(|(_, nombre)| **nombre == jugador.nombre)
+            .map(|(id, _)| *id);
+        match existente {
+            Some(jugador_id) => self.reconectar_jugador(jugador_id, jugador.enlace),
+            None => self.agregar_jugador(jugador),
+        }
+    }
+    /// Función que agrega a la sala un jugador que llegó desde el lobby
+    ///
+    /// Se le confirma al jugador el código de la sala antes de cualquier otro
+    /// mensaje. Los IDs se asignan en orden de llegada y no se reutilizan,
+    /// por lo que el de menor ID es siempre el que llegó primero.
+    ///
+    /// # Args
+    ///
+    /// `jugador` - Jugador entrante
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    pub fn agregar_jugador(&mut self, jugador: JugadorEntrante) {
+        let jugador_id = self.siguiente_jugador;
+        self.siguiente_jugador += 1;
+        println!(
+            "Sala {}: se unió el jugador {}",
+            self.codigo, jugador.nombre
+        );
+        let _ = jugador
+            .enlace
+            .enviar(&Mensaje::SalaUnida(self.codigo.clone()));
+        self.conexiones_jugadores.insert(jugador_id, jugador.enlace);
+        self.nombres_jugadores
+            .insert(jugador_id, jugador.nombre.clone());
+        self.juego.agregar_jugador(jugador_id, jugador.nombre);
+    }
+    /// Función que agrega a la sala un espectador
+    ///
+    /// Si la partida está en curso se le envía el tablero para que no tenga
+    /// que esperar al próximo turno.
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre del espectador
+    ///
+    /// `enlace` - Enlace con el espectador
+    ///
+    /// `administrador` - Si puede ver el tablero sin ocultar
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn agregar_espectador(&mut self, nombre: String, enlace: Enlace, administrador: bool) {
+        println!("Sala {}: {} entró como espectador", self.codigo, nombre);
+        let _ = enlace.enviar(&Mensaje::Observando(self.codigo.clone(), administrador));
+        if self.en_curso {
+            let _ = enlace.enviar(&self.tablero_espectador(administrador));
+        }
+        self.espectadores.push(Espectador {
+            nombre,
+            enlace,
+            administrador,
+        });
+    }
+    /// Función que lleva adelante la sala: espera jugadores, juega la partida y cierra la sala
+    ///
+    /// Los nombres de los jugadores que volvieron a la cola de emparejamiento
+    /// siguen reservados; el resto se libera.
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que se unen desde el lobby
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la partida
+    pub async fn run(
+        mut self,
+        mut entrantes: UnboundedReceiver<JugadorEntrante>,
+    ) -> Result<(), CustomError> {
+        let resultado = if self.cola.is_some() {
+            self.comenzar(&mut entrantes);
+            self.jugar_partida(&mut entrantes).await
+        } else {
+            match self.esperar_comienzo(&mut entrantes).await {
+                Ok(true) => self.jugar_partida(&mut entrantes).await,
+                otro => otro.map(|_| ()),
+            }
+        };
+        let jugadores: Vec<usize> = self.conexiones_jugadores.keys().copied().collect();
+        for jugador_id in jugadores {
+            self.devolver_a_la_cola(jugador_id);
+        }
+        let nombres: Vec<String> = self
+            .nombres_jugadores
+            .iter()
+            .filter(|(id, _)| !self.devueltos.contains(id))
+            .map(|(_, nombre)| nombre)
+            .chain(
+                self.espectadores
+                    .iter()
+                    .map(|espectador| &espectador.nombre),
+            )
+            .cloned()
+            .collect();
+        self.lobby.cerrar_sala(&self.codigo, &nombres);
+        println!("Sala {} cerrada", self.codigo);
+        resultado
+    }
+    /// Función que espera a que la partida pueda comenzar
+    ///
+    /// Los jugadores pueden marcarse listos o dejar de estarlo en cualquier
+    /// momento. Cuando hay jugadores suficientes y todos están listos corre
+    /// una cuenta regresiva, durante la cual se siguen aceptando jugadores;
+    /// si alguno de los que estaban listos se arrepiente o se va, la cuenta
+    /// se cancela. El anfitrión puede comenzar antes, siempre que se haya
+    /// alcanzado el mínimo de jugadores. Mientras tanto se reparten los
+    /// mensajes de chat de la sala.
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que se unen desde el lobby
+    ///
+    /// # Returns
+    ///
+    /// `Result<bool, CustomError>` - Verdadero si la partida puede comenzar, falso si el lobby cerró la sala
+    async fn esperar_comienzo(
+        &mut self,
+        entrantes: &mut UnboundedReceiver<JugadorEntrante>,
+    ) -> Result<bool, CustomError> {
+        let cuenta = Duration::from_secs(self.configuracion.segundos_cuenta_regresiva);
+        let mut cuenta_regresiva: Option<(Instant, HashSet<usize>)> = None;
+        let mut ultimo_estado: Option<EstadoPreparacion> = None;
+        loop {
+            match time::timeout(INTERVALO_CHAT, entrantes.recv()).await {
+                Ok(Some(jugador)) => {
+                    self.recibir_entrante(jugador);
+                    // El que llega tiene que recibir el estado aunque no haya cambiado
+                    ultimo_estado = None;
+                }
+                Ok(None) => return Ok(false),
+                Err(_) => {}
+            }
+            if self.atender_preparacion() {
+                println!("Sala {}: el anfitrión comenzó la partida", self.codigo);
+                self.comenzar(entrantes);
+                return Ok(true);
+            }
+            self.quitar_ausentes();
+
+            let hay_minimo = self.conexiones_jugadores.len() >= self.configuracion.minimo_jugadores;
+            cuenta_regresiva = match cuenta_regresiva {
+                Some((_, ref listos)) if !hay_minimo || !listos.is_subset(&self.listos) => {
+                    println!("Sala {}: se canceló la cuenta regresiva", self.codigo);
+                    None
+                }
+                None if hay_minimo && self.listos.len() == self.conexiones_jugadores.len() => {
+                    Some((Instant::now() + cuenta, self.listos.clone()))
+                }
+                otra => otra,
+            };
+            if let Some((limite, _)) = &cuenta_regresiva {
+                if Instant::now() >= *limite {
+                    println!("Sala {}: todos los jugadores están listos", self.codigo);
+                    self.comenzar(entrantes);
+                    return Ok(true);
+                }
+            }
+
+            let limite = cuenta_regresiva.as_ref().map(|(limite, _)| *limite);
+            let estado = self.estado_preparacion(limite);
+            if ultimo_estado.as_ref() != Some(&estado) {
+                self.difundir_estado(&estado);
+                ultimo_estado = Some(estado);
+            }
+        }
+    }
+    /// Función que atiende los mensajes que enviaron los jugadores mientras se espera el comienzo
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si el anfitrión pidió comenzar y se puede
+    fn atender_preparacion(&mut self) -> bool {
+        let mut comenzar = false;
+        let jugadores: Vec<usize> = self.conexiones_jugadores.keys().copied().collect();
+        for jugador_id in jugadores {
+            while let Some(mensaje) = self
+                .conexiones_jugadores
+                .get_mut(&jugador_id)
+                .and_then(Enlace::pendiente)
+            {
+                match mensaje {
+                    Mensaje::RespuestaComienzo(listo) => {
+                        self.preguntados.remove(&jugador_id);
+                        if listo {
+                            self.listos.insert(jugador_id);
+                        } else {
+                            self.listos.remove(&jugador_id);
+                        }
+                    }
+                    Mensaje::ForzarComienzo => match self.puede_forzar(jugador_id) {
+                        Ok(()) => comenzar = true,
+                        Err(err) => {
+                            let mensaje = Mensaje::ComienzoRechazado(err.to_string());
+                            let _ = self.enviar_mensaje(jugador_id, &mensaje);
+                        }
+                    },
+                    otro => self.atender_chat(jugador_id, otro),
+                }
+            }
+        }
+        comenzar
+    }
+    /// Función que verifica si un jugador puede comenzar la partida sin esperar a los demás
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador que pide comenzar
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si la partida puede comenzar
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorNoEsAnfitrion` - Si el jugador no es el anfitrión de la sala
+    ///
+    /// `CustomError::ErrorFaltanJugadores` - Si no se alcanzó el mínimo de jugadores
+    fn puede_forzar(&self, jugador_id: usize) -> Result<(), CustomError> {
+        if self.anfitrion() != Some(jugador_id) {
+            return Err(CustomError::ErrorNoEsAnfitrion);
+        }
+        if self.conexiones_jugadores.len() < self.configuracion.minimo_jugadores {
+            return Err(CustomError::ErrorFaltanJugadores);
+        }
+        Ok(())
+    }
+    /// Función que obtiene el anfitrión de la sala: el jugador conectado que llegó primero
+    ///
+    /// # Returns
+    ///
+    /// `Option<usize>` - ID del anfitrión, si queda algún jugador
+    fn anfitrion(&self) -> Option<usize> {
+        self.conexiones_jugadores.keys().min().copied()
+    }
+    /// Función que saca de la sala a los jugadores que se desconectaron antes de que comience la partida
+    ///
+    /// A diferencia de lo que pasa durante la partida, no se les guarda el lugar.
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn quitar_ausentes(&mut self) {
+        let ausentes: Vec<usize> = self
+            .conexiones_jugadores
+            .iter()
+            .filter(|(_, conexion)| conexion.cerrado())
+            .map(|(id, _)| *id)
+            .collect();
+        for jugador_id in ausentes {
+            let nombre = self.nombre_jugador(jugador_id);
+            println!("Sala {}: se fue el jugador {}", self.codigo, nombre);
+            self.conexiones_jugadores.remove(&jugador_id);
+            self.nombres_jugadores.remove(&jugador_id);
+            self.listos.remove(&jugador_id);
+            self.preguntados.remove(&jugador_id);
+            self.silenciados.remove(&jugador_id);
+            for silenciados in self.silenciados.values_mut() {
+                silenciados.remove(&jugador_id);
+            }
+            self.juego.eliminar_jugador(jugador_id);
+            self.lobby.salir(&self.codigo, &nombre);
+        }
+    }
+    /// Función que arma el estado de preparación de la sala
+    ///
+    /// # Args
+    ///
+    /// `limite` - Momento en que termina la cuenta regresiva, si corre
+    ///
+    /// # Returns
+    ///
+    /// `EstadoPreparacion` - Jugadores con su estado, anfitrión y segundos que faltan
+    fn estado_preparacion(&self, limite: Option<Instant>) -> EstadoPreparacion {
+        let mut ids: Vec<usize> = self.conexiones_jugadores.keys().copied().collect();
+        ids.sort_unstable();
+        EstadoPreparacion {
+            jugadores: ids
+                .iter()
+                .map(|id| (self.nombre_jugador(*id), self.listos.contains(id)))
+                .collect(),
+            anfitrion: self
+                .anfitrion()
+                .map(|id| self.nombre_jugador(id))
+                .unwrap_or_default(),
+            minimo_jugadores: self.configuracion.minimo_jugadores,
+            segundos_para_comenzar: limite.map(|limite| {
+                let restante = limite.saturating_duration_since(Instant::now());
+                restante.as_secs() + u64::from(restante.subsec_nanos() > 0)
+            }),
+        }
+    }
+    /// Función que avisa a cada jugador el estado de preparación de la sala
+    ///
+    /// Los clientes que no admiten la preparación reciben, como antes,
+    /// `Esperando` mientras faltan jugadores y `PreguntaComienzo` cuando
+    /// alcanza con los que hay y todavía no están listos.
+    ///
+    /// # Args
+    ///
+    /// `estado` - Estado de preparación a enviar
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn difundir_estado(&mut self, estado: &EstadoPreparacion) {
+        let hay_minimo = estado.jugadores.len() >= estado.minimo_jugadores;
+        for (jugador_id, conexion) in &self.conexiones_jugadores {
+            if conexion.admite(PREPARACION) {
+                let _ = conexion.enviar(&Mensaje::EstadoSala(estado.clone()));
+            } else if !hay_minimo {
+                let _ = conexion.enviar(&Mensaje::Esperando);
+            } else if !self.listos.contains(jugador_id) && self.preguntados.insert(*jugador_id) {
+                let _ = conexion.enviar(&Mensaje::PreguntaComienzo);
+            }
+        }
+    }
+    /// Función que da comienzo a la partida
+    ///
+    /// La sala deja de aceptar jugadores, aunque se suman los que ya estaban
+    /// en camino.
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que se unen desde el lobby
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn comenzar(&mut self, entrantes: &mut UnboundedReceiver<JugadorEntrante>) {
+        self.lobby.marcar_en_curso(&self.codigo);
+        while let Ok(jugador) = entrantes.try_recv() {
+            self.recibir_entrante(jugador);
+        }
+        self.en_curso = true;
+        for conexion in self.conexiones_jugadores.values() {
+            let _ = conexion.enviar(&Mensaje::ComenzoJuego);
+        }
+        self.enviar_a_espectadores(|_| Some(Mensaje::ComenzoJuego));
+        println!("Comenzando el juego en la sala {}...", self.codigo);
+    }
+    /// Función que lleva adelante los turnos de la partida hasta que quede un único jugador
+    ///
+    /// Las reglas las resuelve `Juego::aplicar`; el servidor solo pide las
+    /// acciones a cada jugador y les comunica los eventos resultantes. Los
+    /// turnos de los jugadores desconectados se saltean hasta que vuelvan o
+    /// venza su plazo de reconexión.
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que se reconectan
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    pub async fn jugar_partida(
+        &mut self,
+        entrantes: &mut UnboundedReceiver<JugadorEntrante>,
+    ) -> Result<(), CustomError> {
+        let mut rondas = 0;
+        if let Some(segundos) = self.configuracion.segundos_despliegue {
+            self.desplegar_flotas(segundos).await;
+        }
+
+        while !self.juego.finalizo() {
+            while let Ok(jugador) = entrantes.try_recv() {
+                self.recibir_entrante(jugador);
+            }
+            self.expulsar_desconectados();
+            if self.juego.finalizo() {
+                break;
+            }
+            if rondas == self.juego.reglas.ronda_evento_sorpresa {
+                self.crear_evento_sorpresa().await?;
+            }
+            let Some(jugador_id) = self.juego.jugador_en_turno() else {
+                break;
+            };
+            self.transmitir_tablero();
+
+            if self.desconectados.contains_key(&jugador_id) {
+                println!(
+                    "El jugador {} está desconectado, se saltea su turno",
+                    self.nombre_jugador(jugador_id)
+                );
+                let eventos = self.juego.aplicar(jugador_id, Instruccion::Saltar);
+                self.notificar_eventos(eventos);
+                if self.conexiones_jugadores.is_empty() {
+                    self.esperar_reconexion(entrantes).await;
+                }
+                rondas += 1;
+                continue;
+            }
+
+            println!("Turno del jugador {}", self.nombre_jugador(jugador_id));
+            println!(
+                "Cantidad de jugadores con barcos: {:?}",
+                self.juego
+                    .jugadores
+                    .iter()
+                    .filter(|j| j.esta_vivo())
+                    .count()
+            );
+
+            if self.jugar_turno(jugador_id).await.is_err() {
+                self.marcar_desconectado(jugador_id);
+                rondas += 1;
+                continue;
+            }
+
+            if let Some(jugador) = self.juego.obtener_jugador(jugador_id) {
+                if !self.juego.finalizo() {
+                    let _ = self.enviar_mensaje(jugador_id, &Mensaje::Puntos(jugador.puntos));
+                }
+            }
+            rondas += 1;
+        }
+
+        println!("Juego terminado");
+        let jugadores = self.juego.jugadores.clone();
+        task::spawn_blocking(move || ranking::actualizar_ranking(&jugadores))
+            .await
+            .map_err(|_| CustomError::ErrorThreads)?
+    }
+    /// Función que deja a los jugadores ubicar su flota antes del primer turno
+    ///
+    /// Cada jugador parte de la ubicación automática y puede cambiarla
+    /// cuantas veces quiera hasta confirmarla o hasta que venza el plazo.
+    /// Los barcos que no ubicó quedan donde estaban. Los clientes que no
+    /// admiten el despliegue juegan directamente con la ubicación automática.
+    ///
+    /// # Args
+    ///
+    /// `segundos` - Plazo que tienen los jugadores para ubicar su flota
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    async fn desplegar_flotas(&mut self, segundos: u64) {
+        let mut pendientes: HashSet<usize> = HashSet::new();
+        let jugadores: Vec<usize> = self.conexiones_jugadores.keys().copied().collect();
+        for jugador_id in jugadores {
+            if !self.admite(jugador_id, DESPLIEGUE) {
+                continue;
+            }
+            if let Ok((tablero, barcos, _)) = self.estado_visible(jugador_id) {
+                let mensaje = Mensaje::Despliegue(tablero, barcos, segundos);
+                if self.enviar_mensaje(jugador_id, &mensaje).is_ok() {
+                    pendientes.insert(jugador_id);
+                }
+            }
+        }
+
+        let limite = Instant::now() + Duration::from_secs(segundos);
+        while !pendientes.is_empty() && Instant::now() < limite {
+            time::sleep(INTERVALO_CHAT).await;
+            let jugadores: Vec<usize> = pendientes.iter().copied().collect();
+            for jugador_id in jugadores {
+                if self.atender_despliegue(jugador_id) {
+                    pendientes.remove(&jugador_id);
+                }
+            }
+            self.atender_chats_pendientes(None);
+        }
+
+        for jugador_id in pendientes {
+            if let Ok((_, barcos, _)) = self.estado_visible(jugador_id) {
+                let _ = self.enviar_mensaje(jugador_id, &Mensaje::FlotaDesplegada(barcos));
+            }
+        }
+    }
+    /// Función que atiende los mensajes de un jugador que todavía ubica su flota
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `bool` - Verdadero si el jugador terminó de ubicar su flota o se desconectó
+    fn atender_despliegue(&mut self, jugador_id: usize) -> bool {
+        while let Some(mensaje) = self
+            .conexiones_jugadores
+            .get_mut(&jugador_id)
+            .and_then(Enlace::pendiente)
+        {
+            match mensaje {
+                Mensaje::DesplegarFlota(ubicaciones) => {
+                    match self.juego.desplegar_flota(jugador_id, &ubicaciones) {
+                        Ok(()) => {
+                            if let Ok((_, barcos, _)) = self.estado_visible(jugador_id) {
+                                let mensaje = Mensaje::FlotaDesplegada(barcos);
+                                let _ = self.enviar_mensaje(jugador_id, &mensaje);
+                            }
+                            return true;
+                        }
+                        Err(err) => {
+                            let mensaje = Mensaje::DespliegueRechazado(err.to_string());
+                            let _ = self.enviar_mensaje(jugador_id, &mensaje);
+                        }
+                    }
+                }
+                Mensaje::AutoDespliegue => {
+                    if let Ok((_, barcos, _)) = self.estado_visible(jugador_id) {
+                        let _ = self.enviar_mensaje(jugador_id, &Mensaje::FlotaDesplegada(barcos));
+                    }
+                    return true;
+                }
+                otro => self.atender_chat(jugador_id, otro),
+            }
+        }
+        self.conexiones_jugadores
+            .get(&jugador_id)
+            .is_none_or(Enlace::cerrado)
+    }
+    /// Función que pide acciones al jugador en turno hasta que una de ellas termine su turno
+    ///
+    /// Si el jugador no termina su turno dentro del plazo configurado se le
+    /// aplica `Instruccion::Saltar`. Mientras se espera, se envía un `Ping`
+    /// por cada intervalo de latido; si el jugador en turno no contesta a
+    /// tiempo se considera que su conexión se cayó. A los clientes que no
+    /// admiten el latido solo se les aplica el plazo del turno. Mientras
+    /// tanto se reparten los mensajes de chat de toda la sala.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador en turno
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoInstruccion` - Si se pierde la conexión con el jugador
+    async fn jugar_turno(&mut self, jugador_id: usize) -> Result<(), CustomError> {
+        self.descartar_pendientes(jugador_id);
+        self.enviar_mensaje(jugador_id, &Mensaje::RealiceAccion)?;
+        self.enviar_tablero(jugador_id)?;
+
+        let limite = Instant::now() + Duration::from_secs(self.configuracion.segundos_turno);
+        let latido = Duration::from_secs(self.configuracion.segundos_latido);
+        let mut ping_enviado: Option<Instant> = None;
+        let mut sin_novedades = Instant::now();
+        loop {
+            let restante = limite.saturating_duration_since(Instant::now());
+            if restante.is_zero() {
+                println!(
+                    "Se agotó el tiempo del jugador {}",
+                    self.nombre_jugador(jugador_id)
+                );
+                let _ = self.enviar_mensaje(jugador_id, &Mensaje::TurnoAgotado);
+                let eventos = self.juego.aplicar(jugador_id, Instruccion::Saltar);
+                self.notificar_eventos(eventos);
+                return Ok(());
+            }
+
+            match self
+                .recibir_mensaje_con_plazo(jugador_id, restante.min(INTERVALO_CHAT))
+                .await?
+            {
+                Some(Mensaje::Accion(instruccion)) => {
+                    ping_enviado = None;
+                    sin_novedades = Instant::now();
+                    let eventos = self.juego.aplicar(jugador_id, instruccion);
+                    let finaliza_turno = eventos.iter().all(Evento::finaliza_turno);
+                    self.notificar_eventos(eventos);
+                    if finaliza_turno {
+                        return Ok(());
+                    }
+                }
+                Some(Mensaje::PedirResincronizacion) => {
+                    ping_enviado = None;
+                    sin_novedades = Instant::now();
+                    self.enviar_instantanea(jugador_id)?;
+                }
+                Some(otro) => {
+                    ping_enviado = None;
+                    sin_novedades = Instant::now();
+                    self.atender_chat(jugador_id, otro);
+                }
+                None if Instant::now() >= limite => {}
+                None => {
+                    self.atender_chats_pendientes(Some(jugador_id));
+                    match ping_enviado {
+                        Some(enviado) if enviado.elapsed() >= latido => {
+                            return Err(CustomError::ErrorRecibiendoInstruccion);
+                        }
+                        Some(_) => {}
+                        None if sin_novedades.elapsed() >= latido => {
+                            if self.admite(jugador_id, LATIDO) {
+                                self.enviar_mensaje(jugador_id, &Mensaje::Ping)?;
+                                ping_enviado = Some(Instant::now());
+                            }
+                            self.enviar_latidos(jugador_id);
+                            sin_novedades = Instant::now();
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+    /// Función que indica si el cliente de un jugador admite una capacidad del protocolo
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// `capacidad` - Nombre de la capacidad
+    ///
+    /// # Returns
+    ///
+    /// `bool` - true si el jugador está conectado y su cliente admite la capacidad
+    fn admite(&self, jugador_id: usize, capacidad: &str) -> bool {
+        self.conexiones_jugadores
+            .get(&jugador_id)
+            .is_some_and(|conexion| conexion.admite(capacidad))
+    }
+    /// Función que recibe un mensaje esperando como máximo el plazo indicado
+    ///
+    /// # Args
+    ///
+    /// `id` - ID del jugador
+    ///
+    /// `plazo` - Tiempo máximo de espera
+    ///
+    /// # Returns
+    ///
+    /// `Result<Option<Mensaje>, CustomError>` - Mensaje recibido, o `None` si venció el plazo
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoInstruccion` - Si el jugador no tiene una conexión abierta o se cayó
+    async fn recibir_mensaje_con_plazo(
+        &mut self,
+        id: usize,
+        plazo: Duration,
+    ) -> Result<Option<Mensaje>, CustomError> {
+        self.conexiones_jugadores
+            .get_mut(&id)
+            .ok_or(CustomError::ErrorJugadorInexistente)?
+            .recibir_con_plazo(plazo)
+            .await
+            .map_err(|_| CustomError::ErrorRecibiendoInstruccion)
+    }
+    /// Función que descarta los mensajes que el jugador envió fuera de su turno
+    ///
+    /// Por ejemplo, la acción que eligió después de que se le agotara el
+    /// tiempo. Los mensajes de chat no se descartan, se reparten.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn descartar_pendientes(&mut self, jugador_id: usize) {
+        while let Some(mensaje) = self
+            .conexiones_jugadores
+            .get_mut(&jugador_id)
+            .and_then(Enlace::pendiente)
+        {
+            self.atender_chat(jugador_id, mensaje);
+        }
+    }
+    /// Función que reparte los mensajes de chat que enviaron los jugadores que no están en turno
+    ///
+    /// El resto de sus mensajes, como los `Pong`, se descartan.
+    ///
+    /// # Args
+    ///
+    /// `jugador_en_turno` - ID del jugador en turno, cuyos mensajes se leen aparte
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn atender_chats_pendientes(&mut self, jugador_en_turno: Option<usize>) {
+        let jugadores: Vec<usize> = self
+            .conexiones_jugadores
+            .keys()
+            .filter(|id| Some(**id) != jugador_en_turno)
+            .copied()
+            .collect();
+        for jugador_id in jugadores {
+            self.descartar_pendientes(jugador_id);
+        }
+    }
+    /// Función que atiende un mensaje de chat o un pedido de silenciar a otro jugador
+    ///
+    /// Los mensajes que no son de chat se ignoran. Los rechazos solo se le
+    /// envían al jugador si su cliente admite el chat.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador que envió el mensaje
+    ///
+    /// `mensaje` - Mensaje recibido
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn atender_chat(&mut self, jugador_id: usize, mensaje: Mensaje) {
+        let resultado = match mensaje {
+            Mensaje::Chat { para, texto } => self.enviar_chat(jugador_id, para, &texto),
+            Mensaje::Silenciar(nombre, silenciar) => self.silenciar(jugador_id, &nombre, silenciar),
+            _ => Ok(()),
+        };
+        if let Err(err) = resultado {
+            if self.admite(jugador_id, CHAT) {
+                let _ = self.enviar_mensaje(jugador_id, &Mensaje::ChatRechazado(err.to_string()));
+            }
+        }
+    }
+    /// Función que reparte un mensaje de chat
+    ///
+    /// Sin destinatario, el mensaje llega a todos los jugadores conectados y a
+    /// los espectadores; con destinatario, solo a ese jugador. Los jugadores
+    /// que silenciaron al remitente y los clientes que no admiten el chat no
+    /// lo reciben.
+    ///
+    /// # Args
+    ///
+    /// `remitente` - ID del jugador que escribió el mensaje
+    ///
+    /// `para` - Nombre del destinatario, si el mensaje es privado
+    ///
+    /// `texto` - Texto del mensaje
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si el mensaje se repartió
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorChatInvalido` - Si el texto está vacío o supera el largo permitido
+    ///
+    /// `CustomError::ErrorDestinatarioInexistente` - Si el destinatario no es un jugador conectado de la sala o su cliente no admite el chat
+    fn enviar_chat(
+        &mut self,
+        remitente: usize,
+        para: Option<String>,
+        texto: &str,
+    ) -> Result<(), CustomError> {
+        let texto = chat::validar_texto(texto)?;
+        let de = self.nombre_jugador(remitente);
+        let destinatarios: Vec<usize> = match &para {
+            Some(nombre) => vec![self
+                .jugador_conectado(nombre)
+                .filter(|id| self.admite(*id, CHAT))
+                .ok_or(CustomError::ErrorDestinatarioInexistente)?],
+            None => self
+                .conexiones_jugadores
+                .iter()
+                .filter(|(id, conexion)| **id != remitente && conexion.admite(CHAT))
+                .map(|(id, _)| *id)
+                .collect(),
+        };
+        let mensaje = Mensaje::ChatRecibido {
+            de,
+            texto,
+            privado: para.is_some(),
+        };
+        for destinatario in destinatarios {
+            let silenciado = self
+                .silenciados
+                .get(&destinatario)
+                .is_some_and(|silenciados| silenciados.contains(&remitente));
+            if !silenciado {
+                let _ = self.enviar_mensaje(destinatario, &mensaje);
+            }
+        }
+        if para.is_none() {
+            self.enviar_a_espectadores(|espectador| {
+                espectador.enlace.admite(CHAT).then(|| mensaje.clone())
+            });
+        }
+        Ok(())
+    }
+    /// Función que deja de mostrarle a un jugador los mensajes de chat de otro, o vuelve a mostrárselos
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador que pide silenciar
+    ///
+    /// `nombre` - Nombre del jugador a silenciar
+    ///
+    /// `silenciar` - Falso para volver a recibir sus mensajes
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorDestinatarioInexistente` - Si no hay un jugador conectado con ese nombre en la sala
+    fn silenciar(
+        &mut self,
+        jugador_id: usize,
+        nombre: &str,
+        silenciar: bool,
+    ) -> Result<(), CustomError> {
+        let otro = self
+            .jugador_conectado(nombre)
+            .ok_or(CustomError::ErrorDestinatarioInexistente)?;
+        let silenciados = self.silenciados.entry(jugador_id).or_default();
+        if silenciar {
+            silenciados.insert(otro);
+        } else {
+            silenciados.remove(&otro);
+        }
+        Ok(())
+    }
+    /// Función que busca a un jugador conectado de la sala por su nombre
+    ///
+    /// # Args
+    ///
+    /// `nombre` - Nombre del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Option<usize>` - ID del jugador, si está conectado
+    fn jugador_conectado(&self, nombre: &str) -> Option<usize> {
+        self.nombres_jugadores
+            .iter()
+            .find(|(id, otro)| *otro == nombre && self.conexiones_jugadores.contains_key(id))
+            .map(|(id, _)| *id)
+    }
+    /// Función que envía un `Ping` al resto de los jugadores conectados que admiten el latido
+    ///
+    /// Los jugadores a los que no se les puede enviar se marcan como desconectados.
+    ///
+    /// # Args
+    ///
+    /// `jugador_en_turno` - ID del jugador en turno, al que se le envía aparte
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn enviar_latidos(&mut self, jugador_en_turno: usize) {
+        let caidos: Vec<usize> = self
+            .conexiones_jugadores
+            .iter()
+            .filter(|(id, conexion)| {
+                **id != jugador_en_turno
+                    && conexion.admite(LATIDO)
+                    && conexion.enviar(&Mensaje::Ping).is_err()
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for jugador_id in caidos {
+            self.marcar_desconectado(jugador_id);
+        }
+    }
+    /// Función que guarda el lugar de un jugador que perdió la conexión y avisa al resto
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador desconectado
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn marcar_desconectado(&mut self, jugador_id: usize) {
+        let nombre = self.nombre_jugador(jugador_id);
+        println!(
+            "El jugador {} se desconectó, tiene {} segundos para volver",
+            nombre, self.configuracion.segundos_reconexion
+        );
+        self.conexiones_jugadores.remove(&jugador_id);
+        self.desconectados.insert(jugador_id, Instant::now());
+        for conexion in self.conexiones_jugadores.values() {
+            if conexion.admite(RECONEXION) {
+                let _ = conexion.enviar(&Mensaje::NotificacionDesconexion(nombre.clone()));
+            }
+        }
+    }
+    /// Función que devuelve a la sala la conexión de un jugador que ya estaba en ella
+    ///
+    /// Si la partida está en curso se le envía el estado actual para que
+    /// pueda retomarla.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// `conexion` - Enlace con la nueva conexión del jugador
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn reconectar_jugador(&mut self, jugador_id: usize, conexion: Enlace) {
+        let nombre = self.nombre_jugador(jugador_id);
+        if !self.en_curso {
+            let _ = conexion.enviar(&Mensaje::SalaUnida(self.codigo.clone()));
+            // La conexión nueva no recibió la pregunta de comienzo
+            self.preguntados.remove(&jugador_id);
+            self.conexiones_jugadores.insert(jugador_id, conexion);
+            return;
+        }
+        let Some(jugador) = self.juego.obtener_jugador(jugador_id) else {
+            return;
+        };
+        if jugador.ha_perdido {
+            let _ = conexion.enviar(&Mensaje::Perdiste(jugador.puntos));
+            return;
+        }
+        let tablero = self.juego.mapa.tablero_para(jugador.id.to_string());
+        let barcos = self.juego.mapa.serializar_barcos(&jugador.barcos);
+        if conexion
+            .enviar(&Mensaje::Reanudar(tablero, barcos, jugador.monedas))
+            .is_err()
+        {
+            return;
+        }
+        println!("El jugador {} volvió a la partida", nombre);
+        self.desconectados.remove(&jugador_id);
+        // El cliente nuevo no tiene vista del tablero, el próximo envío es completo
+        self.vistas.remove(&jugador_id);
+        for otra in self.conexiones_jugadores.values() {
+            if otra.admite(RECONEXION) {
+                let _ = otra.enviar(&Mensaje::NotificacionReconexion(nombre.clone()));
+            }
+        }
+        self.conexiones_jugadores.insert(jugador_id, conexion);
+    }
+    /// Función que elimina de la partida a los desconectados cuyo plazo de reconexión venció
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn expulsar_desconectados(&mut self) {
+        let plazo = Duration::from_secs(self.configuracion.segundos_reconexion);
+        let vencidos: Vec<usize> = self
+            .desconectados
+            .iter()
+            .filter(|(_, desde)| desde.elapsed() >= plazo)
+            .map(|(jugador_id, _)| *jugador_id)
+            .collect();
+        for jugador_id in vencidos {
+            println!(
+                "El jugador {} no volvió a tiempo y abandona la partida",
+                self.nombre_jugador(jugador_id)
+            );
+            self.desconectados.remove(&jugador_id);
+            let eventos = self.juego.abandonar(jugador_id);
+            self.notificar_eventos(eventos);
+        }
+    }
+    /// Función que espera a que vuelva algún jugador cuando no queda nadie conectado
+    ///
+    /// # Args
+    ///
+    /// `entrantes` - Canal por el que llegan los jugadores que se reconectan
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    async fn esperar_reconexion(&mut self, entrantes: &mut UnboundedReceiver<JugadorEntrante>) {
+        let plazo = Duration::from_secs(self.configuracion.segundos_reconexion);
+        let espera = self
+            .desconectados
+            .values()
+            .map(|desde| plazo.saturating_sub(desde.elapsed()))
+            .min()
+            .unwrap_or_default();
+        if let Ok(Some(jugador)) = time::timeout(espera, entrantes.recv()).await {
+            self.recibir_entrante(jugador);
+        }
+    }
+    /// Función que envía al jugador el tablero, sus barcos y sus monedas
+    ///
+    /// A los clientes que admiten deltas solo se les envían los cambios
+    /// desde la última vista que recibieron, salvo la primera vez.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    fn enviar_tablero(&mut self, jugador_id: usize) -> Result<(), CustomError> {
+        let (tablero, barcos, monedas) = self.estado_visible(jugador_id)?;
+        if !self.admite(jugador_id, DELTAS) {
+            return self.enviar_mensaje(jugador_id, &Mensaje::Tablero(tablero, barcos, monedas));
+        }
+        let Some(vista) = self.vistas.get_mut(&jugador_id) else {
+            return self.enviar_instantanea(jugador_id);
+        };
+        let Some(cambios) = vista.diferencias(&tablero, &barcos) else {
+            return self.enviar_instantanea(jugador_id);
+        };
+        let secuencia = vista.secuencia + 1;
+        vista.aplicar(secuencia, &cambios)?;
+        self.enviar_mensaje(jugador_id, &Mensaje::Delta(secuencia, cambios, monedas))
+    }
+    /// Función que envía al jugador su tablero completo y reinicia su vista
+    ///
+    /// Se usa la primera vez y cuando el cliente pide resincronizar porque
+    /// detectó que le faltan cambios.
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    fn enviar_instantanea(&mut self, jugador_id: usize) -> Result<(), CustomError> {
+        let (tablero, barcos, monedas) = self.estado_visible(jugador_id)?;
+        let secuencia = self
+            .vistas
+            .get(&jugador_id)
+            .map_or(1, |vista| vista.secuencia + 1);
+        self.vistas.insert(
+            jugador_id,
+            VistaTablero::new(secuencia, tablero.clone(), barcos.clone()),
+        );
+        self.enviar_mensaje(
+            jugador_id,
+            &Mensaje::Instantanea(secuencia, tablero, barcos, monedas),
+        )
+    }
+    /// Función que obtiene lo que un jugador puede ver de la partida
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Result<(Vec<Vec<char>>, BarcosSerializados, usize), CustomError>` - Tablero visible, barcos propios y monedas
+    fn estado_visible(
+        &self,
+        jugador_id: usize,
+    ) -> Result<(Vec<Vec<char>>, BarcosSerializados, usize), CustomError> {
+        let jugador = self
+            .juego
+            .obtener_jugador(jugador_id)
+            .ok_or(CustomError::ErrorJugadorInexistente)?;
+        let tablero = self.juego.mapa.tablero_para(jugador.id.to_string());
+        let barcos = self.juego.mapa.serializar_barcos(&jugador.barcos);
+        Ok((tablero, barcos, jugador.monedas))
+    }
+    /// Función que traduce los eventos del juego en mensajes para los jugadores
+    ///
+    /// Los eventos públicos se transmiten además a los espectadores.
+    ///
+    /// # Args
+    ///
+    /// `eventos` - Eventos producidos por el juego
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn notificar_eventos(&mut self, eventos: Vec<Evento>) {
+        let mut publicos: Vec<EventoPublico> = eventos
+            .iter()
+            .filter_map(|evento| self.evento_publico(evento))
+            .collect();
+        // El juego informa el resultado del ataque después de sus impactos,
+        // pero los espectadores tienen que ver primero quién atacó
+        publicos.sort_by_key(|evento| {
+            !matches!(
+                evento,
+                EventoPublico::Ataque { .. }
+                    | EventoPublico::Bombardeo { .. }
+                    | EventoPublico::Torpedo { .. }
+            )
+        });
+        for evento in publicos {
+            self.transmitir(evento);
+        }
+
+        for evento in eventos {
+            match evento {
+                Evento::BarcoGolpeado {
+                    jugador,
+                    coordenadas,
+                } => {
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::BarcoGolpeado(coordenadas));
+                }
+                Evento::BarcoHundido { jugador, .. } => {
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::BarcoHundido);
+                }
+                Evento::ResultadoAtaque {
+                    jugador,
+                    puntos,
+                    monedas,
+                    ..
+                } => {
+                    let _ =
+                        self.enviar_mensaje(jugador, &Mensaje::MensajeInfoAtaque(puntos, monedas));
+                }
+                Evento::ResultadoBomba {
+                    jugador,
+                    impactos,
+                    puntos,
+                    monedas,
+                    ..
+                } => {
+                    let mensaje = Mensaje::ResultadoBomba(impactos, puntos, monedas);
+                    let _ = self.enviar_mensaje(jugador, &mensaje);
+                }
+                Evento::ResultadoTorpedo {
+                    jugador,
+                    impacto,
+                    puntos,
+                    monedas,
+                } => {
+                    let mensaje = Mensaje::ResultadoTorpedo(impacto, puntos, monedas);
+                    let _ = self.enviar_mensaje(jugador, &mensaje);
+                }
+                Evento::ResultadoSonar {
+                    jugador,
+                    centro,
+                    detectado,
+                } => {
+                    let mensaje = Mensaje::ResultadoSonar(centro, detectado);
+                    let _ = self.enviar_mensaje(jugador, &mensaje);
+                }
+                Evento::JugadorEliminado {
+                    jugador,
+                    nombre,
+                    puntos,
+                } => {
+                    println!("El jugador {} ha sido eliminado", nombre);
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::Perdiste(puntos));
+                    self.devolver_a_la_cola(jugador);
+                    for conexion in self.conexiones_jugadores.values() {
+                        let _ = conexion.enviar(&Mensaje::NotificacionEliminacion(nombre.clone()));
+                    }
+                }
+                Evento::TiendaSolicitada { jugador } => {
+                    if let Some(datos) = self.juego.obtener_jugador(jugador) {
+                        let reparacion = self.admite(jugador, REPARACION);
+                        let armas = self.admite(jugador, ARMAS);
+                        let mensaje = if reparacion || armas {
+                            let mut catalogo = tienda::catalogo_completo(&self.juego.reglas);
+                            catalogo.retain(|articulo| match articulo.producto {
+                                Producto::Barco(_) => true,
+                                Producto::Reparacion => reparacion,
+                                Producto::Arma(_) => armas,
+                            });
+                            Mensaje::Catalogo(catalogo, datos.monedas)
+                        } else {
+                            Mensaje::AbrirTienda(tienda::catalogo(), datos.monedas)
+                        };
+                        let _ = self.enviar_mensaje(jugador, &mensaje);
+                    }
+                }
+                Evento::CompraRealizada {
+                    jugador,
+                    tipo,
+                    monedas,
+                } => {
+                    println!(
+                        "El jugador {} ha comprado un {}",
+                        self.nombre_jugador(jugador),
+                        tipo.nombre()
+                    );
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::CompraExitosa(tipo, monedas));
+                }
+                Evento::CompraRechazada { jugador, motivo } => {
+                    if let Some(datos) = self.juego.obtener_jugador(jugador) {
+                        let mensaje = Mensaje::CompraRechazada(motivo, datos.monedas);
+                        let _ = self.enviar_mensaje(jugador, &mensaje);
+                    }
+                }
+                Evento::ArmaComprada {
+                    jugador,
+                    arma,
+                    cantidad,
+                    monedas,
+                } => {
+                    println!(
+                        "El jugador {} ha comprado un arma: {}",
+                        self.nombre_jugador(jugador),
+                        arma.nombre()
+                    );
+                    let mensaje = Mensaje::ArmaComprada(arma, cantidad, monedas);
+                    let _ = self.enviar_mensaje(jugador, &mensaje);
+                }
+                Evento::BarcoReparado {
+                    jugador,
+                    barco,
+                    coordenadas,
+                    monedas,
+                } => {
+                    println!(
+                        "El jugador {} reparó su barco {}",
+                        self.nombre_jugador(jugador),
+                        barco
+                    );
+                    let mensaje = Mensaje::ReparacionExitosa(coordenadas, monedas);
+                    let _ = self.enviar_mensaje(jugador, &mensaje);
+                }
+                Evento::BarcoMovido { jugador, barco, .. } => {
+                    println!(
+                        "El jugador {} movió su barco {}",
+                        self.nombre_jugador(jugador),
+                        barco
+                    );
+                }
+                Evento::TurnoSaltado { .. } => {
+                    println!("Jugador salta su turno.");
+                }
+                Evento::RankingSolicitado { jugador } => {
+                    let ranking = ranking::obtener_ranking().unwrap_or_default();
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::Ranking(ranking));
+                }
+                Evento::AccionInvalida { jugador, motivo } => {
+                    if let Some(datos) = self.juego.obtener_jugador(jugador) {
+                        let mensaje = Mensaje::RepetirAccion(
+                            motivo,
+                            self.juego.mapa.serializar_barcos(&datos.barcos),
+                            datos.monedas,
+                        );
+                        let _ = self.enviar_mensaje(jugador, &mensaje);
+                    }
+                }
+                Evento::PremioObtenido { jugador, .. } => {
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::EventoSorpresaResultado(true));
+                }
+                Evento::Ganador {
+                    jugador,
+                    nombre,
+                    puntos,
+                } => {
+                    println!("El ganador es: {}", nombre);
+                    let _ = self.enviar_mensaje(jugador, &Mensaje::Ganaste(puntos));
+                }
+            }
+        }
+    }
+    /// Función que obtiene la versión pública de un evento del juego
+    ///
+    /// # Args
+    ///
+    /// `evento` - Evento producido por el juego
+    ///
+    /// # Returns
+    ///
+    /// `Option<EventoPublico>` - Evento para los espectadores, o `None` si solo le interesa al jugador involucrado
+    fn evento_publico(&self, evento: &Evento) -> Option<EventoPublico> {
+        let publico = match evento {
+            Evento::ResultadoAtaque {
+                jugador,
+                coordenadas,
+                puntos,
+                ..
+            } => EventoPublico::Ataque {
+                atacante: self.nombre_jugador(*jugador),
+                coordenadas: *coordenadas,
+                puntos: *puntos,
+            },
+            Evento::ResultadoBomba {
+                jugador,
+                centro,
+                puntos,
+                ..
+            } => EventoPublico::Bombardeo {
+                atacante: self.nombre_jugador(*jugador),
+                centro: *centro,
+                puntos: *puntos,
+            },
+            Evento::ResultadoTorpedo {
+                jugador,
+                impacto,
+                puntos,
+                ..
+            } => EventoPublico::Torpedo {
+                atacante: self.nombre_jugador(*jugador),
+                impacto: *impacto,
+                puntos: *puntos,
+            },
+            Evento::BarcoGolpeado {
+                jugador,
+                coordenadas,
+            } => EventoPublico::BarcoGolpeado {
+                jugador: self.nombre_jugador(*jugador),
+                coordenadas: *coordenadas,
+            },
+            Evento::BarcoHundido {
+                jugador,
+                coordenadas,
+            } => EventoPublico::BarcoHundido {
+                jugador: self.nombre_jugador(*jugador),
+                coordenadas: *coordenadas,
+            },
+            Evento::JugadorEliminado { nombre, .. } => EventoPublico::JugadorEliminado {
+                jugador: nombre.clone(),
+            },
+            Evento::CompraRealizada { jugador, tipo, .. } => EventoPublico::Compra {
+                jugador: self.nombre_jugador(*jugador),
+                tipo: *tipo,
+            },
+            Evento::BarcoReparado {
+                jugador,
+                coordenadas,
+                ..
+            } => EventoPublico::Reparacion {
+                jugador: self.nombre_jugador(*jugador),
+                coordenadas: *coordenadas,
+            },
+            Evento::PremioObtenido { jugador, monedas } => EventoPublico::PremioObtenido {
+                jugador: self.nombre_jugador(*jugador),
+                monedas: *monedas,
+            },
+            Evento::Ganador { nombre, puntos, .. } => EventoPublico::Ganador {
+                jugador: nombre.clone(),
+                puntos: *puntos,
+            },
+            _ => return None,
+        };
+        Some(publico)
+    }
+    /// Función que transmite un evento público a los espectadores
+    ///
+    /// Los eventos que agregaron las capacidades nuevas solo se transmiten a
+    /// los espectadores que las admiten.
+    ///
+    /// # Args
+    ///
+    /// `evento` - Evento a transmitir
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn transmitir(&mut self, evento: EventoPublico) {
+        let capacidad = match evento {
+            EventoPublico::Reparacion { .. } => Some(REPARACION),
+            EventoPublico::Bombardeo { .. } | EventoPublico::Torpedo { .. } => Some(ARMAS),
+            _ => None,
+        };
+        let mensaje = Mensaje::Transmision(evento);
+        self.enviar_a_espectadores(|espectador| {
+            capacidad
+                .is_none_or(|capacidad| espectador.enlace.admite(capacidad))
+                .then(|| mensaje.clone())
+        });
+    }
+    /// Función que arma el tablero que ve un espectador
+    ///
+    /// Los administradores lo ven completo; el resto, con los barcos de
+    /// todos los jugadores ocultos.
+    ///
+    /// # Args
+    ///
+    /// `administrador` - Si el espectador es administrador
+    ///
+    /// # Returns
+    ///
+    /// `Mensaje` - Tablero para el espectador
+    fn tablero_espectador(&self, administrador: bool) -> Mensaje {
+        if administrador {
+            Mensaje::TableroEspectador(self.juego.mapa.tablero_completo())
+        } else {
+            Mensaje::TableroEspectador(self.juego.mapa.tablero_para(String::new()))
+        }
+    }
+    /// Función que envía el tablero a los espectadores
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn transmitir_tablero(&mut self) {
+        if self.espectadores.is_empty() {
+            return;
+        }
+        let completo = self.tablero_espectador(true);
+        let oculto = self.tablero_espectador(false);
+        self.enviar_a_espectadores(|espectador| {
+            if espectador.administrador {
+                Some(completo.clone())
+            } else {
+                Some(oculto.clone())
+            }
+        });
+    }
+    /// Función que envía un mensaje a cada espectador
+    ///
+    /// Los espectadores a los que no se les puede enviar dejan la sala y
+    /// se libera su nombre.
+    ///
+    /// # Args
+    ///
+    /// `mensaje_para` - Arma el mensaje para cada espectador, o `None` si no se le envía nada
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn enviar_a_espectadores(&mut self, mensaje_para: impl Fn(&Espectador) -> Option<Mensaje>) {
+        let lobby = Arc::clone(&self.lobby);
+        self.espectadores.retain(|espectador| {
+            let Some(mensaje) = mensaje_para(espectador) else {
+                return true;
+            };
+            let enviado = espectador.enlace.enviar(&mensaje).is_ok();
+            if !enviado {
+                lobby.liberar_nombre(&espectador.nombre);
+            }
+            enviado
+        });
+    }
+    /// Función que saca a un jugador de la sala y, si la sala es emparejada, lo devuelve a la cola
+    ///
+    /// # Args
+    ///
+    /// `jugador_id` - ID del jugador que terminó de jugar
+    ///
+    /// # Returns
+    ///
+    /// `()` - No retorna nada
+    fn devolver_a_la_cola(&mut self, jugador_id: usize) {
+        let Some(enlace) = self.conexiones_jugadores.remove(&jugador_id) else {
+            return;
+        };
+        let Some(cola) = &self.cola else {
+            return;
+        };
+        let jugador = JugadorEntrante {
+            nombre: self.nombre_jugador(jugador_id),
+            enlace,
+            rol: Rol::Jugador,
+        };
+        if cola.send(jugador).is_ok() {
+            self.devueltos.insert(jugador_id);
+        }
+    }
+    fn nombre_jugador(&self, jugador_id: usize) -> String {
+        self.nombres_jugadores
+            .get(&jugador_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+    /// Función que crea un evento sorpresa
+    ///
+    /// El primer jugador en reclamar el cargamento gana el premio; el resto
+    /// recibe el impacto de una bomba.
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Resultado de la función
+    pub async fn crear_evento_sorpresa(&mut self) -> Result<(), CustomError> {
+        //vector perdedores
+        let mut perdedores: Vec<usize> = vec![];
+        self.transmitir(EventoPublico::EventoSorpresa);
+        let mut respuestas = JoinSet::new();
+
+        // Cada enlace pasa a una tarea propia mientras se espera su respuesta
+        // y vuelve a la sala al terminar.
+        let plazo = Duration::from_secs(self.configuracion.segundos_turno);
+        for (player_id, mut jugador) in self.conexiones_jugadores.drain() {
+            println!(
+                "Enviando mensaje de evento sorpresa al jugador {}",
+                player_id
+            ); // Debugging print
+
+            respuestas.spawn(async move {
+                let mut otros = Vec::new();
+                if let Err(e) = jugador.enviar(&Mensaje::EventoSorpresa) {
+                    eprintln!("Error enviando mensaje al jugador {}: {:?}", player_id, e);
+                    return (player_id, jugador, None, otros);
+                }
+
+                let limite = Instant::now() + plazo;
+                loop {
+                    let restante = limite.saturating_duration_since(Instant::now());
+                    match jugador.recibir_con_plazo(restante).await {
+                        Ok(Some(Mensaje::RespuestaEventoSorpresa(respuesta))) => {
+                            let respuesta = respuesta.trim().to_string();
+                            return (player_id, jugador, Some(respuesta), otros);
+                        }
+                        // Los `Pong` pendientes no son la respuesta; el chat se reparte al terminar
+                        Ok(Some(otro)) => otros.push(otro),
+                        Ok(None) => return (player_id, jugador, Some(String::new()), otros),
+                        Err(_) => {
+                            eprintln!("Error");
+                            return (player_id, jugador, None, otros);
+                        }
+                    }
+                }
+            });
+        }
+
+        let mut primero = None;
+        while let Some(resultado) = respuestas.join_next().await {
+            let (player_id, jugador, respuesta, otros) =
+                resultado.map_err(|_| CustomError::ErrorThreads)?;
+            self.conexiones_jugadores.insert(player_id, jugador);
+            for mensaje in otros {
+                self.atender_chat(player_id, mensaje);
+            }
+            let Some(respuesta) = respuesta else {
+                continue;
+            };
+            if respuesta == "primero" && primero.is_none() {
+                primero = Some(player_id);
+            } else {
+                perdedores.push(player_id);
+                let _ = self.enviar_mensaje(player_id, &Mensaje::EventoSorpresaResultado(false));
+            }
+        }
+
+        let eventos = self.juego.resolver_evento_sorpresa(primero, &perdedores);
+        self.notificar_eventos(eventos);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocolo::Protocolo;
+    use barcos::{orientacion::Orientacion, tipo_barco::TipoBarco};
+    use libreria::constantes::PREMIO;
+
+    fn sala() -> Sala {
+        let configuracion = ConfiguracionServidor::default();
+        let lobby = Arc::new(Lobby::new(configuracion.maximo_jugadores));
+        Sala::new("AAAA".to_string(), configuracion, lobby)
+    }
+
+    async fn esperar_estado(
+        cliente: &mut Enlace,
+        condicion: impl Fn(&EstadoPreparacion) -> bool,
+    ) -> EstadoPreparacion {
+        loop {
+            if let Mensaje::EstadoSala(estado) = cliente.recibir().await.unwrap() {
+                if condicion(&estado) {
+                    return estado;
+                }
+            }
+        }
+    }
+
+    fn entrante(nombre: &str, enlace: Enlace) -> JugadorEntrante {
+        JugadorEntrante {
+            nombre: nombre.to_string(),
+            enlace,
+            rol: Rol::Jugador,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sala_comienza_cuando_todos_estan_listos() {
+        let lobby = Arc::new(Lobby::new(4));
+        let (codigo, mut entrantes) = lobby.crear_sala("sala".to_string(), None);
+        let configuracion = ConfiguracionServidor {
+            minimo_jugadores: 2,
+            segundos_cuenta_regresiva: 1,
+            ..Default::default()
+        };
+        let mut sala = Sala::new(codigo.clone(), configuracion, Arc::clone(&lobby));
+        let tarea = tokio::spawn(async move {
+            let comenzo = sala.esperar_comienzo(&mut entrantes).await.unwrap();
+            (sala, comenzo)
+        });
+
+        let (conexion_servidor, mut ana) = Enlace::par();
+        lobby
+            .unirse(&codigo, None, entrante("ana", conexion_servidor))
+            .unwrap();
+        // Beto usa un cliente viejo, que solo entiende la pregunta de comienzo
+        let (mut conexion_servidor, mut beto) = Enlace::par();
+        conexion_servidor.acordar_protocolo(Protocolo::sin_saludo());
+        lobby
+            .unirse(&codigo, None, entrante("beto", conexion_servidor))
+            .unwrap();
+
+        let estado = esperar_estado(&mut ana, |estado| estado.jugadores.len() == 2).await;
+        assert_eq!(estado.anfitrion, "ana");
+        assert_eq!(estado.segundos_para_comenzar, None);
+        assert!(matches!(
+            beto.recibir().await.unwrap(),
+            Mensaje::SalaUnida(_)
+        ));
+        assert!(matches!(
+            beto.recibir().await.unwrap(),
+            Mensaje::PreguntaComienzo
+        ));
+        beto.enviar(&Mensaje::RespuestaComienzo(true)).unwrap();
+        ana.enviar(&Mensaje::RespuestaComienzo(true)).unwrap();
+        esperar_estado(&mut ana, |estado| estado.segundos_para_comenzar.is_some()).await;
+
+        // Quien llega durante la cuenta regresiva juega aunque no esté listo
+        let (conexion_servidor, mut carla) = Enlace::par();
+        lobby
+            .unirse(&codigo, None, entrante("carla", conexion_servidor))
+            .unwrap();
+        assert!(matches!(
+            carla.recibir().await.unwrap(),
+            Mensaje::SalaUnida(_)
+        ));
+        let estado = esperar_estado(&mut carla, |_| true).await;
+        assert_eq!(estado.jugadores[2], ("carla".to_string(), false));
+        assert!(estado.segundos_para_comenzar.is_some());
+
+        let (sala, comenzo) = tarea.await.unwrap();
+        assert!(comenzo);
+        assert!(lobby.listar_salas()[0].en_curso);
+        assert_eq!(sala.juego.jugadores.len(), 3);
+        for cliente in [&mut ana, &mut beto, &mut carla] {
+            loop {
+                if matches!(cliente.recibir().await.unwrap(), Mensaje::ComenzoJuego) {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_el_anfitrion_pasa_al_jugador_mas_antiguo() {
+        let mut sala = sala();
+        let mut clientes = Vec::new();
+        for nombre in ["ana", "beto"] {
+            let (conexion_servidor, conexion_cliente) = Enlace::par();
+            sala.agregar_jugador(entrante(nombre, conexion_servidor));
+            clientes.push(conexion_cliente);
+        }
+        drop(clientes.remove(0));
+        sala.quitar_ausentes();
+
+        // Quien llega tarde no toma el lugar de ana como anfitrión
+        let (conexion_servidor, _carla) = Enlace::par();
+        sala.agregar_jugador(entrante("carla", conexion_servidor));
+        assert_eq!(
+            sala.anfitrion().map(|id| sala.nombre_jugador(id)),
+            Some("beto".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_anfitrion_fuerza_el_comienzo() {
+        let lobby = Arc::new(Lobby::new(4));
+        let (codigo, mut entrantes) = lobby.crear_sala("sala".to_string(), None);
+        let configuracion = ConfiguracionServidor {
+            minimo_jugadores: 2,
+            segundos_cuenta_regresiva: 60,
+            ..Default::default()
+        };
+        let mut sala = Sala::new(codigo.clone(), configuracion, Arc::clone(&lobby));
+        let tarea = tokio::spawn(async move {
+            let comenzo = sala.esperar_comienzo(&mut entrantes).await.unwrap();
+            (sala, comenzo)
+        });
+
+        let mut clientes = Vec::new();
+        for nombre in ["ana", "beto", "carla"] {
+            lobby.registrar_nombre(nombre);
+            let (conexion_servidor, conexion_cliente) = Enlace::par();
+            lobby
+                .unirse(&codigo, None, entrante(nombre, conexion_servidor))
+                .unwrap();
+            clientes.push(conexion_cliente);
+        }
+        esperar_estado(&mut clientes[0], |estado| estado.jugadores.len() == 3).await;
+
+        // Si alguien se va antes de comenzar se libera su lugar
+        drop(clientes.remove(2));
+        esperar_estado(&mut clientes[0], |estado| estado.jugadores.len() == 2).await;
+        assert_eq!(lobby.listar_salas()[0].jugadores, 2);
+        assert!(lobby.registrar_nombre("carla"));
+
+        for cliente in clientes.iter() {
+            cliente.enviar(&Mensaje::RespuestaComienzo(true)).unwrap();
+        }
+        esperar_estado(&mut clientes[0], |estado| {
+            estado.segundos_para_comenzar.is_some()
+        })
+        .await;
+        clientes[1]
+            .enviar(&Mensaje::RespuestaComienzo(false))
+            .unwrap();
+        let estado = esperar_estado(&mut clientes[0], |estado| {
+            estado.segundos_para_comenzar.is_none()
+        })
+        .await;
+        assert_eq!(
+            estado.jugadores,
+            vec![("ana".to_string(), true), ("beto".to_string(), false)]
+        );
+
+        clientes[1].enviar(&Mensaje::ForzarComienzo).unwrap();
+        loop {
+            if let Mensaje::ComienzoRechazado(motivo) = clientes[1].recibir().await.unwrap() {
+                assert_eq!(motivo, CustomError::ErrorNoEsAnfitrion.to_string());
+                break;
+            }
+        }
+        clientes[0].enviar(&Mensaje::ForzarComienzo).unwrap();
+        let (sala, comenzo) = tarea.await.unwrap();
+        assert!(comenzo);
+        assert_eq!(sala.juego.jugadores.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_cierra_la_sala() {
+        let lobby = Arc::new(Lobby::new(4));
+        lobby.registrar_nombre("ana");
+        let (codigo, entrantes) = lobby.crear_sala("sala".to_string(), None);
+        let sala = Sala::new(
+            codigo.clone(),
+            ConfiguracionServidor::default(),
+            Arc::clone(&lobby),
+        );
+        let (conexion_servidor, _conexion_cliente) = Enlace::par();
+        lobby
+            .unirse(
+                &codigo,
+                None,
+                JugadorEntrante {
+                    nombre: "ana".to_string(),
+                    enlace: conexion_servidor,
+                    rol: Rol::Jugador,
+                },
+            )
+            .unwrap();
+        lobby.cerrar_sala(&codigo, &[]);
+
+        sala.run(entrantes).await.unwrap();
+        assert!(lobby.listar_salas().is_empty());
+        assert!(lobby.registrar_nombre("ana"));
+    }
+
+    async fn sala_en_curso(configuracion: ConfiguracionServidor) -> (Sala, Vec<Enlace>) {
+        let lobby = Arc::new(Lobby::new(configuracion.maximo_jugadores));
+        let mut sala = Sala::new("AAAA".to_string(), configuracion, lobby);
+        let mut clientes = Vec::new();
+        for nombre in ["ana", "beto"] {
+            let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+            sala.agregar_jugador(JugadorEntrante {
+                nombre: nombre.to_string(),
+                enlace: conexion_servidor,
+                rol: Rol::Jugador,
+            });
+            assert!(matches!(
+                conexion_cliente.recibir().await.unwrap(),
+                Mensaje::SalaUnida(_)
+            ));
+            clientes.push(conexion_cliente);
+        }
+        sala.en_curso = true;
+        (sala, clientes)
+    }
+
+    #[tokio::test]
+    async fn test_jugador_desconectado_vuelve_a_la_partida() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        sala.marcar_desconectado(1);
+        assert!(matches!(
+            clientes[0].recibir().await.unwrap(),
+            Mensaje::NotificacionDesconexion(nombre) if nombre == "beto"
+        ));
+
+        sala.expulsar_desconectados();
+        assert!(!sala.juego.finalizo());
+
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        sala.recibir_entrante(JugadorEntrante {
+            nombre: "beto".to_string(),
+            enlace: conexion_servidor,
+            rol: Rol::Jugador,
+        });
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Reanudar(_, barcos, 500) if !barcos.is_empty()
+        ));
+        assert!(matches!(
+            clientes[0].recibir().await.unwrap(),
+            Mensaje::NotificacionReconexion(nombre) if nombre == "beto"
+        ));
+        assert!(sala.desconectados.is_empty());
+        assert_eq!(sala.juego.jugadores.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_desconectado_sin_volver_abandona_la_partida() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor {
+            segundos_reconexion: 0,
+            ..Default::default()
+        })
+        .await;
+        sala.marcar_desconectado(1);
+        sala.expulsar_desconectados();
+
+        assert!(sala.juego.finalizo());
+        assert!(sala.juego.jugadores[1].ha_perdido);
+        let mut recibidos = Vec::new();
+        for _ in 0..3 {
+            recibidos.push(clientes[0].recibir().await.unwrap());
+        }
+        assert!(matches!(
+            recibidos.as_slice(),
+            [
+                Mensaje::NotificacionDesconexion(_),
+                Mensaje::NotificacionEliminacion(_),
+                Mensaje::Ganaste(_)
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_turno_agotado_se_saltea() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor {
+            segundos_turno: 1,
+            ..Default::default()
+        })
+        .await;
+        sala.jugar_turno(0).await.unwrap();
+
+        let mut recibidos = Vec::new();
+        for _ in 0..3 {
+            recibidos.push(clientes[0].recibir().await.unwrap());
+        }
+        assert!(matches!(
+            recibidos.as_slice(),
+            [
+                Mensaje::RealiceAccion,
+                Mensaje::Instantanea(..),
+                Mensaje::TurnoAgotado
+            ]
+        ));
+        assert_eq!(sala.juego.jugador_en_turno(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_jugador_que_no_contesta_el_ping_se_desconecta() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor {
+            segundos_latido: 1,
+            ..Default::default()
+        })
+        .await;
+        assert!(sala.jugar_turno(0).await.is_err());
+
+        let mut recibidos = Vec::new();
+        for _ in 0..3 {
+            recibidos.push(clientes[0].recibir().await.unwrap());
+        }
+        assert!(matches!(
+            recibidos.as_slice(),
+            [
+                Mensaje::RealiceAccion,
+                Mensaje::Instantanea(..),
+                Mensaje::Ping
+            ]
+        ));
+        assert!(matches!(
+            clientes[1].recibir().await.unwrap(),
+            Mensaje::Ping
+        ));
+        assert_eq!(sala.juego.jugador_en_turno(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_cliente_sin_latido_no_recibe_ping() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor {
+            segundos_turno: 2,
+            segundos_latido: 1,
+            ..Default::default()
+        })
+        .await;
+        if let Some(enlace) = sala.conexiones_jugadores.get_mut(&0) {
+            enlace.acordar_protocolo(Protocolo::sin_saludo());
+        }
+        sala.jugar_turno(0).await.unwrap();
+
+        let mut recibidos = Vec::new();
+        for _ in 0..3 {
+            recibidos.push(clientes[0].recibir().await.unwrap());
+        }
+        assert!(matches!(
+            recibidos.as_slice(),
+            [
+                Mensaje::RealiceAccion,
+                Mensaje::Tablero(..),
+                Mensaje::TurnoAgotado
+            ]
+        ));
+        assert!(matches!(
+            clientes[1].recibir().await.unwrap(),
+            Mensaje::Ping
+        ));
+        assert_eq!(sala.juego.jugador_en_turno(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_jugadores_ubican_su_flota_antes_de_comenzar() {
+        let mut configuracion = ConfiguracionServidor::default();
+        configuracion.reglas.flota_inicial = vec![TipoBarco::Fragata, TipoBarco::Acorazado];
+        let (mut sala, mut clientes) = sala_en_curso(configuracion).await;
+        // La flota de beto se ubica al azar y podría ocupar el lugar elegido por ana
+        let posiciones_beto: Vec<(i32, i32)> = sala.juego.jugadores[1]
+            .barcos
+            .iter()
+            .flat_map(|barco| barco.posiciones.clone())
+            .collect();
+        sala.juego.mapa.liberar_posiciones(&posiciones_beto);
+        let mut beto = clientes.pop().unwrap();
+        let mut ana = clientes.pop().unwrap();
+        let fase = tokio::spawn(async move {
+            sala.desplegar_flotas(5).await;
+            sala
+        });
+
+        let Mensaje::Despliegue(_, barcos, 5) = ana.recibir().await.unwrap() else {
+            panic!("Se esperaba el despliegue");
+        };
+        let id_barco = barcos
+            .iter()
+            .max_by_key(|(_, posiciones)| posiciones.len())
+            .unwrap()
+            .0;
+        let id_otro = barcos.iter().find(|(id, _)| *id != id_barco).unwrap().0;
+        ana.enviar(&Mensaje::DesplegarFlota(vec![(
+            id_barco,
+            (-1, 0),
+            Orientacion::Horizontal,
+        )]))
+        .unwrap();
+        assert!(matches!(
+            ana.recibir().await.unwrap(),
+            Mensaje::DespliegueRechazado(_)
+        ));
+        ana.enviar(&Mensaje::DesplegarFlota(vec![
+            (id_barco, (0, 0), Orientacion::Vertical),
+            (id_otro, (2, 0), Orientacion::Horizontal),
+        ]))
+        .unwrap();
+        let Mensaje::FlotaDesplegada(barcos) = ana.recibir().await.unwrap() else {
+            panic!("Se esperaba la flota desplegada");
+        };
+        let (_, posiciones) = barcos.iter().find(|(id, _)| *id == id_barco).unwrap();
+        assert_eq!(posiciones[..2], [(0, 0), (0, 1)]);
+
+        assert!(matches!(
+            beto.recibir().await.unwrap(),
+            Mensaje::Despliegue(..)
+        ));
+        beto.enviar(&Mensaje::AutoDespliegue).unwrap();
+        assert!(matches!(
+            beto.recibir().await.unwrap(),
+            Mensaje::FlotaDesplegada(_)
+        ));
+
+        let sala = fase.await.unwrap();
+        assert_eq!(sala.juego.mapa.tablero[[1, 0]], '0');
+    }
+
+    #[tokio::test]
+    async fn test_tablero_se_envia_como_cambios() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        sala.enviar_tablero(0).unwrap();
+        let mut vista = match clientes[0].recibir().await.unwrap() {
+            Mensaje::Instantanea(secuencia, tablero, barcos, _) => {
+                VistaTablero::new(secuencia, tablero, barcos)
+            }
+            otro => panic!("Mensaje inesperado: {:?}", otro),
+        };
+
+        let eventos = sala.juego.abandonar(1);
+        sala.notificar_eventos(eventos);
+        sala.enviar_tablero(0).unwrap();
+        let mensaje = loop {
+            match clientes[0].recibir().await.unwrap() {
+                Mensaje::Delta(secuencia, cambios, _) => break (secuencia, cambios),
+                _ => continue,
+            }
+        };
+        vista.aplicar(mensaje.0, &mensaje.1).unwrap();
+        let (tablero, barcos, _) = sala.estado_visible(0).unwrap();
+        assert_eq!(vista, VistaTablero::new(2, tablero, barcos));
+
+        sala.enviar_instantanea(0).unwrap();
+        assert!(matches!(
+            clientes[0].recibir().await.unwrap(),
+            Mensaje::Instantanea(3, ..)
+        ));
+        assert_eq!(sala.vistas[&0].secuencia, 3);
+    }
+
+    #[tokio::test]
+    async fn test_espectadores_reciben_eventos_publicos_sin_jugar() {
+        let (mut sala, _clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        let mut espectadores = Vec::new();
+        for (nombre, administrador) in [("carla", false), ("dario", true)] {
+            let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+            sala.recibir_entrante(JugadorEntrante {
+                nombre: nombre.to_string(),
+                enlace: conexion_servidor,
+                rol: Rol::Espectador { administrador },
+            });
+            assert_eq!(
+                conexion_cliente.recibir().await.unwrap(),
+                Mensaje::Observando("AAAA".to_string(), administrador)
+            );
+            let Mensaje::TableroEspectador(tablero) = conexion_cliente.recibir().await.unwrap()
+            else {
+                panic!("Se esperaba el tablero");
+            };
+            let ve_barcos = tablero.iter().flatten().any(char::is_ascii_digit);
+            assert_eq!(ve_barcos, administrador);
+            espectadores.push(conexion_cliente);
+        }
+        assert_eq!(sala.juego.jugadores.len(), 2);
+        assert_eq!(sala.conexiones_jugadores.len(), 2);
+
+        let objetivo = sala.juego.jugadores[1].barcos[0].posiciones[0];
+        let eventos = sala.juego.aplicar(0, Instruccion::Ataque(0, objetivo));
+        sala.notificar_eventos(eventos);
+        for espectador in espectadores.iter_mut() {
+            assert_eq!(
+                espectador.recibir().await.unwrap(),
+                Mensaje::Transmision(EventoPublico::Ataque {
+                    atacante: "ana".to_string(),
+                    coordenadas: objetivo,
+                    puntos: sala.juego.jugadores[0].puntos,
+                })
+            );
+            assert!(matches!(
+                espectador.recibir().await.unwrap(),
+                Mensaje::Transmision(
+                    EventoPublico::BarcoGolpeado { jugador, coordenadas }
+                    | EventoPublico::BarcoHundido { jugador, coordenadas }
+                ) if jugador == "beto" && coordenadas == objetivo
+            ));
+        }
+
+        drop(espectadores.remove(0));
+        sala.transmitir(EventoPublico::EventoSorpresa);
+        assert_eq!(sala.espectadores.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_la_tienda_ofrece_solo_lo_que_admite_cada_cliente() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        sala.notificar_eventos(vec![Evento::TiendaSolicitada { jugador: 0 }]);
+        let Mensaje::Catalogo(catalogo, _) = clientes[0].recibir().await.unwrap() else {
+            panic!("Se esperaba el catálogo completo");
+        };
+        assert_eq!(catalogo, tienda::catalogo_completo(&sala.juego.reglas));
+
+        if let Some(enlace) = sala.conexiones_jugadores.get_mut(&1) {
+            enlace.acordar_protocolo(Protocolo {
+                version: 3,
+                capacidades: vec![ARMAS.to_string()],
+            });
+        }
+        sala.notificar_eventos(vec![Evento::TiendaSolicitada { jugador: 1 }]);
+        let Mensaje::Catalogo(catalogo, _) = clientes[1].recibir().await.unwrap() else {
+            panic!("Se esperaba el catálogo con las armas");
+        };
+        assert!(catalogo
+            .iter()
+            .any(|articulo| matches!(articulo.producto, Producto::Arma(_))));
+        assert!(catalogo
+            .iter()
+            .all(|articulo| articulo.producto != Producto::Reparacion));
+
+        if let Some(enlace) = sala.conexiones_jugadores.get_mut(&1) {
+            enlace.acordar_protocolo(Protocolo::sin_saludo());
+        }
+        sala.notificar_eventos(vec![Evento::TiendaSolicitada { jugador: 1 }]);
+        assert_eq!(
+            clientes[1].recibir().await.unwrap(),
+            Mensaje::AbrirTienda(tienda::catalogo(), sala.juego.reglas.monedas_iniciales)
+        );
+    }
+
+    fn chat(para: Option<&str>, texto: &str) -> Mensaje {
+        Mensaje::Chat {
+            para: para.map(str::to_string),
+            texto: texto.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_global_privado_y_silenciado() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        let (conexion_servidor, mut carla) = Enlace::par();
+        sala.agregar_jugador(JugadorEntrante {
+            nombre: "carla".to_string(),
+            enlace: conexion_servidor,
+            rol: Rol::Jugador,
+        });
+        carla.recibir().await.unwrap();
+
+        sala.atender_chat(0, chat(None, " hola "));
+        let esperado = Mensaje::ChatRecibido {
+            de: "ana".to_string(),
+            texto: "hola".to_string(),
+            privado: false,
+        };
+        assert_eq!(clientes[1].recibir().await.unwrap(), esperado);
+        assert_eq!(carla.recibir().await.unwrap(), esperado);
+        assert!(clientes[0].pendiente().is_none());
+
+        sala.atender_chat(1, chat(Some("carla"), "solo para vos"));
+        assert!(matches!(
+            carla.recibir().await.unwrap(),
+            Mensaje::ChatRecibido { de, privado: true, .. } if de == "beto"
+        ));
+
+        sala.atender_chat(2, Mensaje::Silenciar("ana".to_string(), true));
+        sala.atender_chat(0, chat(None, "¿me leen?"));
+        assert!(matches!(
+            clientes[1].recibir().await.unwrap(),
+            Mensaje::ChatRecibido { .. }
+        ));
+        assert!(carla.pendiente().is_none());
+
+        sala.atender_chat(1, chat(Some("dario"), "hola"));
+        sala.atender_chat(1, chat(None, &"a".repeat(500)));
+        for _ in 0..2 {
+            assert!(matches!(
+                clientes[1].recibir().await.unwrap(),
+                Mensaje::ChatRechazado(_)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cliente_sin_chat_no_recibe_mensajes_de_chat() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor::default()).await;
+        if let Some(enlace) = sala.conexiones_jugadores.get_mut(&1) {
+            enlace.acordar_protocolo(Protocolo::sin_saludo());
+        }
+        let (mut conexion_servidor, mut espectador) = Enlace::par();
+        conexion_servidor.acordar_protocolo(Protocolo::sin_saludo());
+        sala.recibir_entrante(JugadorEntrante {
+            nombre: "carla".to_string(),
+            enlace: conexion_servidor,
+            rol: Rol::Espectador {
+                administrador: false,
+            },
+        });
+        for _ in 0..2 {
+            espectador.recibir().await.unwrap();
+        }
+
+        sala.atender_chat(0, chat(None, "hola"));
+        sala.atender_chat(1, chat(None, &"a".repeat(500)));
+        assert!(clientes[1].pendiente().is_none());
+        assert!(espectador.pendiente().is_none());
+
+        sala.atender_chat(0, chat(Some("beto"), "¿me leés?"));
+        assert!(matches!(
+            clientes[0].recibir().await.unwrap(),
+            Mensaje::ChatRechazado(_)
+        ));
+        assert!(clientes[1].pendiente().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chat_llega_durante_el_turno_de_otro() {
+        let (mut sala, mut clientes) = sala_en_curso(ConfiguracionServidor {
+            segundos_turno: 1,
+            ..Default::default()
+        })
+        .await;
+        clientes[1].enviar(&chat(None, "apurate")).unwrap();
+        sala.jugar_turno(0).await.unwrap();
+
+        let mut recibidos = Vec::new();
+        for _ in 0..4 {
+            recibidos.push(clientes[0].recibir().await.unwrap());
+        }
+        assert!(matches!(
+            recibidos.as_slice(),
+            [
+                Mensaje::RealiceAccion,
+                Mensaje::Instantanea(..),
+                Mensaje::ChatRecibido { de, .. },
+                Mensaje::TurnoAgotado
+            ] if de == "beto"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sala_emparejada_devuelve_a_la_cola() {
+        let lobby = Arc::new(Lobby::new(4));
+        let (cola, mut retornados) = tokio::sync::mpsc::unbounded_channel();
+        let mut sala = Sala::emparejada(
+            "AAAA".to_string(),
+            ConfiguracionServidor::default(),
+            lobby,
+            cola,
+        );
+        let mut clientes = Vec::new();
+        for nombre in ["ana", "beto"] {
+            let (conexion_servidor, conexion_cliente) = Enlace::par();
+            sala.agregar_jugador(entrante(nombre, conexion_servidor));
+            clientes.push(conexion_cliente);
+        }
+
+        let eventos = sala.juego.abandonar(1);
+        sala.notificar_eventos(eventos);
+        let beto = retornados.try_recv().unwrap();
+        assert_eq!(beto.nombre, "beto");
+        assert!(sala.devueltos.contains(&1));
+        assert!(!sala.devueltos.contains(&0));
+
+        // El enlace que vuelve a la cola es el mismo, el cliente no se reconecta
+        beto.enlace.enviar(&Mensaje::Ping).unwrap();
+        loop {
+            match clientes[1].recibir().await.unwrap() {
+                Mensaje::Ping => break,
+                Mensaje::SalaUnida(_) | Mensaje::Perdiste(_) => {}
+                otro => panic!("Mensaje inesperado: {:?}", otro),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evento_sorpresa_no_se_repite_si_se_desconecta_el_jugador_en_turno() {
+        let mut configuracion = ConfiguracionServidor {
+            segundos_turno: 1,
+            ..Default::default()
+        };
+        configuracion.reglas.ronda_evento_sorpresa = 0;
+        let (mut sala, mut clientes) = sala_en_curso(configuracion).await;
+        let mut beto = clientes.pop().unwrap();
+        // Ana, que tiene el primer turno, se desconecta durante la ronda del evento
+        drop(clientes);
+        let (_entrada, mut entrantes) = tokio::sync::mpsc::unbounded_channel();
+        let partida = tokio::spawn(async move {
+            let _ = sala.jugar_partida(&mut entrantes).await;
+        });
+
+        let mut eventos_sorpresa = 0;
+        let mut turnos = 0;
+        while turnos < 2 {
+            match beto.recibir().await.unwrap() {
+                Mensaje::EventoSorpresa => {
+                    eventos_sorpresa += 1;
+                    beto.enviar(&Mensaje::RespuestaEventoSorpresa("tarde".to_string()))
+                        .unwrap();
+                }
+                Mensaje::RealiceAccion => {
+                    turnos += 1;
+                    beto.enviar(&Mensaje::Accion(Instruccion::Saltar)).unwrap();
+                }
+                _ => {}
+            }
+        }
+        partida.abort();
+        assert_eq!(eventos_sorpresa, 1);
+    }
+
+    #[tokio::test]
+    async fn test_evento_sorpresa_con_conexiones_en_memoria() {
+        let mut sala = sala();
+        let mut handles = Vec::new();
+        for (id, respuesta) in ["primero", "tarde"].into_iter().enumerate() {
+            let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+            sala.conexiones_jugadores.insert(id, conexion_servidor);
+            sala.juego.agregar_jugador(id, id.to_string());
+            handles.push(tokio::spawn(async move {
+                assert!(matches!(
+                    conexion_cliente.recibir().await.unwrap(),
+                    Mensaje::EventoSorpresa
+                ));
+                conexion_cliente
+                    .enviar(&Mensaje::RespuestaEventoSorpresa(respuesta.to_string()))
+                    .unwrap();
+                match conexion_cliente.recibir().await.unwrap() {
+                    Mensaje::EventoSorpresaResultado(gano) => gano,
+                    otro => panic!("Mensaje inesperado: {:?}", otro),
+                }
+            }));
+        }
+
+        sala.crear_evento_sorpresa().await.unwrap();
+
+        let mut resultados = Vec::new();
+        for handle in handles {
+            resultados.push(handle.await.unwrap());
+        }
+        assert_eq!(resultados, vec![true, false]);
+        assert_eq!(sala.juego.jugadores[0].monedas, 500 + PREMIO);
+        assert_eq!(sala.juego.jugadores[1].monedas, 500);
+    }
+}
@@ -1,60 +1,89 @@
-use libreria::{
-    constantes::{CORDENADAS_BOMBA, PREMIO},
-    custom_error::CustomError,
-};
-use std::{
-    collections::HashMap,
-    io::{Read, Write},
-    net::{TcpListener, TcpStream},
-    sync::{mpsc, Arc, Mutex},
-    thread,
+use libreria::custom_error::CustomError;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, sync::mpsc::UnboundedSender};
+
+use crate::{
+    codec::Codec,
+    configuracion::ConfiguracionServidor,
+    emparejamiento::Emparejamiento,
+    enlace::Enlace,
+    lobby::{JugadorEntrante, Lobby, Rol},
+    mensaje::Mensaje,
+    protocolo::{Protocolo, CHAT, RECONEXION, VERSION_MINIMA, VERSION_PROTOCOLO},
+    sala::Sala,
 };
 
-use crate::{juego::Juego, jugador::Jugador, mensaje::Mensaje};
+/// Tiempo que se espera el `Hola` antes de atender al cliente como uno sin saludo
+const ESPERA_SALUDO: Duration = Duration::from_secs(2);
 
 /// Estructura que representa el servidor
-
+///
+/// El servidor solo registra a los jugadores y los ubica en una sala; cada
+/// conexión y cada sala corren en su propia tarea asincrónica, por lo que la
+/// cantidad de hilos no crece con la de jugadores. Los jugadores que buscan
+/// partida sin elegir sala pasan a la cola de emparejamiento. Opcionalmente
+/// también acepta clientes por WebSocket, que juegan en las mismas salas.
 #[derive(Clone)]
 pub struct Server {
     arc_server: Arc<TcpListener>,
-    jugadores: Arc<Mutex<Vec<thread::JoinHandle<()>>>>,
-    pub conexiones_jugadores: HashMap<usize, Arc<Mutex<TcpStream>>>,
-    nombres_jugadores: HashMap<usize, String>,
-    pub juego: Juego,
-    next_player_id: usize,
-    jugadores_conectados: usize,
+    websocket: Option<Arc<TcpListener>>,
+    lobby: Arc<Lobby>,
+    emparejamiento: UnboundedSender<JugadorEntrante>,
+    configuracion: ConfiguracionServidor,
 }
 
 impl Server {
     /// Función que crea un nuevo servidor
     ///
+    /// # Args
+    ///
+    /// `configuracion` - Dirección, límites de jugadores y parámetros de la partida
+    ///
     /// # Returns
     ///
     /// `Result<Self, CustomError>` - Resultado de la función
     ///
     /// # Errors
     ///
-    /// `CustomError::ErrorCreatingSocket` - Error al crear el socket
-    pub fn new() -> Result<Self, CustomError> {
-        let server =
-            TcpListener::bind("127.0.0.1:8080").map_err(|_| CustomError::ErrorCreatingSocket)?;
-        let jugadores = Arc::new(Mutex::new(Vec::new()));
-        let conexiones_jugadores = HashMap::new();
-        let nombres_jugadores = HashMap::new();
-        let juego = Juego::new(0);
+    /// `CustomError::ErrorCreatingSocket` - Error al crear alguno de los sockets
+    pub async fn new(configuracion: ConfiguracionServidor) -> Result<Self, CustomError> {
+        let server = TcpListener::bind(configuracion.direccion())
+            .await
+            .map_err(|_| CustomError::ErrorCreatingSocket)?;
+        let websocket = match configuracion.direccion_websocket() {
+            Some(direccion) => Some(Arc::new(
+                TcpListener::bind(direccion)
+                    .await
+                    .map_err(|_| CustomError::ErrorCreatingSocket)?,
+            )),
+            None => None,
+        };
+        let lobby = Arc::new(Lobby::new(configuracion.maximo_jugadores));
+        let emparejamiento = Emparejamiento::lanzar(configuracion.clone(), Arc::clone(&lobby));
         println!("Servidor iniciado.");
         Ok(Server {
             arc_server: Arc::new(server),
-            jugadores,
-            conexiones_jugadores,
-            nombres_jugadores,
-            juego,
-            next_player_id: 0,
-            jugadores_conectados: 0,
+            websocket,
+            lobby,
+            emparejamiento,
+            configuracion,
         })
     }
+    /// Función que obtiene la dirección en la que se aceptan clientes por WebSocket
+    ///
+    /// # Returns
+    ///
+    /// `Option<SocketAddr>` - Dirección del socket, si se habilitó el WebSocket
+    pub fn direccion_websocket(&self) -> Option<SocketAddr> {
+        self.websocket
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+    }
     /// Función que ejecuta el servidor
     ///
+    /// Cada conexión nueva se atiende en su propia tarea. Si se habilitó el
+    /// WebSocket, sus conexiones se aceptan en otra tarea.
+    ///
     /// # Returns
     ///
     /// `Result<(), CustomError>` - Resultado de la función
@@ -62,346 +91,569 @@ impl Server {
     /// # Errors
     ///
     /// `CustomError::ErrorAceptandoConexion` - Error al aceptar la conexión
-    ///
-    /// `CustomError::ErrorRecibiendoInstruccion` - Error al recibir la instrucción
-    pub fn run(&mut self) -> Result<(), CustomError> {
-        let mut self_clone = self.clone();
-        for stream in self.arc_server.incoming() {
-            let mut stream = stream.map_err(|_| CustomError::ErrorAceptandoConexion)?;
-            self_clone.jugadores_conectados += 1;
+    pub async fn run(&self) -> Result<(), CustomError> {
+        if let Some(websocket) = &self.websocket {
+            let self_clone = self.clone();
+            let websocket = Arc::clone(websocket);
+            tokio::spawn(async move {
+                if let Err(err) = self_clone.escuchar_websocket(websocket).await {
+                    eprintln!("Error en el WebSocket: {}", err);
+                }
+            });
+        }
+        loop {
+            let (stream, _) = self
+                .arc_server
+                .accept()
+                .await
+                .map_err(|_| CustomError::ErrorAceptandoConexion)?;
+            let enlace = Enlace::desde_socket(stream);
             println!("Nuevo jugador conectado");
 
-            let mensaje_serializado = serde_json::to_string(&Mensaje::Registro).unwrap();
-            Self::enviar_mensaje(&mut stream, mensaje_serializado.as_bytes().to_vec()).unwrap();
-
-            let mut buffer = [0; 2048];
-            loop {
-                let bytes_read = stream
-                    .read(&mut buffer)
-                    .map_err(|_| CustomError::ErrorRecibiendoInstruccion)?;
-                let nombre_usuario = String::from_utf8_lossy(&buffer[..bytes_read])
-                    .trim()
-                    .to_string();
-
-                if !self_clone
-                    .nombres_jugadores
-                    .values()
-                    .any(|name| name == &nombre_usuario)
-                {
-                    println!(
-                        "Jugador conectado con el nombre de usuario: {}",
-                        nombre_usuario
-                    );
-                    self_clone.handle_client(stream, nombre_usuario)?;
-                    break;
+            let self_clone = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = self_clone.atender_conexion(enlace).await {
+                    eprintln!("Error atendiendo al jugador: {}", err);
                 }
-
-                let mensaje_serializado = serde_json::to_string(&Mensaje::NombreEnUso).unwrap();
-                Self::enviar_mensaje(&mut stream, mensaje_serializado.as_bytes().to_vec()).unwrap();
-            }
+            });
         }
-        Ok(())
     }
 
-    /// Función que maneja al cliente
+    /// Función que acepta clientes por WebSocket y atiende cada uno en su propia tarea
     ///
     /// # Args
     ///
-    /// `stream` - Flujo de datos
-    ///
-    /// `nombre_usuario` - Nombre del usuario
+    /// `listener` - Socket en el que se aceptan las conexiones
     ///
     /// # Returns
     ///
     /// `Result<(), CustomError>` - Resultado de la función
-    fn handle_client(
-        &mut self,
-        stream: TcpStream,
-        nombre_usuario: String,
-    ) -> Result<(), CustomError> {
-        let jugador_id = self.next_player_id;
-        self.next_player_id += 1;
-        let player_connection = Arc::new(Mutex::new(stream));
-        self.conexiones_jugadores
-            .insert(jugador_id, player_connection);
-        self.nombres_jugadores
-            .insert(jugador_id, nombre_usuario.clone());
-        self.juego
-            .agregar_jugador(jugador_id, nombre_usuario.clone());
-        let self_clone = self.clone();
-        let handle = thread::spawn(move || {
-            let _ = self_clone
-                .preguntar_comienzo_juego()
-                .map_err(|_| CustomError::ErrorThreads);
-        });
-
-        self.jugadores.lock().unwrap().push(handle);
-
-        Ok(())
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorAceptandoConexion` - Error al aceptar la conexión
+    async fn escuchar_websocket(&self, listener: Arc<TcpListener>) -> Result<(), CustomError> {
+        loop {
+            let (stream, _) = listener
+                .accept()
+                .await
+                .map_err(|_| CustomError::ErrorAceptandoConexion)?;
+            let self_clone = self.clone();
+            tokio::spawn(async move {
+                let Ok(websocket) = tokio_tungstenite::accept_async(stream).await else {
+                    eprintln!("Error aceptando el WebSocket");
+                    return;
+                };
+                println!("Nuevo jugador conectado por WebSocket");
+                let enlace = Enlace::desde_websocket(websocket);
+                if let Err(err) = self_clone.atender_conexion(enlace).await {
+                    eprintln!("Error atendiendo al jugador: {}", err);
+                }
+            });
+        }
     }
-    /// Función que envía una instrucción
+
+    /// Función que registra a un jugador y lo deja en la sala que elija
     ///
-    /// # Args
+    /// Antes del registro se acuerda la versión del protocolo. Un jugador
+    /// nuevo recibe el token de su sesión si su cliente admite la
+    /// reconexión; uno que se reconecta con su token vuelve directamente a
+    /// su sala.
     ///
-    /// `player_id` - ID del jugador
+    /// # Args
     ///
-    /// `instruccion` - Instrucción
+    /// `enlace` - Enlace con el jugador
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Resultado de la función
-    pub fn enviar_instruccion(
-        &self,
-        player_id: usize,
-        instruccion: &str,
-    ) -> Result<(), CustomError> {
-        if let Some(player_connection) = self.conexiones_jugadores.get(&player_id) {
-            let mut connection = player_connection.lock().unwrap();
-            connection
-                .write_all(instruccion.as_bytes())
-                .map_err(|_| CustomError::ErrorEnviandoInstruccion)?;
-            Ok(())
+    /// `Result<(), CustomError>` - Ok cuando el jugador quedó en una sala
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorRecibiendoInstruccion` - Si el jugador envía un mensaje inesperado o se desconecta
+    ///
+    /// `CustomError::ErrorVersionIncompatible` - Si el cliente usa una versión del protocolo que ya no se atiende
+    pub async fn atender_conexion(&self, mut enlace: Enlace) -> Result<(), CustomError> {
+        self.saludar(&mut enlace).await?;
+        let Some((nombre_usuario, enlace)) = self.registrar_usuario(enlace).await? else {
+            println!("Jugador reconectado a su sala");
+            return Ok(());
+        };
+        println!(
+            "Jugador conectado con el nombre de usuario: {}",
+            nombre_usuario
+        );
+        let token = self.lobby.crear_sesion(&nombre_usuario);
+        let envio_token = if enlace.admite(RECONEXION) {
+            enlace.enviar(&Mensaje::Sesion(token))
         } else {
-            Err(CustomError::ErrorJugadorInexistente)
+            Ok(())
+        };
+        let resultado = match envio_token {
+            Ok(()) => self.elegir_sala(enlace, &nombre_usuario).await,
+            Err(err) => Err(err),
+        };
+        if resultado.is_err() {
+            self.lobby.liberar_nombre(&nombre_usuario);
         }
+        resultado
     }
-    /// Función que envía un mensaje
+
+    /// Función que acuerda con el cliente la versión del protocolo, sus capacidades y el codec
     ///
-    /// # Args
+    /// Si el cliente no se presenta con `Hola` se lo atiende con la versión
+    /// mínima y sin capacidades. El protocolo acordado queda fijado en el
+    /// enlace antes de enviar la `Bienvenida`, así la primera respuesta del
+    /// cliente ya se lee con el codec acordado.
     ///
-    /// `stream` - Flujo de datos
+    /// # Args
     ///
-    /// `msg` - Mensaje
+    /// `enlace` - Enlace con el jugador
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Resultado de la función
-    pub fn enviar_mensaje(stream: &mut TcpStream, msg: Vec<u8>) -> Result<(), CustomError> {
-        let result_stream = stream.write_all(&msg);
-        result_stream.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        let result_flush = stream.flush();
-        result_flush.map_err(|_| CustomError::ErrorEnviarMensaje)?;
-        Ok(())
+    /// `Result<(), CustomError>` - Ok si se acordó un protocolo
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorVersionIncompatible` - Si se rechazó la versión del cliente
+    async fn saludar(&self, enlace: &mut Enlace) -> Result<(), CustomError> {
+        let (version, capacidades) = match enlace.recibir_con_plazo(ESPERA_SALUDO).await? {
+            Some(Mensaje::Hola {
+                version,
+                capacidades,
+            }) => (version, capacidades),
+            None => {
+                enlace.acordar_protocolo(Protocolo::sin_saludo());
+                return Ok(());
+            }
+            Some(_) => return Err(CustomError::ErrorRecibiendoInstruccion),
+        };
+        let codec = if enlace.solo_texto() {
+            Codec::Json
+        } else {
+            self.configuracion.codec
+        };
+        let admitidas = Protocolo::con_codec(codec).capacidades;
+        match Protocolo::negociar(version, &capacidades, &admitidas) {
+            Ok(protocolo) => {
+                let bienvenida = Mensaje::Bienvenida {
+                    version: protocolo.version,
+                    capacidades: protocolo.capacidades.clone(),
+                };
+                enlace.acordar_protocolo(protocolo);
+                enlace.enviar(&bienvenida)
+            }
+            Err(err) => {
+                enlace.enviar(&Mensaje::VersionRechazada(format!(
+                    "El servidor atiende las versiones {} a {} del protocolo y el cliente usa la {}. Actualizá el cliente.",
+                    VERSION_MINIMA, VERSION_PROTOCOLO, version
+                )))?;
+                Err(err)
+            }
+        }
     }
-    /// Función que recibe un mensaje
+
+    /// Función que pide al jugador un nombre de usuario que no esté en uso o el token de su sesión
     ///
     /// # Args
     ///
-    /// `id` - ID del jugador
+    /// `enlace` - Enlace con el jugador
     ///
     /// # Returns
     ///
-    /// `Result<String, CustomError>` - Resultado de la función
-    pub fn recibir_mensaje(&mut self, id: usize) -> Result<String, CustomError> {
-        let mut buffer = [0; 2048];
-        let stream = self.conexiones_jugadores.get(&id).unwrap();
-        let bytes_read = stream
-            .lock()
-            .unwrap()
-            .read(&mut buffer)
-            .map_err(|_| CustomError::ErrorRecibiendoInstruccion)?;
-        let message = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
-        Ok(message)
-    }
-    /// Función que espera a los jugadores
-    ///
-    /// `()` - No retorna nada
-    fn esperar_jugadores(&self) {
-        for connection in self.conexiones_jugadores.values() {
-            let mut connection = connection.lock().unwrap();
-            let mensaje_serializado = serde_json::to_string(&Mensaje::Esperando).unwrap();
-            Server::enviar_mensaje(&mut connection, mensaje_serializado.as_bytes().to_vec())
-                .unwrap();
+    /// `Result<Option<(String, Enlace)>, CustomError>` - Nombre reservado para el jugador junto con su enlace, o `None` si se reconectó a su sala
+    async fn registrar_usuario(
+        &self,
+        mut enlace: Enlace,
+    ) -> Result<Option<(String, Enlace)>, CustomError> {
+        enlace.enviar(&Mensaje::Registro)?;
+        loop {
+            match enlace
+                .recibir()
+                .await
+                .map_err(|_| CustomError::ErrorRecibiendoInstruccion)?
+            {
+                Mensaje::NombreUsuario(nombre) => {
+                    let nombre_usuario = nombre.trim().to_string();
+                    if self.lobby.registrar_nombre(&nombre_usuario) {
+                        return Ok(Some((nombre_usuario, enlace)));
+                    }
+                    enlace.enviar(&Mensaje::NombreEnUso)?;
+                }
+                Mensaje::Reconectar(token) => match self.lobby.reconectar(token.trim(), enlace) {
+                    Ok(()) => return Ok(None),
+                    Err((err, devuelto)) => {
+                        enlace = devuelto;
+                        enlace.enviar(&Mensaje::SesionRechazada(err.to_string()))?;
+                    }
+                },
+                _ => return Err(CustomError::ErrorRecibiendoInstruccion),
+            }
         }
-
-        std::thread::sleep(std::time::Duration::from_secs(5));
     }
-    /// Función que pregunta si se quiere comenzar el juego
+
+    /// Función que atiende al jugador en el lobby hasta que se une a una sala
+    ///
+    /// El jugador también puede entrar a una sala como espectador, en cuyo
+    /// caso es administrador si indica la clave configurada en el servidor,
+    /// o pasar a la cola de emparejamiento.
+    ///
+    /// # Args
+    ///
+    /// `enlace` - Enlace con el jugador
+    ///
+    /// `nombre_usuario` - Nombre del jugador
     ///
     /// # Returns
     ///
-    /// `Result<(), CustomError>` - Ok si se puede comenzar el juego o Error si no se puede
-    pub fn preguntar_comienzo_juego(&self) -> Result<(), CustomError> {
-        if self.conexiones_jugadores.len() < 3 {
-            println!("Esperando más jugadores para comenzar el juego...");
-            self.esperar_jugadores();
-            Ok(())
-        } else {
-            let mut respuestas: HashMap<usize, String> = HashMap::new();
-
-            for connection in self.conexiones_jugadores.values() {
-                let mut connection = connection.lock().unwrap();
-                let mensaje_serializado =
-                    serde_json::to_string(&Mensaje::PreguntaComienzo).unwrap();
-                Server::enviar_mensaje(&mut connection, mensaje_serializado.as_bytes().to_vec())
-                    .map_err(|_| CustomError::ErrorEnviarMensaje)?;
-            }
-
-            for (player_id, connection) in &self.conexiones_jugadores {
-                let mut connection = connection.lock().unwrap();
-                let mut buffer = [0; 512];
-                let bytes_read = connection.read(&mut buffer).unwrap();
-                let respuesta = String::from_utf8_lossy(&buffer[..bytes_read])
-                    .trim()
-                    .to_string();
-                respuestas.insert(*player_id, respuesta);
-            }
-            if respuestas.values().all(|respuesta| respuesta == "si") {
-                for connection in self.conexiones_jugadores.values() {
-                    let mut connection = connection.lock().unwrap();
-                    let mensaje_serializado =
-                        serde_json::to_string(&Mensaje::ComenzoJuego).unwrap();
-                    Server::enviar_mensaje(
-                        &mut connection,
-                        mensaje_serializado.as_bytes().to_vec(),
-                    )
-                    .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    /// `Result<(), CustomError>` - Ok cuando el enlace pasó a la sala o a la cola
+    async fn elegir_sala(
+        &self,
+        mut enlace: Enlace,
+        nombre_usuario: &str,
+    ) -> Result<(), CustomError> {
+        'lobby: loop {
+            enlace.enviar(&Mensaje::Lobby)?;
+            let (codigo, contraseña, rol) = loop {
+                match enlace
+                    .recibir()
+                    .await
+                    .map_err(|_| CustomError::ErrorRecibiendoInstruccion)?
+                {
+                    Mensaje::ListarSalas => {
+                        enlace.enviar(&Mensaje::Salas(self.lobby.listar_salas()))?;
+                        continue 'lobby;
+                    }
+                    Mensaje::CrearSala(nombre_sala, contraseña) => {
+                        break (
+                            self.crear_sala(nombre_sala, contraseña.clone()),
+                            contraseña,
+                            Rol::Jugador,
+                        )
+                    }
+                    Mensaje::UnirseSala(codigo, contraseña) => {
+                        break (codigo, contraseña, Rol::Jugador)
+                    }
+                    Mensaje::BuscarPartida => {
+                        let jugador = JugadorEntrante {
+                            nombre: nombre_usuario.to_string(),
+                            enlace,
+                            rol: Rol::Jugador,
+                        };
+                        return self
+                            .emparejamiento
+                            .send(jugador)
+                            .map_err(|_| CustomError::ErrorEnviarMensaje);
+                    }
+                    Mensaje::Observar {
+                        codigo,
+                        contraseña,
+                        clave_administrador,
+                    } => {
+                        let administrador = clave_administrador.is_some()
+                            && clave_administrador == self.configuracion.clave_administrador;
+                        break (codigo, contraseña, Rol::Espectador { administrador });
+                    }
+                    // Fuera de una sala no hay con quién chatear; se avisa sin volver a mostrar el lobby
+                    Mensaje::Chat { .. } | Mensaje::Silenciar(..) => {
+                        if enlace.admite(CHAT) {
+                            enlace.enviar(&Mensaje::ChatRechazado(
+                                CustomError::ErrorChatFueraDeSala.to_string(),
+                            ))?;
+                        }
+                    }
+                    _ => return Err(CustomError::ErrorRecibiendoInstruccion),
                 }
-                println!("Todos los jugadores quieren comenzar el juego.");
-                println!("Comenzando el juego...");
-                let _ = self.comenzar_juego();
-                Ok(())
-            } else {
-                println!("Al menos un jugador no quiere comenzar el juego. Esperando nuevas conexiones...");
-                let _ = self.preguntar_comienzo_juego();
+            };
 
-                Ok(())
+            let jugador = JugadorEntrante {
+                nombre: nombre_usuario.to_string(),
+                enlace,
+                rol,
+            };
+            let resultado = match rol {
+                Rol::Jugador => self.lobby.unirse(&codigo, contraseña, jugador),
+                Rol::Espectador { .. } => self.lobby.observar(&codigo, contraseña, jugador),
+            };
+            match resultado {
+                Ok(()) => return Ok(()),
+                Err((err, jugador)) => {
+                    enlace = jugador.enlace;
+                    enlace.enviar(&Mensaje::SalaRechazada(err.to_string()))?;
+                }
             }
         }
     }
-    /// Función que comienza el juego
-    ///
-    /// # Returns
-    ///
-    /// `Result<(), CustomError>` - Resultado de la función
-    ///
-    /// # Errors
-    ///
-    /// `CustomError::ErrorThreads` - Error en los threads
-    pub fn comenzar_juego(&self) -> Result<(), CustomError> {
-        let mut self_clone = self.clone();
-        let mut self_clone_dos = self.clone();
-        let handle: thread::JoinHandle<()> = thread::spawn(move || {
-            let _ = self_clone
-                .juego
-                .iniciar_juego(&mut self_clone_dos)
-                .map_err(|_| CustomError::ErrorThreads);
-        });
-        self.jugadores.lock().unwrap().push(handle);
-        Ok(())
-    }
-    /// Función que crea un evento sorpresa
+
+    /// Función que abre una sala y lanza la tarea que lleva adelante su partida
     ///
     /// # Args
     ///
-    /// `jugadores` - Vector de jugadores
+    /// `nombre` - Nombre visible de la sala
+    ///
+    /// `contraseña` - Contraseña para unirse, si la sala es privada
     ///
     /// # Returns
     ///
-    /// `()` - No retorna nada
-    pub fn crear_evento_sorpresa(&mut self, jugadores: &mut [Jugador]) -> Result<(), CustomError> {
-        //vector perdedores
-        let mut perdedores: Vec<usize> = vec![];
-        let (tx, rx) = mpsc::channel();
-        let mut handles = vec![];
+    /// `String` - Código de la sala creada
+    fn crear_sala(&self, nombre: String, contraseña: Option<String>) -> String {
+        let (codigo, entrantes) = self.lobby.crear_sala(nombre, contraseña);
+        println!("Sala {} creada", codigo);
+        let sala = Sala::new(
+            codigo.clone(),
+            self.configuracion.clone(),
+            Arc::clone(&self.lobby),
+        );
+        tokio::spawn(async move {
+            if let Err(err) = sala.run(entrantes).await {
+                eprintln!("Error en la sala: {}", err);
+            }
+        });
+        codigo
+    }
+}
 
-        for (player_id, jugador) in &self.conexiones_jugadores {
-            let jugador = Arc::clone(jugador);
-            let tx = tx.clone();
-            let player_id = *player_id;
-            println!(
-                "Enviando mensaje de evento sorpresa al jugador {}",
-                player_id
-            ); // Debugging print
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let handle = thread::spawn(move || {
-                let mut jugador = jugador.lock().unwrap();
-                let mensaje_serializado = serde_json::to_string(&Mensaje::EventoSorpresa).unwrap();
-                if let Err(e) =
-                    Server::enviar_mensaje(&mut jugador, mensaje_serializado.as_bytes().to_vec())
-                {
-                    eprintln!("Error enviando mensaje al jugador {}: {:?}", player_id, e);
-                    return;
-                }
+    async fn server() -> Server {
+        let configuracion = ConfiguracionServidor {
+            puerto: 0,
+            ..Default::default()
+        };
+        Server::new(configuracion).await.unwrap()
+    }
 
-                let mut buffer = [0; 512];
-                match jugador.read(&mut buffer) {
-                    Ok(bytes_read) => {
-                        let respuesta = String::from_utf8_lossy(&buffer[..bytes_read])
-                            .trim()
-                            .to_string();
-                        tx.send((player_id, respuesta)).unwrap();
-                    }
-                    Err(_) => {
-                        eprintln!("Error");
-                    }
-                }
-            });
+    async fn saludar(conexion_cliente: &mut Enlace) {
+        let protocolo = Protocolo::actual();
+        conexion_cliente
+            .enviar(&Mensaje::Hola {
+                version: protocolo.version,
+                capacidades: protocolo.capacidades,
+            })
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Bienvenida {
+                version: VERSION_PROTOCOLO,
+                ..
+            }
+        ));
+    }
 
-            handles.push(handle);
-        }
+    #[tokio::test]
+    async fn test_server_new() {
+        let server = server().await;
+        assert!(server.lobby.listar_salas().is_empty());
+    }
 
-        let mut primero = None;
-        let mut remaining_players = self.conexiones_jugadores.len();
+    #[tokio::test]
+    async fn test_crear_sala_y_unirse_desde_el_lobby() {
+        let server = server().await;
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        let server_clone = server.clone();
+        let handle =
+            tokio::spawn(async move { server_clone.atender_conexion(conexion_servidor).await });
+        saludar(&mut conexion_cliente).await;
 
-        while let Ok((player_id, respuesta)) = rx.recv() {
-            remaining_players -= 1;
-            if respuesta == "primero" && primero.is_none() {
-                primero = Some(player_id);
-                jugadores[player_id].monedas += PREMIO;
-                let mut jugador = self
-                    .conexiones_jugadores
-                    .get(&player_id)
-                    .unwrap()
-                    .lock()
-                    .unwrap();
-                let mensaje_serializado =
-                    serde_json::to_string(&Mensaje::EventoSorpresaResultado(true)).unwrap();
-                Server::enviar_mensaje(&mut jugador, mensaje_serializado.as_bytes().to_vec())
-                    .unwrap();
-            } else {
-                let mut jugador = self
-                    .conexiones_jugadores
-                    .get(&player_id)
-                    .unwrap()
-                    .lock()
-                    .unwrap();
-                perdedores.push(player_id);
-                let mensaje_serializado =
-                    serde_json::to_string(&Mensaje::EventoSorpresaResultado(false)).unwrap();
-                Server::enviar_mensaje(&mut jugador, mensaje_serializado.as_bytes().to_vec())
-                    .unwrap();
-            }
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Registro
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::Reconectar("token".to_string()))
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::SesionRechazada(_)
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::NombreUsuario("ana".to_string()))
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Sesion(_)
+        ));
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Lobby
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::UnirseSala("ZZZZ".to_string(), None))
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::SalaRechazada(_)
+        ));
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Lobby
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::Chat {
+                para: None,
+                texto: "¿hay alguien?".to_string(),
+            })
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::ChatRechazado(_)
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::CrearSala("sala de ana".to_string(), None))
+            .unwrap();
+        let codigo = match conexion_cliente.recibir().await.unwrap() {
+            Mensaje::SalaUnida(codigo) => codigo,
+            otro => panic!("Mensaje inesperado: {:?}", otro),
+        };
+
+        handle.await.unwrap().unwrap();
+        let salas = server.lobby.listar_salas();
+        assert_eq!(salas.len(), 1);
+        assert_eq!(salas[0].codigo, codigo);
+        assert_eq!(salas[0].jugadores, 1);
+        assert!(!server.lobby.registrar_nombre("ana"));
+    }
+
+    #[tokio::test]
+    async fn test_reconectar_vuelve_a_la_sala() {
+        let server = server().await;
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        let server_clone = server.clone();
+        let handle =
+            tokio::spawn(async move { server_clone.atender_conexion(conexion_servidor).await });
+        saludar(&mut conexion_cliente).await;
+        conexion_cliente.recibir().await.unwrap();
+        conexion_cliente
+            .enviar(&Mensaje::NombreUsuario("ana".to_string()))
+            .unwrap();
+        let token = match conexion_cliente.recibir().await.unwrap() {
+            Mensaje::Sesion(token) => token,
+            otro => panic!("Mensaje inesperado: {:?}", otro),
+        };
+        conexion_cliente.recibir().await.unwrap();
+        conexion_cliente
+            .enviar(&Mensaje::CrearSala("sala".to_string(), None))
+            .unwrap();
+        let codigo = match conexion_cliente.recibir().await.unwrap() {
+            Mensaje::SalaUnida(codigo) => codigo,
+            otro => panic!("Mensaje inesperado: {:?}", otro),
+        };
+        handle.await.unwrap().unwrap();
+
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        let server_clone = server.clone();
+        let handle =
+            tokio::spawn(async move { server_clone.atender_conexion(conexion_servidor).await });
+        saludar(&mut conexion_cliente).await;
+        conexion_cliente.recibir().await.unwrap();
+        conexion_cliente
+            .enviar(&Mensaje::Reconectar(token))
+            .unwrap();
+        handle.await.unwrap().unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::SalaUnida(reconectada) if reconectada == codigo
+        ));
+        assert_eq!(server.lobby.listar_salas()[0].jugadores, 1);
+    }
+
+    #[tokio::test]
+    async fn test_version_rechazada() {
+        let server = server().await;
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        conexion_cliente
+            .enviar(&Mensaje::Hola {
+                version: 0,
+                capacidades: Vec::new(),
+            })
+            .unwrap();
+
+        assert!(matches!(
+            server.atender_conexion(conexion_servidor).await,
+            Err(CustomError::ErrorVersionIncompatible)
+        ));
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::VersionRechazada(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cliente_sin_saludo_no_recibe_token() {
+        let server = server().await;
+        let (conexion_servidor, mut conexion_cliente) = Enlace::par();
+        let server_clone = server.clone();
+        tokio::spawn(async move { server_clone.atender_conexion(conexion_servidor).await });
+
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Registro
+        ));
+        conexion_cliente
+            .enviar(&Mensaje::NombreUsuario("ana".to_string()))
+            .unwrap();
+        assert!(matches!(
+            conexion_cliente.recibir().await.unwrap(),
+            Mensaje::Lobby
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cliente_websocket_se_registra_en_el_lobby() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
 
-            if remaining_players == 0 {
-                break;
+        let server = Server::new(ConfiguracionServidor {
+            puerto: 0,
+            puerto_websocket: Some(0),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let direccion = server.direccion_websocket().unwrap();
+        let server_clone = server.clone();
+        tokio::spawn(async move { server_clone.run().await });
+
+        let stream = tokio::net::TcpStream::connect(direccion).await.unwrap();
+        let (mut websocket, _) =
+            tokio_tungstenite::client_async(format!("ws://{}/", direccion), stream)
+                .await
+                .unwrap();
+        let hola = serde_json::to_string(&Mensaje::Hola {
+            version: VERSION_PROTOCOLO,
+            capacidades: Protocolo::actual().capacidades,
+        })
+        .unwrap();
+        websocket.send(Message::Text(hola)).await.unwrap();
+        let mut recibidos = Vec::new();
+        while recibidos.len() < 2 {
+            if let Message::Text(texto) = websocket.next().await.unwrap().unwrap() {
+                recibidos.push(serde_json::from_str::<Mensaje>(&texto).unwrap());
             }
         }
-
-        for handle in handles {
-            if handle.join().is_err() {
-                return Err(CustomError::ErrorThreads);
+        match &recibidos[0] {
+            Mensaje::Bienvenida { capacidades, .. } => {
+                assert!(!capacidades.iter().any(|c| c == crate::protocolo::BINARIO))
             }
+            otro => panic!("Mensaje inesperado: {:?}", otro),
         }
+        assert_eq!(recibidos[1], Mensaje::Registro);
 
-        for jugador in jugadores.iter_mut() {
-            if perdedores.contains(&jugador.id) {
-                let coordenadas = CORDENADAS_BOMBA;
-                jugador.procesar_ataque(coordenadas, self);
+        let nombre = serde_json::to_string(&Mensaje::NombreUsuario("web".to_string())).unwrap();
+        websocket.send(Message::Text(nombre)).await.unwrap();
+        let mut recibidos = Vec::new();
+        while recibidos.len() < 2 {
+            if let Message::Text(texto) = websocket.next().await.unwrap().unwrap() {
+                recibidos.push(serde_json::from_str::<Mensaje>(&texto).unwrap());
             }
         }
-
-        Ok(())
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_server_new() {
-        let server = Server::new().unwrap();
-        assert_eq!(server.jugadores_conectados, 0);
+        assert!(matches!(
+            recibidos.as_slice(),
+            [Mensaje::Sesion(_), Mensaje::Lobby]
+        ));
+        assert!(!server.lobby.registrar_nombre("web"));
     }
 }
@@ -0,0 +1,145 @@
+use barcos::tipo_barco::TipoBarco;
+use serde::{Deserialize, Serialize};
+
+use crate::{arma::TipoArma, reglas::Reglas};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Estructura que representa un barco a la venta en la tienda
+///
+/// Es el artículo que reciben los clientes que no admiten la reparación.
+pub struct ArticuloTienda {
+    pub tipo: TipoBarco,
+    pub precio: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+/// Enumeración que representa lo que se puede comprar en la tienda
+pub enum Producto {
+    Barco(TipoBarco),
+    Reparacion,
+    Arma(TipoArma),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Estructura que representa cualquier producto a la venta en la tienda
+pub struct ArticuloCatalogo {
+    pub producto: Producto,
+    pub precio: usize,
+}
+
+impl From<ArticuloTienda> for ArticuloCatalogo {
+    fn from(articulo: ArticuloTienda) -> Self {
+        ArticuloCatalogo {
+            producto: Producto::Barco(articulo.tipo),
+            precio: articulo.precio,
+        }
+    }
+}
+
+/// Función que obtiene los barcos a la venta en la tienda
+///
+/// # Returns
+///
+/// `Vec<ArticuloTienda>` - Artículos a la venta
+pub fn catalogo() -> Vec<ArticuloTienda> {
+    TipoBarco::TODOS
+        .into_iter()
+        .map(|tipo| ArticuloTienda {
+            tipo,
+            precio: tipo.precio(),
+        })
+        .collect()
+}
+
+/// Función que obtiene un artículo del catálogo
+///
+/// # Args
+///
+/// `tipo` - Clase de barco buscada
+///
+/// # Returns
+///
+/// `Option<ArticuloTienda>` - Artículo encontrado, si la clase está a la venta
+pub fn obtener_articulo(tipo: TipoBarco) -> Option<ArticuloTienda> {
+    catalogo()
+        .into_iter()
+        .find(|articulo| articulo.tipo == tipo)
+}
+
+/// Función que obtiene todos los productos a la venta en la tienda
+///
+/// # Args
+///
+/// `reglas` - Reglas de la partida, de las que se toman los precios
+///
+/// # Returns
+///
+/// `Vec<ArticuloCatalogo>` - Artículos a la venta
+pub fn catalogo_completo(reglas: &Reglas) -> Vec<ArticuloCatalogo> {
+    let mut catalogo: Vec<ArticuloCatalogo> =
+        catalogo().into_iter().map(ArticuloCatalogo::from).collect();
+    catalogo.push(ArticuloCatalogo {
+        producto: Producto::Reparacion,
+        precio: reglas.precio_reparacion,
+    });
+    catalogo.extend(TipoArma::TODAS.into_iter().map(|arma| ArticuloCatalogo {
+        producto: Producto::Arma(arma),
+        precio: reglas.precio_arma(arma),
+    }));
+    catalogo
+}
+
+/// Función que obtiene un producto del catálogo completo
+///
+/// # Args
+///
+/// `producto` - Producto buscado
+///
+/// `reglas` - Reglas de la partida, de las que se toman los precios
+///
+/// # Returns
+///
+/// `Option<ArticuloCatalogo>` - Artículo encontrado, si el producto está a la venta
+pub fn obtener_producto(producto: Producto, reglas: &Reglas) -> Option<ArticuloCatalogo> {
+    catalogo_completo(reglas)
+        .into_iter()
+        .find(|articulo| articulo.producto == producto)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogo() {
+        let catalogo = catalogo();
+        assert_eq!(catalogo.len(), TipoBarco::TODOS.len());
+        assert!(catalogo
+            .iter()
+            .all(|articulo| articulo.precio == articulo.tipo.precio()));
+    }
+
+    #[test]
+    fn test_obtener_articulo() {
+        let acorazado = obtener_articulo(TipoBarco::Acorazado).unwrap();
+        assert_eq!(acorazado.precio, 300);
+    }
+
+    #[test]
+    fn test_catalogo_completo() {
+        let reglas = Reglas {
+            precio_reparacion: 75,
+            ..Default::default()
+        };
+        let catalogo = catalogo_completo(&reglas);
+        assert_eq!(
+            catalogo.len(),
+            TipoBarco::TODOS.len() + 1 + TipoArma::TODAS.len()
+        );
+        let reparacion = obtener_producto(Producto::Reparacion, &reglas).unwrap();
+        assert_eq!(reparacion.precio, 75);
+        assert!(catalogo
+            .iter()
+            .any(|articulo| matches!(articulo.producto, Producto::Reparacion)));
+    }
+}
@@ -0,0 +1,217 @@
+use std::io::{Read, Write};
+
+use libreria::{constantes::TAMANIO_MAXIMO_TRAMA, custom_error::CustomError};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Cantidad de bytes del prefijo de longitud de cada trama
+const TAMANIO_PREFIJO: usize = 4;
+
+/// Función que escribe una trama en el stream
+///
+/// Cada trama se compone de un prefijo de 4 bytes big-endian con la longitud
+/// del contenido, seguido del contenido.
+///
+/// # Args
+///
+/// `escritor` - Stream en el que se escribirá la trama
+///
+/// `datos` - Contenido de la trama
+///
+/// # Returns
+///
+/// `Result<(), CustomError>` - Resultado de la escritura
+///
+/// # Errors
+///
+/// `CustomError::ErrorTamanioTrama` - Si el contenido excede el tamaño máximo
+///
+/// `CustomError::ErrorEnviarMensaje` - Si no se pudo escribir en el stream
+pub fn escribir_trama<W: Write>(escritor: &mut W, datos: &[u8]) -> Result<(), CustomError> {
+    if datos.len() > TAMANIO_MAXIMO_TRAMA {
+        return Err(CustomError::ErrorTamanioTrama);
+    }
+    let longitud = (datos.len() as u32).to_be_bytes();
+    escritor
+        .write_all(&longitud)
+        .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    escritor
+        .write_all(datos)
+        .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    escritor
+        .flush()
+        .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    Ok(())
+}
+
+/// Función que lee una trama completa del stream
+///
+/// Bloquea hasta recibir la trama entera, sin importar en cuántas lecturas
+/// llegue, y deja en el stream los bytes de las tramas siguientes.
+///
+/// # Args
+///
+/// `lector` - Stream del que se leerá la trama
+///
+/// # Returns
+///
+/// `Result<Vec<u8>, CustomError>` - Contenido de la trama
+///
+/// # Errors
+///
+/// `CustomError::ErrorTamanioTrama` - Si la longitud anunciada excede el tamaño máximo
+///
+/// `CustomError::ErrorRecibiendoMensaje` - Si no se pudo leer del stream
+pub fn leer_trama<R: Read>(lector: &mut R) -> Result<Vec<u8>, CustomError> {
+    let mut prefijo = [0; TAMANIO_PREFIJO];
+    lector
+        .read_exact(&mut prefijo)
+        .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+    let longitud = u32::from_be_bytes(prefijo) as usize;
+    if longitud > TAMANIO_MAXIMO_TRAMA {
+        return Err(CustomError::ErrorTamanioTrama);
+    }
+    let mut datos = vec![0; longitud];
+    lector
+        .read_exact(&mut datos)
+        .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+    Ok(datos)
+}
+
+/// Función que escribe una trama en un stream asincrónico
+///
+/// Usa el mismo formato que `escribir_trama`.
+///
+/// # Args
+///
+/// `escritor` - Stream en el que se escribirá la trama
+///
+/// `datos` - Contenido de la trama
+///
+/// # Returns
+///
+/// `Result<(), CustomError>` - Resultado de la escritura
+///
+/// # Errors
+///
+/// `CustomError::ErrorTamanioTrama` - Si el contenido excede el tamaño máximo
+///
+/// `CustomError::ErrorEnviarMensaje` - Si no se pudo escribir en el stream
+pub async fn escribir_trama_async<W: AsyncWrite + Unpin>(
+    escritor: &mut W,
+    datos: &[u8],
+) -> Result<(), CustomError> {
+    if datos.len() > TAMANIO_MAXIMO_TRAMA {
+        return Err(CustomError::ErrorTamanioTrama);
+    }
+    let longitud = (datos.len() as u32).to_be_bytes();
+    escritor
+        .write_all(&longitud)
+        .await
+        .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    escritor
+        .write_all(datos)
+        .await
+        .map_err(|_| CustomError::ErrorEnviarMensaje)?;
+    escritor
+        .flush()
+        .await
+        .map_err(|_| CustomError::ErrorEnviarMensaje)
+}
+
+/// Función que lee una trama completa de un stream asincrónico
+///
+/// Usa el mismo formato que `leer_trama`.
+///
+/// # Args
+///
+/// `lector` - Stream del que se leerá la trama
+///
+/// # Returns
+///
+/// `Result<Vec<u8>, CustomError>` - Contenido de la trama
+///
+/// # Errors
+///
+/// `CustomError::ErrorTamanioTrama` - Si la longitud anunciada excede el tamaño máximo
+///
+/// `CustomError::ErrorRecibiendoMensaje` - Si no se pudo leer del stream
+pub async fn leer_trama_async<R: AsyncRead + Unpin>(
+    lector: &mut R,
+) -> Result<Vec<u8>, CustomError> {
+    let mut prefijo = [0; TAMANIO_PREFIJO];
+    lector
+        .read_exact(&mut prefijo)
+        .await
+        .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+    let longitud = u32::from_be_bytes(prefijo) as usize;
+    if longitud > TAMANIO_MAXIMO_TRAMA {
+        return Err(CustomError::ErrorTamanioTrama);
+    }
+    let mut datos = vec![0; longitud];
+    lector
+        .read_exact(&mut datos)
+        .await
+        .map_err(|_| CustomError::ErrorRecibiendoMensaje)?;
+    Ok(datos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_tramas_consecutivas() {
+        let mut buffer = Vec::new();
+        escribir_trama(&mut buffer, b"primera").unwrap();
+        escribir_trama(&mut buffer, b"segunda").unwrap();
+
+        let mut lector = Cursor::new(buffer);
+        assert_eq!(leer_trama(&mut lector).unwrap(), b"primera");
+        assert_eq!(leer_trama(&mut lector).unwrap(), b"segunda");
+        assert!(leer_trama(&mut lector).is_err());
+    }
+
+    #[test]
+    fn test_trama_incompleta() {
+        let mut buffer = Vec::new();
+        escribir_trama(&mut buffer, b"contenido").unwrap();
+        buffer.truncate(buffer.len() - 1);
+
+        let mut lector = Cursor::new(buffer);
+        assert!(matches!(
+            leer_trama(&mut lector),
+            Err(CustomError::ErrorRecibiendoMensaje)
+        ));
+    }
+
+    #[test]
+    fn test_trama_excede_tamanio_maximo() {
+        let datos = vec![0; TAMANIO_MAXIMO_TRAMA + 1];
+        assert!(matches!(
+            escribir_trama(&mut Vec::new(), &datos),
+            Err(CustomError::ErrorTamanioTrama)
+        ));
+
+        let prefijo = ((TAMANIO_MAXIMO_TRAMA + 1) as u32).to_be_bytes();
+        let mut lector = Cursor::new(prefijo.to_vec());
+        assert!(matches!(
+            leer_trama(&mut lector),
+            Err(CustomError::ErrorTamanioTrama)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tramas_asincronicas_compatibles() {
+        let mut buffer = Vec::new();
+        escribir_trama(&mut buffer, b"sincronica").unwrap();
+        escribir_trama_async(&mut buffer, b"asincronica")
+            .await
+            .unwrap();
+
+        let mut lector = buffer.as_slice();
+        assert_eq!(leer_trama_async(&mut lector).await.unwrap(), b"sincronica");
+        assert_eq!(leer_trama(&mut lector).unwrap(), b"asincronica");
+        assert!(leer_trama_async(&mut lector).await.is_err());
+    }
+}
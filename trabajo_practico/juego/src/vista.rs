@@ -0,0 +1,199 @@
+use libreria::custom_error::CustomError;
+use serde::{Deserialize, Serialize};
+
+/// Barcos de un jugador, como pares `(id, posiciones)`
+pub type BarcosSerializados = Vec<(usize, Vec<(i32, i32)>)>;
+
+/// Cambio que se aplica sobre la vista del tablero de un jugador
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CambioTablero {
+    /// Nuevo contenido de la celda en `(fila, columna)`
+    Celda(usize, usize, char),
+    /// Nuevas posiciones de un barco propio; si no existía, se agrega
+    BarcoMovido(usize, Vec<(i32, i32)>),
+    /// Barco propio que ya no está en el tablero
+    BarcoHundido(usize),
+}
+
+/// Estructura con lo que un jugador sabe de su tablero
+///
+/// El servidor guarda la última vista que le envió a cada jugador para
+/// mandarle solo las diferencias, y el cliente mantiene la suya aplicando
+/// esos cambios en orden.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VistaTablero {
+    pub secuencia: u64,
+    pub tablero: Vec<Vec<char>>,
+    pub barcos: BarcosSerializados,
+}
+
+impl VistaTablero {
+    /// Función que crea una vista a partir de una instantánea completa
+    ///
+    /// # Args
+    ///
+    /// `secuencia` - Número de secuencia de la instantánea
+    ///
+    /// `tablero` - Tablero visible para el jugador
+    ///
+    /// `barcos` - Barcos del jugador
+    ///
+    /// # Returns
+    ///
+    /// `VistaTablero` - Vista creada
+    pub fn new(secuencia: u64, tablero: Vec<Vec<char>>, barcos: BarcosSerializados) -> Self {
+        VistaTablero {
+            secuencia,
+            tablero,
+            barcos,
+        }
+    }
+
+    /// Función que calcula los cambios necesarios para pasar de esta vista a la indicada
+    ///
+    /// # Args
+    ///
+    /// `tablero` - Tablero visible actual
+    ///
+    /// `barcos` - Barcos actuales del jugador
+    ///
+    /// # Returns
+    ///
+    /// `Option<Vec<CambioTablero>>` - Cambios, o `None` si el tamaño del tablero no coincide
+    pub fn diferencias(
+        &self,
+        tablero: &[Vec<char>],
+        barcos: &BarcosSerializados,
+    ) -> Option<Vec<CambioTablero>> {
+        if self.tablero.len() != tablero.len()
+            || self
+                .tablero
+                .iter()
+                .zip(tablero)
+                .any(|(anterior, actual)| anterior.len() != actual.len())
+        {
+            return None;
+        }
+        let mut cambios = Vec::new();
+        for (fila, (anterior, actual)) in self.tablero.iter().zip(tablero).enumerate() {
+            for (columna, (celda_anterior, celda)) in anterior.iter().zip(actual).enumerate() {
+                if celda_anterior != celda {
+                    cambios.push(CambioTablero::Celda(fila, columna, *celda));
+                }
+            }
+        }
+        for (id, _) in &self.barcos {
+            if !barcos.iter().any(|(actual, _)| actual == id) {
+                cambios.push(CambioTablero::BarcoHundido(*id));
+            }
+        }
+        for (id, posiciones) in barcos {
+            if !self.barcos.contains(&(*id, posiciones.clone())) {
+                cambios.push(CambioTablero::BarcoMovido(*id, posiciones.clone()));
+            }
+        }
+        Some(cambios)
+    }
+
+    /// Función que aplica los cambios de una actualización
+    ///
+    /// # Args
+    ///
+    /// `secuencia` - Número de secuencia de la actualización
+    ///
+    /// `cambios` - Cambios a aplicar
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), CustomError>` - Ok si la actualización era la siguiente esperada
+    ///
+    /// # Errors
+    ///
+    /// `CustomError::ErrorSecuenciaTablero` - Si se perdió alguna actualización o un cambio no entra en el tablero; la vista no se modifica
+    pub fn aplicar(
+        &mut self,
+        secuencia: u64,
+        cambios: &[CambioTablero],
+    ) -> Result<(), CustomError> {
+        let fuera_del_tablero = cambios.iter().any(|cambio| match cambio {
+            CambioTablero::Celda(fila, columna, _) => self
+                .tablero
+                .get(*fila)
+                .and_then(|celdas| celdas.get(*columna))
+                .is_none(),
+            _ => false,
+        });
+        if secuencia != self.secuencia + 1 || fuera_del_tablero {
+            return Err(CustomError::ErrorSecuenciaTablero);
+        }
+        for cambio in cambios {
+            match cambio {
+                CambioTablero::Celda(fila, columna, celda) => {
+                    self.tablero[*fila][*columna] = *celda;
+                }
+                CambioTablero::BarcoMovido(id, posiciones) => {
+                    match self.barcos.iter_mut().find(|(actual, _)| actual == id) {
+                        Some((_, anteriores)) => *anteriores = posiciones.clone(),
+                        None => self.barcos.push((*id, posiciones.clone())),
+                    }
+                }
+                CambioTablero::BarcoHundido(id) => self.barcos.retain(|(actual, _)| actual != id),
+            }
+        }
+        self.secuencia = secuencia;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vista() -> VistaTablero {
+        VistaTablero::new(
+            4,
+            vec![vec!['.', '0'], vec!['1', '.']],
+            vec![(0, vec![(0, 1)]), (1, vec![(1, 1)])],
+        )
+    }
+
+    #[test]
+    fn test_diferencias_y_aplicar_reconstruyen_el_estado() {
+        let mut cliente = vista();
+        let tablero = vec![vec!['X', '.'], vec!['1', '0']];
+        let barcos = vec![(0, vec![(1, 1)]), (2, vec![(0, 0)])];
+
+        let cambios = cliente.diferencias(&tablero, &barcos).unwrap();
+        assert!(cambios.contains(&CambioTablero::Celda(0, 0, 'X')));
+        assert!(cambios.contains(&CambioTablero::BarcoHundido(1)));
+        assert!(cambios.contains(&CambioTablero::BarcoMovido(2, vec![(0, 0)])));
+        assert!(!cambios.contains(&CambioTablero::Celda(1, 0, '1')));
+
+        cliente.aplicar(5, &cambios).unwrap();
+        assert_eq!(cliente, VistaTablero::new(5, tablero, barcos));
+    }
+
+    #[test]
+    fn test_aplicar_detecta_saltos_de_secuencia() {
+        let mut cliente = vista();
+        assert!(matches!(
+            cliente.aplicar(6, &[CambioTablero::BarcoHundido(0)]),
+            Err(CustomError::ErrorSecuenciaTablero)
+        ));
+        assert!(cliente
+            .aplicar(5, &[CambioTablero::Celda(9, 0, 'X')])
+            .is_err());
+        assert_eq!(cliente, vista());
+    }
+
+    #[test]
+    fn test_diferencias_con_otro_tamanio() {
+        assert!(vista().diferencias(&[vec!['.']], &Vec::new()).is_none());
+        assert_eq!(
+            vista()
+                .diferencias(&vista().tablero, &vista().barcos)
+                .unwrap(),
+            Vec::new()
+        );
+    }
+}
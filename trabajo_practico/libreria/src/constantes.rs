@@ -1,8 +1,25 @@
 pub const ATAQ: &str = "atacar";
 pub const MOV: &str = "mover";
+pub const GIRO: &str = "girar";
+pub const REP: &str = "reparar";
+pub const TORP: &str = "lanzar el torpedo";
 pub const EVENTO_SORPRESA: usize = 2;
 pub const PREMIO: usize = 50;
+pub const MONEDAS_INICIALES: usize = 500;
+pub const PUNTOS_HUNDIMIENTO: usize = 15;
+pub const MONEDAS_HUNDIMIENTO: usize = 100;
+pub const PUNTOS_IMPACTO: usize = 5;
+pub const MONEDAS_IMPACTO: usize = 50;
+pub const FILAS_MAPA: usize = 10;
+pub const COLUMNAS_MAPA: usize = 10;
 pub const CORDENADAS_BOMBA: (i32, i32) = (5, 5);
-pub const ACORAZADO: usize = 3;
-pub const BUQUE: usize = 2;
-pub const FRAGATA: usize = 1;
+pub const PRECIO_REPARACION: usize = 60;
+pub const PRECIO_BOMBA: usize = 250;
+pub const PRECIO_TORPEDO: usize = 150;
+pub const PRECIO_SONAR: usize = 80;
+pub const RADIO_SONAR: i32 = 2;
+pub const VELOCIDAD_FRAGATA: usize = 3;
+pub const VELOCIDAD_BUQUE: usize = 2;
+pub const VELOCIDAD_ACORAZADO: usize = 1;
+pub const TAMANIO_MAXIMO_TRAMA: usize = 64 * 1024;
+pub const LONGITUD_MAXIMA_CHAT: usize = 200;
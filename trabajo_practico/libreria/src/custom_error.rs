@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::constantes::LONGITUD_MAXIMA_CHAT;
+
 #[derive(Debug)]
 pub enum CustomError {
     Err,
@@ -20,6 +22,22 @@ pub enum CustomError {
     ErrorThreads,
     ErrorMostrandoRanking,
     ErrorCompraBarco,
+    ErrorTamanioTrama,
+    ErrorConfiguracion,
+    ErrorReglas,
+    ErrorSalaInexistente,
+    ErrorSalaEnCurso,
+    ErrorSalaLlena,
+    ErrorContraseniaIncorrecta,
+    ErrorSesionInvalida,
+    ErrorVersionIncompatible,
+    ErrorSecuenciaTablero,
+    ErrorChatInvalido,
+    ErrorDestinatarioInexistente,
+    ErrorChatFueraDeSala,
+    ErrorNoEsAnfitrion,
+    ErrorFaltanJugadores,
+    ErrorUbicacionInvalida,
 }
 
 impl fmt::Display for CustomError {
@@ -45,6 +63,45 @@ impl fmt::Display for CustomError {
             CustomError::ErrorThreads => write!(f, "Error en los threads"),
             CustomError::ErrorMostrandoRanking => write!(f, "Error mostrando ranking"),
             CustomError::ErrorCompraBarco => write!(f, "Error en la compra del barco"),
+            CustomError::ErrorTamanioTrama => {
+                write!(f, "Error: la trama excede el tamaño máximo permitido")
+            }
+            CustomError::ErrorConfiguracion => write!(f, "Error: configuración invalida"),
+            CustomError::ErrorReglas => write!(f, "Error: reglas invalidas"),
+            CustomError::ErrorSalaInexistente => write!(f, "No existe una sala con ese código"),
+            CustomError::ErrorSalaEnCurso => write!(f, "La partida de esa sala ya comenzó"),
+            CustomError::ErrorSalaLlena => write!(f, "La sala está llena"),
+            CustomError::ErrorContraseniaIncorrecta => {
+                write!(f, "La contraseña de la sala es incorrecta")
+            }
+            CustomError::ErrorSesionInvalida => {
+                write!(f, "La sesión no existe o su partida ya terminó")
+            }
+            CustomError::ErrorVersionIncompatible => {
+                write!(f, "Error: versión del protocolo incompatible")
+            }
+            CustomError::ErrorSecuenciaTablero => {
+                write!(f, "Error: se perdieron actualizaciones del tablero")
+            }
+            CustomError::ErrorChatInvalido => write!(
+                f,
+                "El mensaje de chat debe tener entre 1 y {} caracteres",
+                LONGITUD_MAXIMA_CHAT
+            ),
+            CustomError::ErrorDestinatarioInexistente => {
+                write!(f, "No hay un jugador conectado con ese nombre en la sala")
+            }
+            CustomError::ErrorChatFueraDeSala => write!(f, "Unite a una sala para chatear"),
+            CustomError::ErrorNoEsAnfitrion => {
+                write!(f, "Solo el anfitrión de la sala puede comenzar la partida")
+            }
+            CustomError::ErrorFaltanJugadores => {
+                write!(f, "Todavía no hay jugadores suficientes para comenzar")
+            }
+            CustomError::ErrorUbicacionInvalida => write!(
+                f,
+                "Cada barco tiene que quedar dentro del tablero y sin superponerse con otro"
+            ),
         }
     }
 }